@@ -0,0 +1,328 @@
+//! The boot ABI between the bundler/bootloader and the kernel.
+//!
+//! Everything here is `#[repr(C)]` with explicit sizes: the structures are
+//! written by the packer on the host and read by the kernel on target, so
+//! layout must be stable.
+
+#![no_std]
+
+/// Current version of the [`BootInfo`] ABI. Bumped whenever the layout or
+/// meaning of a field changes; the kernel accepts this version and older
+/// ones, and rejects anything newer.
+///
+/// Version history: 2 appended [`BootInfo::console`].
+pub const BOOT_INFO_VERSION: u32 = 2;
+
+/// Maximum number of memory map entries in [`BootInfo`].
+pub const MMAP_MAX: usize = 8;
+
+/// Maximum length of the boot command line in bytes.
+pub const CMDLINE_MAX: usize = 128;
+
+/// One region of usable memory.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemMapEntry {
+    pub start: u64,
+    pub len: u64,
+}
+
+impl MemMapEntry {
+    pub const fn empty() -> Self {
+        Self { start: 0, len: 0 }
+    }
+}
+
+/// Where the init application lives inside the packed image.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InitDescriptor {
+    /// Offset of the init binary from the start of the image.
+    pub offset: u32,
+    /// Size of the init binary in bytes.
+    pub size: u32,
+    /// Entry point offset within the init binary.
+    pub entry: u32,
+}
+
+impl InitDescriptor {
+    pub const fn empty() -> Self {
+        Self {
+            offset: 0,
+            size: 0,
+            entry: 0,
+        }
+    }
+}
+
+/// Arguments handed from the bundler to the kernel.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Args {
+    pub init: InitDescriptor,
+    /// Boot command line (e.g. `console=lpuart1 loglevel=debug`), not
+    /// NUL-terminated; `cmdline_len` holds the valid length.
+    pub cmdline: [u8; CMDLINE_MAX],
+    pub cmdline_len: u32,
+}
+
+impl Args {
+    pub const fn empty() -> Self {
+        Self {
+            init: InitDescriptor::empty(),
+            cmdline: [0; CMDLINE_MAX],
+            cmdline_len: 0,
+        }
+    }
+
+    /// Stores the command line, rejecting input longer than [`CMDLINE_MAX`].
+    pub fn set_cmdline(&mut self, raw: &[u8]) -> Result<(), CmdlineTooLong> {
+        if raw.len() > CMDLINE_MAX {
+            return Err(CmdlineTooLong { len: raw.len() });
+        }
+        self.cmdline[..raw.len()].copy_from_slice(raw);
+        self.cmdline_len = raw.len() as u32;
+        Ok(())
+    }
+
+    /// The valid part of the command line.
+    pub fn cmdline(&self) -> &[u8] {
+        let len = (self.cmdline_len as usize).min(CMDLINE_MAX);
+        &self.cmdline[..len]
+    }
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// Per-instance environment the kernel writes into an init task's data
+/// segment (directly after `.bss`) before the task first runs, so the app
+/// can read its surroundings without syscalls.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct InitArgs {
+    /// Task id the kernel assigned to this instance.
+    pub task_id: u32,
+    /// Bytes of heap this instance may allocate.
+    pub heap_budget: u32,
+    /// Command line, same convention as [`Args::cmdline`].
+    pub cmdline: [u8; CMDLINE_MAX],
+    pub cmdline_len: u32,
+}
+
+impl InitArgs {
+    pub const fn empty() -> Self {
+        Self {
+            task_id: 0,
+            heap_budget: 0,
+            cmdline: [0; CMDLINE_MAX],
+            cmdline_len: 0,
+        }
+    }
+
+    /// Stores the command line, rejecting input longer than [`CMDLINE_MAX`].
+    pub fn set_cmdline(&mut self, raw: &[u8]) -> Result<(), CmdlineTooLong> {
+        if raw.len() > CMDLINE_MAX {
+            return Err(CmdlineTooLong { len: raw.len() });
+        }
+        self.cmdline[..raw.len()].copy_from_slice(raw);
+        self.cmdline_len = raw.len() as u32;
+        Ok(())
+    }
+
+    /// The valid part of the command line.
+    pub fn cmdline(&self) -> &[u8] {
+        let len = (self.cmdline_len as usize).min(CMDLINE_MAX);
+        &self.cmdline[..len]
+    }
+}
+
+impl Default for InitArgs {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// Error for a command line exceeding [`CMDLINE_MAX`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CmdlineTooLong {
+    pub len: usize,
+}
+
+/// What kind of device the boot console is. Carried as a raw `u32` in
+/// [`ConsoleInfo`] so an unknown kind from a newer producer degrades to "no
+/// console" instead of undefined behaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleKind {
+    /// A (LP)UART at [`ConsoleInfo::base_addr`].
+    Uart,
+    /// A memory-mapped framebuffer at [`ConsoleInfo::base_addr`].
+    Framebuffer,
+}
+
+impl ConsoleKind {
+    /// Decodes the raw kind word; `None` for "no console" (0) and for kinds
+    /// this reader does not know.
+    pub fn from_u32(raw: u32) -> Option<Self> {
+        match raw {
+            1 => Some(Self::Uart),
+            2 => Some(Self::Framebuffer),
+            _ => None,
+        }
+    }
+
+    /// The raw kind word written into [`ConsoleInfo::kind`].
+    pub const fn to_u32(self) -> u32 {
+        match self {
+            Self::Uart => 1,
+            Self::Framebuffer => 2,
+        }
+    }
+}
+
+/// Where the boot console lives, so the kernel's print path selects the
+/// device from boot data instead of hardcoding a UART.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleInfo {
+    /// Device base address.
+    pub base_addr: u64,
+    /// Raw device kind; decode via [`ConsoleKind::from_u32`]. 0 means no
+    /// console.
+    pub kind: u32,
+    /// Baud rate for UART consoles; 0 where not applicable.
+    pub baud: u32,
+}
+
+impl ConsoleInfo {
+    /// No console.
+    pub const fn empty() -> Self {
+        Self {
+            base_addr: 0,
+            kind: 0,
+            baud: 0,
+        }
+    }
+
+    pub const fn new(kind: ConsoleKind, base_addr: u64, baud: u32) -> Self {
+        Self {
+            base_addr,
+            kind: kind.to_u32(),
+            baud,
+        }
+    }
+
+    /// The decoded device kind; `None` when no (known) console is described.
+    pub fn kind(&self) -> Option<ConsoleKind> {
+        ConsoleKind::from_u32(self.kind)
+    }
+}
+
+/// Everything the bootloader tells the kernel about the machine and image.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BootInfo {
+    /// ABI version of this structure.
+    pub version: u32,
+    pub mmap: [MemMapEntry; MMAP_MAX],
+    pub mmap_len: u32,
+    pub args: Args,
+    /// The boot console device, since version 2. Read through
+    /// [`Self::console`], which accounts for older producers.
+    pub console: ConsoleInfo,
+}
+
+impl BootInfo {
+    pub const fn empty() -> Self {
+        Self {
+            version: BOOT_INFO_VERSION,
+            mmap: [MemMapEntry::empty(); MMAP_MAX],
+            mmap_len: 0,
+            args: Args::empty(),
+            console: ConsoleInfo::empty(),
+        }
+    }
+
+    /// The console described by the bootloader, if any. A producer older
+    /// than version 2 never wrote the field, so its contents are ignored.
+    pub fn console(&self) -> Option<ConsoleInfo> {
+        if self.version < 2 || self.console.kind().is_none() {
+            return None;
+        }
+        Some(self.console)
+    }
+
+    /// Checks that this structure was written by a bootloader this kernel
+    /// understands. Older versions are accepted (fields only ever get
+    /// appended); a strictly newer version means the producer may have laid
+    /// out fields this reader misinterprets, so it is rejected.
+    pub fn validate(&self) -> Result<(), BootInfoError> {
+        if self.version > BOOT_INFO_VERSION {
+            return Err(BootInfoError::UnsupportedVersion {
+                found: self.version,
+                max_supported: BOOT_INFO_VERSION,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Errors from [`BootInfo::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootInfoError {
+    /// The bootloader is newer than this kernel.
+    UnsupportedVersion { found: u32, max_supported: u32 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_and_older_versions_validate() {
+        let mut info = BootInfo::empty();
+        assert_eq!(info.version, BOOT_INFO_VERSION);
+        assert_eq!(info.validate(), Ok(()));
+
+        info.version = BOOT_INFO_VERSION - 1;
+        assert_eq!(info.validate(), Ok(()));
+    }
+
+    #[test]
+    fn console_info_respects_version_and_kind() {
+        let mut info = BootInfo::empty();
+        assert_eq!(info.console(), None);
+
+        info.console = ConsoleInfo::new(ConsoleKind::Uart, 0x4000_8000, 115_200);
+        let console = info.console().expect("console");
+        assert_eq!(console.kind(), Some(ConsoleKind::Uart));
+        assert_eq!(console.base_addr, 0x4000_8000);
+        assert_eq!(console.baud, 115_200);
+
+        // An older producer never wrote the field: whatever the memory
+        // holds, it must not be read.
+        info.version = 1;
+        assert_eq!(info.console(), None);
+
+        // A kind from a newer producer degrades to "no console".
+        info.version = BOOT_INFO_VERSION;
+        info.console.kind = 99;
+        assert_eq!(info.console(), None);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let mut info = BootInfo::empty();
+        info.version = BOOT_INFO_VERSION + 1;
+        assert_eq!(
+            info.validate(),
+            Err(BootInfoError::UnsupportedVersion {
+                found: BOOT_INFO_VERSION + 1,
+                max_supported: BOOT_INFO_VERSION,
+            })
+        );
+    }
+}
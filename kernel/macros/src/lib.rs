@@ -0,0 +1,188 @@
+//! Procedural macros for kernel service declarations.
+//!
+//! Deliberately dependency-free: the attribute grammar is tiny, so the
+//! arguments are parsed from their token text instead of pulling `syn`/
+//! `quote` into every kernel build.
+
+use proc_macro::TokenStream;
+
+/// Marks a function as a service entry point.
+///
+/// `#[service]` currently records scheduling hints next to the function:
+///
+/// * `cpu = N` pins the service to CPU `N` (0-based). Without it the service
+///   may run on any CPU.
+/// * `mem_size = N` / `stack_size = N` size the service's memory and stack
+///   in bytes. Both must be non-zero — a zero size builds a task that only
+///   fails obscurely at spawn — and the stack must be a multiple of 8 (ARM
+///   AAPCS stack alignment). Violations are compile errors.
+///
+/// The attribute emits `<NAME>_CPU_AFFINITY` (and, when given,
+/// `<NAME>_MEM_SIZE` / `<NAME>_STACK_SIZE`) constants alongside the
+/// unchanged function, which task setup feeds into the scheduler. On today's
+/// single-core target the affinity mask is stored and validated but not
+/// acted on.
+#[proc_macro_attribute]
+pub fn service(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match parse_service_args(&attr.to_string()) {
+        Ok(args) => args,
+        Err(msg) => return compile_error(&msg),
+    };
+    let item_text = item.to_string();
+    let Some(name) = fn_name(&item_text) else {
+        return compile_error("#[service] must be applied to a function");
+    };
+
+    let mask = match args.cpu {
+        // Affinity is a bitmask over the (up to 8) CPUs of the target family.
+        Some(cpu) if cpu < 8 => 1u8 << cpu,
+        Some(cpu) => return compile_error(&format!("cpu index {cpu} out of range (0..8)")),
+        None => u8::MAX,
+    };
+
+    let upper = name.to_uppercase();
+    let mut generated = format!("#[allow(dead_code)]\npub const {upper}_CPU_AFFINITY: u8 = {mask:#010b};\n");
+    if let Some(mem_size) = args.mem_size {
+        generated.push_str(&format!(
+            "#[allow(dead_code)]\npub const {upper}_MEM_SIZE: usize = {mem_size};\n"
+        ));
+    }
+    if let Some(stack_size) = args.stack_size {
+        generated.push_str(&format!(
+            "#[allow(dead_code)]\npub const {upper}_STACK_SIZE: usize = {stack_size};\n"
+        ));
+    }
+    generated.push_str(&item_text);
+    generated.parse().expect("generated code must tokenize")
+}
+
+/// The parsed `#[service(...)]` arguments.
+#[derive(Debug)]
+struct ServiceArgs {
+    cpu: Option<u8>,
+    mem_size: Option<u64>,
+    stack_size: Option<u64>,
+}
+
+/// Parses the attribute argument list, e.g. `cpu = 1, stack_size = 1024`.
+/// Empty input is valid and means no hints. Size arguments are validated
+/// here so misuse fails the build with a pointed message instead of an
+/// obscure spawn failure at runtime.
+fn parse_service_args(attr: &str) -> Result<ServiceArgs, String> {
+    let mut args = ServiceArgs {
+        cpu: None,
+        mem_size: None,
+        stack_size: None,
+    };
+    for part in attr.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = part.split_once('=') else {
+            return Err(format!("expected `key = value`, got `{part}`"));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "cpu" => {
+                let cpu = value
+                    .parse::<u8>()
+                    .map_err(|_| format!("`cpu` expects a small integer, got `{value}`"))?;
+                if args.cpu.replace(cpu).is_some() {
+                    return Err("`cpu` given twice".to_string());
+                }
+            }
+            "mem_size" | "stack_size" => {
+                let size = parse_size(key, value)?;
+                let slot = if key == "mem_size" {
+                    &mut args.mem_size
+                } else {
+                    &mut args.stack_size
+                };
+                if slot.replace(size).is_some() {
+                    return Err(format!("`{key}` given twice"));
+                }
+            }
+            other => return Err(format!("unknown #[service] argument `{other}`")),
+        }
+    }
+    if let Some(stack_size) = args.stack_size {
+        if !stack_size.is_multiple_of(8) {
+            return Err(format!(
+                "`stack_size` must be a multiple of 8 (ARM stack alignment), got {stack_size}"
+            ));
+        }
+    }
+    Ok(args)
+}
+
+/// Parses a size argument (integer literal, `_` separators allowed),
+/// rejecting zero: a service with no memory or stack cannot run.
+fn parse_size(key: &str, value: &str) -> Result<u64, String> {
+    let size = value
+        .replace('_', "")
+        .parse::<u64>()
+        .map_err(|_| format!("`{key}` expects a byte count, got `{value}`"))?;
+    if size == 0 {
+        return Err(format!("`{key}` must not be zero"));
+    }
+    Ok(size)
+}
+
+/// Extracts the name of the annotated function from its token text.
+fn fn_name(item: &str) -> Option<&str> {
+    let after_fn = item.split("fn ").nth(1)?;
+    let end = after_fn.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    let name = &after_fn[..end];
+    (!name.is_empty()).then_some(name)
+}
+
+/// Renders `msg` as a `compile_error!` so attribute misuse fails the build
+/// with a readable message.
+fn compile_error(msg: &str) -> TokenStream {
+    format!("compile_error!({msg:?});")
+        .parse()
+        .expect("error message must tokenize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_argument_is_parsed_and_validated() {
+        assert_eq!(parse_service_args("").unwrap().cpu, None);
+        assert_eq!(parse_service_args("cpu = 1").unwrap().cpu, Some(1));
+        assert_eq!(parse_service_args("cpu=0").unwrap().cpu, Some(0));
+
+        assert!(parse_service_args("cpu = lots").is_err());
+        assert!(parse_service_args("cpu = 1, cpu = 2").is_err());
+        assert!(parse_service_args("core = 1").is_err());
+    }
+
+    #[test]
+    fn size_arguments_are_parsed_and_validated() {
+        let args = parse_service_args("stack_size = 1024, mem_size = 4096").unwrap();
+        assert_eq!(args.mem_size, Some(4096));
+        assert_eq!(args.stack_size, Some(1024));
+        assert_eq!(parse_service_args("mem_size = 64_000").unwrap().mem_size, Some(64_000));
+
+        // Zero sizes and misaligned stacks must fail the build, not spawn.
+        let err = parse_service_args("mem_size = 0").unwrap_err();
+        assert!(err.contains("`mem_size` must not be zero"));
+        let err = parse_service_args("stack_size = 0").unwrap_err();
+        assert!(err.contains("`stack_size` must not be zero"));
+        let err = parse_service_args("stack_size = 1027").unwrap_err();
+        assert!(err.contains("multiple of 8"));
+        assert!(err.contains("1027"));
+
+        assert!(parse_service_args("stack_size = lots").is_err());
+        assert!(parse_service_args("stack_size = 8, stack_size = 16").is_err());
+    }
+
+    #[test]
+    fn fn_name_is_found_in_the_item_text() {
+        assert_eq!(fn_name("pub fn telemetry_main() -> ! { loop {} }"), Some("telemetry_main"));
+        assert_eq!(fn_name("struct NotAFn;"), None);
+    }
+}
@@ -0,0 +1,91 @@
+//! Kernel-side fault hooks for compiler-assisted stack protection.
+//!
+//! When the kernel is built with the toolchain's stack protector
+//! (`-Z stack-protector=strong`), function prologues push a canary taken
+//! from the `__stack_chk_guard` symbol and epilogues verify it, calling
+//! `__stack_chk_fail` on a mismatch. Both live here: the guard is seeded
+//! from the HAL entropy source at init (falling back to a fixed pattern on
+//! machines without one), and the fail handler feeds the kernel panic path
+//! with a distinct message — a smashed kernel stack is not a recoverable
+//! task fault.
+
+use hal::{Capabilities, Machine, Machinelike};
+
+/// Guard value the compiler's prologue/epilogue canary checks compare
+/// against. Written once during init, before any protected frame returns.
+#[no_mangle]
+pub static mut __stack_chk_guard: usize = FALLBACK_GUARD;
+
+/// Used when the machine has no entropy source. A terminator-style value:
+/// the embedded zero and newline also stop string operations that overflow
+/// into the canary.
+const FALLBACK_GUARD: usize = 0x00_0A_FF_0D;
+
+/// Seeds the stack-protector guard from the machine's entropy source.
+///
+/// Called once, early in `kernel_init`, while only the boot frame is live:
+/// re-seeding later would make every protected frame already on the stack
+/// fail its epilogue check.
+pub fn init_stack_guard() {
+    let guard = if Machine::capabilities().supports(Capabilities::RNG) {
+        match Machine::random_u32() {
+            Some(word) => word as usize,
+            None => FALLBACK_GUARD,
+        }
+    } else {
+        FALLBACK_GUARD
+    };
+    // SAFETY: single write before tasks exist; nothing reads the guard
+    // concurrently during init.
+    unsafe { __stack_chk_guard = guard };
+}
+
+/// The diagnostic printed on a smashed kernel stack, separated from the
+/// panic so tests can verify the exact wording.
+pub fn stack_chk_fail_message() -> &'static str {
+    "kernel stack smashed: __stack_chk_fail (canary overwritten)"
+}
+
+/// Called by stack-protector epilogues on a canary mismatch. The frame that
+/// detected the corruption cannot be trusted to return, so this never does.
+#[no_mangle]
+pub extern "C" fn __stack_chk_fail() -> ! {
+    panic!("{}", stack_chk_fail_message());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fail_handler_formats_the_expected_diagnostic() {
+        // The handler itself cannot be exercised here: a panic crossing an
+        // `extern "C"` boundary aborts the test process. The message it
+        // panics with is shared through this helper instead.
+        let msg = stack_chk_fail_message();
+        assert!(msg.contains("__stack_chk_fail"));
+        assert!(msg.contains("stack smashed"));
+    }
+
+    #[test]
+    fn guard_is_seeded_from_the_machine() {
+        // The capability set is process-wide, so this is the only test that
+        // may change it; both branches run here sequentially.
+        use hal::TestingMachine;
+
+        TestingMachine::set_capabilities(Capabilities::RNG | Capabilities::DWT);
+        init_stack_guard();
+        // The testing machine offers entropy; the guard must pick it up
+        // rather than keep the fallback pattern.
+        let guard = unsafe { __stack_chk_guard };
+        assert_ne!(guard, 0);
+
+        // Without an RNG capability the entropy source must not even be
+        // consulted; the fixed terminator pattern is used instead.
+        TestingMachine::set_capabilities(Capabilities::DWT);
+        init_stack_guard();
+        assert_eq!(unsafe { __stack_chk_guard }, FALLBACK_GUARD);
+
+        TestingMachine::set_capabilities(Capabilities::RNG | Capabilities::DWT);
+    }
+}
@@ -0,0 +1,66 @@
+//! The Osiris kernel.
+//!
+//! This crate is `no_std` on target builds; host unit tests link against std.
+
+#![cfg_attr(not(test), no_std)]
+
+#[macro_use]
+pub mod utils;
+
+pub mod faults;
+pub mod mem;
+mod panic;
+pub mod print;
+pub mod sched;
+pub mod services;
+pub mod softirq;
+pub mod sync;
+pub mod syscalls;
+pub mod time;
+pub mod uspace;
+
+use hal::{Machine, Machinelike};
+
+/// Marks a function as a service entry point, recording scheduling hints
+/// like `cpu = N` next to it. Re-exported so services only depend on the
+/// kernel crate.
+pub use kernel_macros::service;
+
+/// Errors the running kernel surfaces to its callers (syscall handlers and
+/// internal services), as opposed to the one-shot [`KernelInitError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelError {
+    /// A memory access faulted (or would fault) on behalf of a task.
+    Fault,
+}
+
+/// Errors that abort [`kernel_init`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelInitError {
+    /// The bootloader wrote a [`interface::BootInfo`] version this kernel
+    /// does not understand.
+    BootInfo(interface::BootInfoError),
+    /// The memory map was unusable.
+    Mem(mem::MemInitError),
+}
+
+/// Brings up the kernel from the loader-provided boot information.
+///
+/// A version mismatch or bad memory map is reported here and aborts the boot
+/// instead of turning into misread fields or an obscure allocation failure
+/// later.
+pub fn kernel_init(boot_info: &interface::BootInfo) -> Result<(), KernelInitError> {
+    Machine::init();
+    // Seed the stack-protector canary while only the boot frame is live.
+    faults::init_stack_guard();
+    if let Err(err) = boot_info.validate() {
+        kprintln!("kernel_init: incompatible boot info: {:?}", err);
+        return Err(KernelInitError::BootInfo(err));
+    }
+    print::init_console(boot_info);
+    if let Err(err) = mem::init_memory(boot_info) {
+        kprintln!("kernel_init: memory setup failed: {:?}", err);
+        return Err(KernelInitError::Mem(err));
+    }
+    Ok(())
+}
@@ -0,0 +1,804 @@
+//! A best-fit allocator with an in-line free list.
+//!
+//! Free blocks carry their bookkeeping ([`BestFitMeta`]) at the start of the
+//! block itself, so the allocator needs no memory of its own. The free list is
+//! kept sorted by address so neighbouring free blocks can be coalesced on
+//! `free`.
+
+use core::ops::Range;
+use core::ptr::NonNull;
+
+use crate::utils::align_up;
+
+/// Minimum alignment of every block handed out by the allocator.
+pub const MIN_ALIGN: usize = 8;
+
+/// Default minimum size of a split-off remainder. Splitting below this just
+/// litters the free list with slivers too small to ever satisfy a request.
+pub const DEFAULT_MIN_SPLIT: usize = 32;
+
+/// Byte pattern written over freed block bodies under debug builds, to catch
+/// use-after-free and free-list overwrites.
+#[cfg(debug_assertions)]
+pub const POISON_BYTE: u8 = 0xDE;
+
+/// Number of distinct allocation tags tracked under debug builds. Tags beyond
+/// this are dropped: the allocation succeeds but stays unattributed.
+#[cfg(debug_assertions)]
+pub const MAX_TAGS: usize = 16;
+
+/// Errors reported by the kernel heap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapError {
+    /// No free block is large enough to satisfy the request.
+    OutOfMemory,
+    /// The range passed to `add_range`/`remove_range` is empty, unaligned or
+    /// too small to hold a block header.
+    InvalidRange,
+    /// The range cannot be reclaimed because parts of it are still allocated
+    /// (or a free block straddles the range boundary).
+    RangeInUse,
+    /// The free list is corrupted: a header carries an implausible size or
+    /// link. Reported by [`BestFitAllocator::check_integrity`].
+    Corrupted,
+}
+
+/// In-line header of a block. While a block is free, `next` links it into the
+/// free list; while it is allocated, only `size` is meaningful.
+#[repr(C)]
+struct BestFitMeta {
+    /// Usable bytes following this header.
+    size: usize,
+    /// Next free block (sorted by address). Only valid while the block is free.
+    next: Option<NonNull<BestFitMeta>>,
+    /// Whether the whole body carries the poison pattern. Cleared for donated
+    /// and coalesced memory, where only parts may be poisoned.
+    #[cfg(debug_assertions)]
+    poisoned: bool,
+    /// The subsystem tag recorded by [`BestFitAllocator::malloc_tagged`],
+    /// so `free` knows which per-tag counter to credit. `None` while the
+    /// block is free or for untagged allocations.
+    #[cfg(debug_assertions)]
+    tag: Option<&'static str>,
+}
+
+const META_SIZE: usize = core::mem::size_of::<BestFitMeta>();
+
+/// The kernel's best-fit heap allocator.
+pub struct BestFitAllocator {
+    /// First free block, sorted by address.
+    head: Option<NonNull<BestFitMeta>>,
+    /// Bytes currently handed out (excluding block headers).
+    used: usize,
+    /// Most bytes ever simultaneously handed out, for sizing the heap.
+    peak_used: usize,
+    /// Minimum size of a remainder worth splitting off. Requests leaving
+    /// less than this get the whole block (recorded in the block header).
+    min_split: usize,
+    /// Outstanding bytes per allocation tag, for leak attribution when the
+    /// heap fills. Kept out of release builds entirely.
+    #[cfg(debug_assertions)]
+    tag_bytes: [(Option<&'static str>, usize); MAX_TAGS],
+}
+
+// SAFETY: the free list is raw memory owned exclusively by the allocator;
+// concurrent access is serialized by the `SpinLock` around the global heap.
+unsafe impl Send for BestFitAllocator {}
+
+impl Default for BestFitAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BestFitAllocator {
+    /// Creates an allocator that owns no memory yet.
+    pub const fn new() -> Self {
+        Self {
+            head: None,
+            used: 0,
+            peak_used: 0,
+            min_split: DEFAULT_MIN_SPLIT,
+            #[cfg(debug_assertions)]
+            tag_bytes: [(None, 0); MAX_TAGS],
+        }
+    }
+
+    /// Tunes the minimum remainder worth splitting off a block. Larger values
+    /// trade over-allocation for less free-list fragmentation.
+    pub fn set_min_split(&mut self, bytes: usize) {
+        self.min_split = align_up(bytes.max(MIN_ALIGN), MIN_ALIGN);
+    }
+
+    /// Donates `range` to the allocator. The range must be `MIN_ALIGN`-aligned
+    /// and large enough to hold at least one block header.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the memory in `range` is unused and
+    /// stays valid for the lifetime of the allocator.
+    pub unsafe fn add_range(&mut self, range: Range<usize>) -> Result<(), HeapError> {
+        if !range.start.is_multiple_of(MIN_ALIGN) || range.end <= range.start + META_SIZE {
+            return Err(HeapError::InvalidRange);
+        }
+
+        let meta = range.start as *mut BestFitMeta;
+        (*meta).size = range.end - range.start - META_SIZE;
+        (*meta).next = None;
+        #[cfg(debug_assertions)]
+        {
+            (*meta).poisoned = false;
+            (*meta).tag = None;
+        }
+        self.insert_free(NonNull::new_unchecked(meta));
+        Ok(())
+    }
+
+    /// Detaches every free block fully contained in `range` from the free
+    /// list, so the memory can be handed to a different owner.
+    ///
+    /// The whole range must consist of free blocks: if any byte of it is
+    /// currently allocated, or a free block straddles a range boundary,
+    /// nothing is removed and an error is returned.
+    pub fn remove_range(&mut self, range: Range<usize>) -> Result<(), HeapError> {
+        if range.end <= range.start {
+            return Err(HeapError::InvalidRange);
+        }
+
+        // First pass: every free block overlapping the range must be fully
+        // contained, and the contained blocks must cover the range exactly.
+        // Any shortfall means live allocations (or foreign memory) inside.
+        let mut covered = 0usize;
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            let (start, end) = unsafe { Self::block_span(node) };
+            if start < range.end && end > range.start {
+                if start < range.start || end > range.end {
+                    return Err(HeapError::RangeInUse);
+                }
+                covered += end - start;
+            }
+            cur = unsafe { node.as_ref().next };
+        }
+        if covered != range.end - range.start {
+            return Err(HeapError::RangeInUse);
+        }
+
+        // Second pass: unlink all contained blocks.
+        let mut link = &mut self.head;
+        while let Some(node) = *link {
+            let (start, end) = unsafe { Self::block_span(node) };
+            if start >= range.start && end <= range.end {
+                *link = unsafe { node.as_ref().next };
+            } else {
+                link = unsafe { &mut (*node.as_ptr()).next };
+            }
+        }
+        Ok(())
+    }
+
+    /// Allocates `size` bytes (rounded up to `MIN_ALIGN`) from the best
+    /// fitting free block.
+    pub fn malloc(&mut self, size: usize) -> Result<NonNull<u8>, HeapError> {
+        let size = align_up(size.max(1), MIN_ALIGN);
+
+        // Find the smallest block that fits, remembering the link to it.
+        let mut best: Option<*mut Option<NonNull<BestFitMeta>>> = None;
+        let mut best_size = usize::MAX;
+        let mut link: *mut Option<NonNull<BestFitMeta>> = &mut self.head;
+        unsafe {
+            while let Some(node) = *link {
+                let node_size = node.as_ref().size;
+                if node_size >= size && node_size < best_size {
+                    best = Some(link);
+                    best_size = node_size;
+                }
+                link = &mut (*node.as_ptr()).next;
+            }
+
+            let link = best.ok_or(HeapError::OutOfMemory)?;
+            let mut node = (*link).unwrap();
+
+            // If the block body carries poison from an earlier free, it must
+            // be *fully* poisoned: a partial pattern means something wrote
+            // through a dangling pointer into free memory.
+            #[cfg(debug_assertions)]
+            Self::debug_check_poison(node);
+
+            let meta = node.as_mut();
+
+            if meta.size >= size + META_SIZE + self.min_split {
+                // Split: the tail of the block becomes a new free block that
+                // replaces this one in the list.
+                let rest = (node.as_ptr() as usize + META_SIZE + size) as *mut BestFitMeta;
+                (*rest).size = meta.size - size - META_SIZE;
+                (*rest).next = meta.next;
+                // The remainder's body is the untouched tail of this block.
+                #[cfg(debug_assertions)]
+                {
+                    (*rest).poisoned = meta.poisoned;
+                    (*rest).tag = None;
+                }
+                *link = Some(NonNull::new_unchecked(rest));
+                meta.size = size;
+            } else {
+                // Hand out the whole block; `meta.size` keeps the actual
+                // (over-allocated) size so `free` sees what was granted.
+                *link = meta.next;
+            }
+            meta.next = None;
+
+            // Account the block's actual size, which can exceed the request
+            // when the remainder was too small to split off.
+            self.used += meta.size;
+            self.peak_used = self.peak_used.max(self.used);
+
+            Ok(NonNull::new_unchecked(
+                (node.as_ptr() as usize + META_SIZE) as *mut u8,
+            ))
+        }
+    }
+
+    /// Like [`Self::malloc`], but attributes the allocation to `tag` for the
+    /// per-tag outstanding-bytes accounting ([`Self::tag_stats`]). Use one
+    /// tag per subsystem (`"sched"`, `"uspace"`, ...) so a filling heap can
+    /// be blamed. Compiles down to a plain `malloc` in release builds.
+    #[cfg(debug_assertions)]
+    pub fn malloc_tagged(
+        &mut self,
+        size: usize,
+        tag: &'static str,
+    ) -> Result<NonNull<u8>, HeapError> {
+        let ptr = self.malloc(size)?;
+        let node = (ptr.as_ptr() as usize - META_SIZE) as *mut BestFitMeta;
+        // SAFETY: `node` is the header of the block just handed out.
+        unsafe {
+            let granted = (*node).size;
+            if let Some(bytes) = self.tag_slot(tag) {
+                *bytes += granted;
+                (*node).tag = Some(tag);
+            }
+            // A full tag table drops the tag; the allocation itself stands.
+        }
+        Ok(ptr)
+    }
+
+    /// Release counterpart of the tagged allocation: the tag is ignored and
+    /// the block layout stays untouched.
+    #[cfg(not(debug_assertions))]
+    pub fn malloc_tagged(
+        &mut self,
+        size: usize,
+        _tag: &'static str,
+    ) -> Result<NonNull<u8>, HeapError> {
+        self.malloc(size)
+    }
+
+    /// The counter slot for `tag`, claiming a fresh one on first use. `None`
+    /// once all [`MAX_TAGS`] slots are taken.
+    #[cfg(debug_assertions)]
+    fn tag_slot(&mut self, tag: &'static str) -> Option<&mut usize> {
+        let index = self
+            .tag_bytes
+            .iter()
+            .position(|(t, _)| *t == Some(tag))
+            .or_else(|| {
+                let free = self.tag_bytes.iter().position(|(t, _)| t.is_none());
+                if let Some(i) = free {
+                    self.tag_bytes[i].0 = Some(tag);
+                }
+                free
+            })?;
+        Some(&mut self.tag_bytes[index].1)
+    }
+
+    /// Bytes currently outstanding under `tag`.
+    #[cfg(debug_assertions)]
+    pub fn tag_outstanding(&self, tag: &str) -> usize {
+        self.tag_bytes
+            .iter()
+            .find(|(t, _)| *t == Some(tag))
+            .map(|(_, bytes)| *bytes)
+            .unwrap_or(0)
+    }
+
+    /// Iterates `(tag, outstanding bytes)` over every tag seen so far, for
+    /// dumping when the heap fills.
+    #[cfg(debug_assertions)]
+    pub fn tag_stats(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        self.tag_bytes
+            .iter()
+            .filter_map(|&(tag, bytes)| tag.map(|t| (t, bytes)))
+    }
+
+    /// Returns an allocation of `size` bytes previously obtained from
+    /// [`Self::malloc`] to the allocator, coalescing with adjacent free
+    /// blocks.
+    ///
+    /// `size` is the size the caller *requested*; the block header is
+    /// authoritative for how much was actually granted, so a block handed
+    /// out whole (without splitting) frees correctly too. A `size` larger
+    /// than the granted size is a caller bug.
+    pub fn free(&mut self, ptr: NonNull<u8>, size: usize) {
+        let size = align_up(size.max(1), MIN_ALIGN);
+        let node = (ptr.as_ptr() as usize - META_SIZE) as *mut BestFitMeta;
+        unsafe {
+            BUG_ON!(
+                size > (*node).size,
+                "free() size exceeds the allocated block"
+            );
+            self.used -= (*node).size;
+            // Poison the whole granted body so use-after-free shows up as a
+            // recognizable pattern, and credit the block's granted size back
+            // to its tag. Compiled out in release builds.
+            #[cfg(debug_assertions)]
+            {
+                let granted = (*node).size;
+                if let Some(tag) = (*node).tag.take() {
+                    if let Some(bytes) = self.tag_slot(tag) {
+                        *bytes -= granted;
+                    }
+                }
+                core::ptr::write_bytes(ptr.as_ptr(), POISON_BYTE, granted);
+                (*node).poisoned = true;
+            }
+            self.insert_free(NonNull::new_unchecked(node));
+        }
+    }
+
+    /// The actual usable size of an allocation, which can exceed the
+    /// requested size when the block was handed out without splitting.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a live allocation obtained from [`Self::malloc`].
+    pub unsafe fn usable_size(&self, ptr: NonNull<u8>) -> usize {
+        let node = (ptr.as_ptr() as usize - META_SIZE) as *const BestFitMeta;
+        (*node).size
+    }
+
+    /// Most bytes ever simultaneously allocated (excluding block headers).
+    pub fn peak_used(&self) -> usize {
+        self.peak_used
+    }
+
+    /// Total number of free bytes (excluding block headers).
+    pub fn free_bytes(&self) -> usize {
+        let mut total = 0;
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            unsafe {
+                total += node.as_ref().size;
+                cur = node.as_ref().next;
+            }
+        }
+        total
+    }
+
+    /// Walks the free list verifying every header is still plausible: node
+    /// addresses `MIN_ALIGN`-aligned, sizes positive and not wrapping the
+    /// address space, and links strictly ascending by address. The strict
+    /// ordering doubles as the cycle check — a looped `next` would have to
+    /// revisit a lower address — so the walk is bounded even on a corrupted
+    /// list. The kernel analog of a malloc consistency check; exposed to
+    /// tasks through the `heapcheck` syscall.
+    pub fn check_integrity(&self) -> Result<(), HeapError> {
+        let mut prev_end = 0usize;
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            let addr = node.as_ptr() as usize;
+            if !addr.is_multiple_of(MIN_ALIGN) || addr < prev_end {
+                return Err(HeapError::Corrupted);
+            }
+            // SAFETY: the header was valid when linked in; the checks above
+            // reject pointers that left the list's sorted order, and a size
+            // of 0 or one overflowing the address space never came from us.
+            let size = unsafe { node.as_ref().size };
+            let Some(end) = addr.checked_add(META_SIZE + size) else {
+                return Err(HeapError::Corrupted);
+            };
+            if size == 0 {
+                return Err(HeapError::Corrupted);
+            }
+            prev_end = end;
+            cur = unsafe { node.as_ref().next };
+        }
+        Ok(())
+    }
+
+    /// Inserts `node` into the address-sorted free list and coalesces it with
+    /// its neighbours where they are contiguous.
+    unsafe fn insert_free(&mut self, node: NonNull<BestFitMeta>) {
+        let mut link: *mut Option<NonNull<BestFitMeta>> = &mut self.head;
+        while let Some(cur) = *link {
+            if cur.as_ptr() as usize > node.as_ptr() as usize {
+                break;
+            }
+            link = &mut (*cur.as_ptr()).next;
+        }
+        (*node.as_ptr()).next = *link;
+        *link = Some(node);
+
+        // Merge with the following block if contiguous.
+        let (_, end) = Self::block_span(node);
+        if let Some(next) = (*node.as_ptr()).next {
+            if next.as_ptr() as usize == end {
+                (*node.as_ptr()).size += META_SIZE + next.as_ref().size;
+                (*node.as_ptr()).next = next.as_ref().next;
+                // The absorbed header interrupts the poison pattern.
+                #[cfg(debug_assertions)]
+                {
+                    (*node.as_ptr()).poisoned = false;
+                }
+            }
+        }
+
+        // Merge with the preceding block if contiguous.
+        let mut cur = self.head;
+        while let Some(prev) = cur {
+            if let Some(next) = prev.as_ref().next {
+                if next == node {
+                    let (_, prev_end) = Self::block_span(prev);
+                    if prev_end == node.as_ptr() as usize {
+                        (*prev.as_ptr()).size += META_SIZE + (*node.as_ptr()).size;
+                        (*prev.as_ptr()).next = (*node.as_ptr()).next;
+                        #[cfg(debug_assertions)]
+                        {
+                            (*prev.as_ptr()).poisoned = false;
+                        }
+                    }
+                    return;
+                }
+            }
+            cur = prev.as_ref().next;
+        }
+    }
+
+    /// Verifies a fully-poisoned free block's pattern is intact before the
+    /// block is handed out. Donated or coalesced memory is not marked
+    /// poisoned and is skipped.
+    #[cfg(debug_assertions)]
+    unsafe fn debug_check_poison(node: NonNull<BestFitMeta>) {
+        if !node.as_ref().poisoned {
+            return;
+        }
+        let body = (node.as_ptr() as usize + META_SIZE) as *const u8;
+        for i in 0..node.as_ref().size {
+            BUG_ON!(
+                *body.add(i) != POISON_BYTE,
+                "free block overwritten at offset {}",
+                i
+            );
+        }
+    }
+
+    /// Start and end address of a block including its header.
+    unsafe fn block_span(node: NonNull<BestFitMeta>) -> (usize, usize) {
+        let start = node.as_ptr() as usize;
+        (start, start + META_SIZE + node.as_ref().size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `MIN_ALIGN`-aligned arena for host tests.
+    struct Arena(Vec<u64>);
+
+    impl Arena {
+        fn new(size: usize) -> Self {
+            Arena(vec![0u64; size / 8])
+        }
+
+        fn range(&self) -> Range<usize> {
+            let start = self.0.as_ptr() as usize;
+            start..start + self.0.len() * 8
+        }
+    }
+
+    /// Asserts that no two allocations in `ptrs` (with their sizes) overlap.
+    pub fn verify_ptrs_not_overlaping(ptrs: &[(NonNull<u8>, usize)]) {
+        for (i, (a, a_size)) in ptrs.iter().enumerate() {
+            for (b, b_size) in ptrs.iter().skip(i + 1) {
+                let a = a.as_ptr() as usize;
+                let b = b.as_ptr() as usize;
+                assert!(
+                    a + a_size <= b || b + b_size <= a,
+                    "allocations overlap: {a:#x}+{a_size} vs {b:#x}+{b_size}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn malloc_free_roundtrip() {
+        let arena = Arena::new(1024);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+
+        let a = alloc.malloc(64).unwrap();
+        let b = alloc.malloc(128).unwrap();
+        verify_ptrs_not_overlaping(&[(a, 64), (b, 128)]);
+
+        alloc.free(a, 64);
+        alloc.free(b, 128);
+        assert_eq!(alloc.free_bytes(), 1024 - super::META_SIZE);
+    }
+
+    #[test]
+    fn peak_used_tracks_the_high_water_mark() {
+        let arena = Arena::new(1024);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+        assert_eq!(alloc.peak_used(), 0);
+
+        // Peak at 192 bytes...
+        let a = alloc.malloc(64).unwrap();
+        let b = alloc.malloc(128).unwrap();
+        assert_eq!(alloc.peak_used(), 192);
+
+        // ...then drop back down and reallocate less: the peak must reflect
+        // the maximum, not the final, usage.
+        alloc.free(a, 64);
+        alloc.free(b, 128);
+        let c = alloc.malloc(32).unwrap();
+        assert_eq!(alloc.peak_used(), 192);
+        alloc.free(c, 32);
+        assert_eq!(alloc.peak_used(), 192);
+    }
+
+    #[test]
+    fn remainder_below_the_split_threshold_is_not_split() {
+        let arena = Arena::new(256);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+        let total = alloc.free_bytes();
+
+        // The remainder would be smaller than the threshold: the whole block
+        // is handed out and the header records the over-allocated size.
+        let request = total - META_SIZE - DEFAULT_MIN_SPLIT + MIN_ALIGN;
+        let ptr = alloc.malloc(request).unwrap();
+        assert_eq!(alloc.free_bytes(), 0);
+        assert_eq!(unsafe { alloc.usable_size(ptr) }, total);
+        assert_eq!(alloc.peak_used(), total);
+
+        // Freeing with the granted size returns everything.
+        alloc.free(ptr, total);
+        assert_eq!(alloc.free_bytes(), total);
+    }
+
+    #[test]
+    fn freeing_an_over_allocated_block_with_the_requested_size_works() {
+        let arena = Arena::new(256);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+        let total = alloc.free_bytes();
+
+        // Force the no-split path: the block keeps its larger size while the
+        // caller only knows what it asked for. Freeing with the requested
+        // size must return the whole block, not trip a size check.
+        let request = total - META_SIZE - DEFAULT_MIN_SPLIT + MIN_ALIGN;
+        let ptr = alloc.malloc(request).unwrap();
+        assert!(unsafe { alloc.usable_size(ptr) } > request);
+        alloc.free(ptr, request);
+        assert_eq!(alloc.free_bytes(), total);
+    }
+
+    #[test]
+    fn remainder_at_the_split_threshold_is_split() {
+        let arena = Arena::new(256);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+        let total = alloc.free_bytes();
+
+        let request = total - META_SIZE - DEFAULT_MIN_SPLIT;
+        let ptr = alloc.malloc(request).unwrap();
+        assert_eq!(unsafe { alloc.usable_size(ptr) }, request);
+        assert_eq!(alloc.free_bytes(), DEFAULT_MIN_SPLIT);
+        alloc.free(ptr, request);
+        assert_eq!(alloc.free_bytes(), total);
+    }
+
+    #[test]
+    fn raising_the_threshold_widens_the_no_split_window() {
+        let arena = Arena::new(512);
+        let mut alloc = BestFitAllocator::new();
+        alloc.set_min_split(128);
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+        let total = alloc.free_bytes();
+
+        // This remainder would satisfy the default threshold, but not the
+        // raised one.
+        let request = total - META_SIZE - DEFAULT_MIN_SPLIT;
+        let ptr = alloc.malloc(request).unwrap();
+        assert_eq!(unsafe { alloc.usable_size(ptr) }, total);
+        alloc.free(ptr, total);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn freed_block_is_poisoned() {
+        let arena = Arena::new(1024);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+
+        let a = alloc.malloc(64).unwrap();
+        unsafe { core::ptr::write_bytes(a.as_ptr(), 0xAB, 64) };
+        alloc.free(a, 64);
+
+        let body = unsafe { core::slice::from_raw_parts(a.as_ptr(), 64) };
+        assert!(body.iter().all(|&b| b == POISON_BYTE));
+
+        // The heap stays usable after poisoning, including for other sizes.
+        let b = alloc.malloc(32).unwrap();
+        unsafe { core::ptr::write_bytes(b.as_ptr(), 0xCD, 32) };
+        alloc.free(b, 32);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn tagged_allocations_group_outstanding_bytes_by_tag() {
+        let arena = Arena::new(1024);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+
+        let a = alloc.malloc_tagged(64, "sched").unwrap();
+        let b = alloc.malloc_tagged(32, "uspace").unwrap();
+        let c = alloc.malloc_tagged(16, "sched").unwrap();
+        // Untagged allocations stay out of the attribution.
+        let d = alloc.malloc(128).unwrap();
+
+        assert_eq!(alloc.tag_outstanding("sched"), 64 + 16);
+        assert_eq!(alloc.tag_outstanding("uspace"), 32);
+        assert_eq!(alloc.tag_outstanding("print"), 0);
+        let mut stats: Vec<_> = alloc.tag_stats().collect();
+        stats.sort();
+        assert_eq!(stats, [("sched", 80), ("uspace", 32)]);
+
+        // Freeing credits the block's granted size back to its tag.
+        alloc.free(a, 64);
+        assert_eq!(alloc.tag_outstanding("sched"), 16);
+        alloc.free(c, 16);
+        assert_eq!(alloc.tag_outstanding("sched"), 0);
+        assert_eq!(alloc.tag_outstanding("uspace"), 32);
+        alloc.free(b, 32);
+        alloc.free(d, 128);
+        assert!(alloc.tag_stats().all(|(_, bytes)| bytes == 0));
+    }
+
+    #[test]
+    fn remove_range_all_free_succeeds() {
+        let arena = Arena::new(1024);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+
+        alloc.remove_range(arena.range()).unwrap();
+        assert_eq!(alloc.free_bytes(), 0);
+        assert_eq!(alloc.malloc(8), Err(HeapError::OutOfMemory));
+    }
+
+    #[test]
+    fn remove_range_with_live_allocation_errors() {
+        let arena = Arena::new(1024);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+
+        let a = alloc.malloc(64).unwrap();
+        assert_eq!(
+            alloc.remove_range(arena.range()),
+            Err(HeapError::RangeInUse)
+        );
+
+        // The failed removal must leave the free list usable.
+        let b = alloc.malloc(32).unwrap();
+        verify_ptrs_not_overlaping(&[(a, 64), (b, 32)]);
+        alloc.free(a, 64);
+        alloc.free(b, 32);
+        alloc.remove_range(arena.range()).unwrap();
+        assert_eq!(alloc.free_bytes(), 0);
+    }
+
+    #[test]
+    fn integrity_check_passes_and_catches_a_corrupted_link() {
+        let arena = Arena::new(1024);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+        assert_eq!(alloc.check_integrity(), Ok(()));
+
+        // Two free blocks: the freed head block and the arena tail (the live
+        // allocation in between keeps them from coalescing).
+        let a = alloc.malloc(64).unwrap();
+        let _b = alloc.malloc(64).unwrap();
+        alloc.free(a, 64);
+        assert_eq!(alloc.check_integrity(), Ok(()));
+
+        // Overwrite the freed block's `next` to point back at its own
+        // header — the classic shape of a clobbered free list. The
+        // sorted-order check reports it instead of looping forever.
+        let header = a.as_ptr() as usize - META_SIZE;
+        let next_field = (header + core::mem::size_of::<usize>()) as *mut usize;
+        unsafe { *next_field = header };
+        assert_eq!(alloc.check_integrity(), Err(HeapError::Corrupted));
+    }
+
+    /// A xorshift64 generator: deterministic, no dependencies, good enough to
+    /// shake out coalescing/splitting bugs reproducibly.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    /// Interleaves thousands of allocs and frees of varying sizes, checking
+    /// after every operation that live blocks never overlap and that the
+    /// free/live accounting adds up. Rerun a failing seed by itself to
+    /// reproduce a failure.
+    fn stress(seed: u64) {
+        const ARENA: usize = 16 * 1024;
+        const OPS: usize = 4000;
+
+        let arena = Arena::new(ARENA);
+        let mut alloc = BestFitAllocator::new();
+        unsafe { alloc.add_range(arena.range()).unwrap() };
+
+        let mut rng = Rng(seed);
+        let mut live: Vec<(NonNull<u8>, usize)> = Vec::new();
+        for op in 0..OPS {
+            // Bias towards allocation so the heap actually fills up.
+            if live.is_empty() || !rng.next().is_multiple_of(3) {
+                let size = 1 + (rng.next() % 512) as usize;
+                if let Ok(ptr) = alloc.malloc(size) {
+                    // Fill the requested bytes with a per-block pattern so a
+                    // later overlap shows up as a clobbered pattern too.
+                    unsafe { core::ptr::write_bytes(ptr.as_ptr(), op as u8, size) };
+                    live.push((ptr, size));
+                }
+                // OutOfMemory is fine under stress; freeing makes room again.
+            } else {
+                let index = (rng.next() as usize) % live.len();
+                let (ptr, size) = live.swap_remove(index);
+                let slice = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), size) };
+                assert!(
+                    slice.iter().all(|&b| b == slice[0]),
+                    "seed {seed:#x}: block pattern clobbered before free"
+                );
+                alloc.free(ptr, size);
+            }
+
+            verify_ptrs_not_overlaping(&live);
+
+            // Every byte of the arena is a header or counted exactly once as
+            // free or granted; free-block headers account for the slack.
+            let granted: usize = live
+                .iter()
+                .map(|(ptr, _)| unsafe { alloc.usable_size(*ptr) })
+                .sum();
+            assert!(
+                alloc.free_bytes() + granted + live.len() * META_SIZE <= ARENA,
+                "seed {seed:#x}: accounting exceeds the arena"
+            );
+        }
+
+        // Freeing everything must coalesce back into one block, so freed
+        // memory is fully reusable.
+        for (ptr, size) in live.drain(..) {
+            alloc.free(ptr, size);
+        }
+        assert_eq!(alloc.free_bytes(), ARENA - META_SIZE);
+        let all = alloc.malloc(ARENA - META_SIZE).unwrap();
+        alloc.free(all, ARENA - META_SIZE);
+    }
+
+    #[test]
+    fn randomized_alloc_free_stress_keeps_invariants() {
+        for seed in [0x9E37_79B9_7F4A_7C15, 0xDEAD_BEEF_CAFE_F00D, 42] {
+            stress(seed);
+        }
+    }
+}
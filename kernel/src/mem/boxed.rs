@@ -0,0 +1,138 @@
+//! An owned heap allocation, the kernel's counterpart to `alloc::boxed::Box`.
+//!
+//! Size bookkeeping: the heap's `free` requires the exact allocation size, and
+//! a `Box<T>` always allocates exactly `size_of::<T>()` bytes. Any path that
+//! reconstitutes a box — [`Box::from_raw`] after [`Box::into_raw`] — therefore
+//! frees with the right size for free, as long as `T` matches the original
+//! allocation.
+
+use core::mem::size_of;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use crate::mem::best_fit::MIN_ALIGN;
+use crate::mem::{with_heap, HeapError};
+
+/// An owned `T` on the kernel heap. Freed on drop.
+pub struct Box<T> {
+    ptr: NonNull<T>,
+}
+
+impl<T> Box<T> {
+    /// Moves `value` onto the kernel heap.
+    pub fn new(value: T) -> Result<Self, HeapError> {
+        // The heap hands out MIN_ALIGN-aligned blocks; larger alignments
+        // would need an aligned allocation path we don't have.
+        const { assert!(core::mem::align_of::<T>() <= MIN_ALIGN) };
+
+        let raw = with_heap(|heap| heap.malloc(size_of::<T>()))?;
+        let ptr = raw.cast::<T>();
+        // SAFETY: freshly allocated, properly aligned and large enough for T.
+        unsafe { ptr.write(value) };
+        Ok(Self { ptr })
+    }
+
+    /// Releases ownership and returns the raw pointer without freeing.
+    ///
+    /// The allocation must eventually be returned via [`Box::from_raw`] (or
+    /// deliberately leaked), otherwise the memory is lost.
+    pub fn into_raw(b: Self) -> NonNull<T> {
+        let ptr = b.ptr;
+        core::mem::forget(b);
+        ptr
+    }
+
+    /// Reconstitutes a box from a pointer produced by [`Box::into_raw`].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `Box::<T>::into_raw` with the same `T` and must
+    /// not be used (or passed here again) afterwards — the returned box owns
+    /// the allocation and frees it on drop.
+    pub unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        Self { ptr }
+    }
+
+    /// Leaks the allocation, returning a mutable reference that lives for the
+    /// rest of the kernel's lifetime.
+    pub fn leak(b: Self) -> &'static mut T {
+        let mut ptr = Self::into_raw(b);
+        // SAFETY: the allocation is never freed, so the reference cannot
+        // dangle; into_raw gave us exclusive ownership.
+        unsafe { ptr.as_mut() }
+    }
+}
+
+impl<T> Deref for Box<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the box owns a live, initialized T.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for Box<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the box owns a live, initialized T exclusively.
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> Drop for Box<T> {
+    fn drop(&mut self) {
+        // SAFETY: the value is live and about to be unreachable; the
+        // allocation was malloc'd with exactly size_of::<T>() bytes.
+        unsafe {
+            self.ptr.as_ptr().drop_in_place();
+            with_heap(|heap| heap.free(self.ptr.cast::<u8>(), size_of::<T>()));
+        }
+    }
+}
+
+// SAFETY: a Box<T> is an exclusive owner; sending or sharing it is exactly as
+// safe as sending or sharing the T itself.
+unsafe impl<T: Send> Send for Box<T> {}
+unsafe impl<T: Sync> Sync for Box<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Donates a leaked host allocation to the global heap so boxed tests
+    /// have memory to work with.
+    fn donate(words: usize) {
+        let arena: &'static mut [u64] = Vec::leak(vec![0u64; words]);
+        let start = arena.as_ptr() as usize;
+        with_heap(|heap| unsafe {
+            heap.add_range(start..start + core::mem::size_of_val(arena))
+        })
+        .unwrap();
+    }
+
+    // One test: concurrent tests would skew the global free_bytes readings.
+    #[test]
+    fn raw_roundtrip_frees_exactly_once() {
+        donate(64);
+
+        let before = with_heap(|heap| heap.free_bytes());
+        let boxed = Box::new(0xAB54_A98C_EB1F_0AD2u64).unwrap();
+        let during = with_heap(|heap| heap.free_bytes());
+        assert!(during < before);
+
+        // into_raw must not free: the bytes stay claimed.
+        let raw = Box::into_raw(boxed);
+        assert_eq!(with_heap(|heap| heap.free_bytes()), during);
+
+        // from_raw restores ownership; dropping frees exactly the claimed
+        // bytes, back to the starting level (no double-free, no leak).
+        let boxed = unsafe { Box::from_raw(raw) };
+        assert_eq!(*boxed, 0xAB54_A98C_EB1F_0AD2u64);
+        drop(boxed);
+        assert_eq!(with_heap(|heap| heap.free_bytes()), before);
+
+        let leaked: &'static mut u32 = Box::leak(Box::new(7u32).unwrap());
+        *leaked += 1;
+        assert_eq!(*leaked, 8);
+    }
+}
@@ -0,0 +1,84 @@
+//! Kernel memory management.
+
+pub mod best_fit;
+pub mod boxed;
+
+pub use best_fit::{BestFitAllocator, HeapError};
+pub use boxed::Box;
+
+use interface::{BootInfo, MMAP_MAX};
+
+use crate::sync::SpinLock;
+
+/// The kernel heap, fed from the boot memory map by [`init_memory`].
+static HEAP: SpinLock<BestFitAllocator> = SpinLock::new(BestFitAllocator::new());
+
+/// Runs `f` with the kernel heap locked.
+pub fn with_heap<R>(f: impl FnOnce(&mut BestFitAllocator) -> R) -> R {
+    f(&mut HEAP.lock())
+}
+
+/// Errors while setting up memory from the boot information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemInitError {
+    /// The memory map contains no usable (non-empty) region.
+    NoUsableMemory,
+    /// `mmap_len` exceeds the fixed memory map array.
+    InvalidMmapLen(u32),
+    /// A region was rejected by the allocator (misaligned or overlapping).
+    BadRegion(HeapError),
+}
+
+/// Feeds the usable regions of the boot memory map into the kernel heap.
+///
+/// Rejects a malformed map up front — no usable region, or an `mmap_len`
+/// larger than the fixed array — so a bad boot fails here with a clear error
+/// instead of as a generic out-of-memory panic on the first allocation.
+pub fn init_memory(boot_info: &BootInfo) -> Result<(), MemInitError> {
+    if boot_info.mmap_len as usize > MMAP_MAX {
+        return Err(MemInitError::InvalidMmapLen(boot_info.mmap_len));
+    }
+    let entries = &boot_info.mmap[..boot_info.mmap_len as usize];
+    if entries.iter().all(|entry| entry.len == 0) {
+        return Err(MemInitError::NoUsableMemory);
+    }
+
+    with_heap(|heap| {
+        for entry in entries.iter().filter(|entry| entry.len != 0) {
+            let start = entry.start as usize;
+            let end = start + entry.len as usize;
+            // SAFETY: the boot memory map describes RAM that is unused by the
+            // kernel image; the loader guarantees the regions do not overlap.
+            unsafe { heap.add_range(start..end) }.map_err(MemInitError::BadRegion)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_mmap_is_rejected() {
+        let boot_info = BootInfo::empty();
+        assert_eq!(init_memory(&boot_info), Err(MemInitError::NoUsableMemory));
+    }
+
+    #[test]
+    fn zero_length_entries_are_not_usable() {
+        let mut boot_info = BootInfo::empty();
+        boot_info.mmap_len = 2;
+        assert_eq!(init_memory(&boot_info), Err(MemInitError::NoUsableMemory));
+    }
+
+    #[test]
+    fn oversized_mmap_len_is_rejected() {
+        let mut boot_info = BootInfo::empty();
+        boot_info.mmap_len = MMAP_MAX as u32 + 1;
+        assert_eq!(
+            init_memory(&boot_info),
+            Err(MemInitError::InvalidMmapLen(MMAP_MAX as u32 + 1))
+        );
+    }
+}
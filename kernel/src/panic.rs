@@ -0,0 +1,16 @@
+//! The kernel panic handler.
+
+#[cfg(all(not(test), target_os = "none"))]
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    use hal::{Machine, Machinelike};
+
+    crate::kprintln!("kernel panic: {}", info);
+    // Replay the log ring so the crash dump carries the most recent lines
+    // even if earlier output never left the UART.
+    crate::kprintln!("--- last log lines ---");
+    crate::print::flush_log_ring();
+    // Make sure the dump actually left the UART before the reset kills it.
+    let _ = Machine::flush();
+    Machine::reset();
+}
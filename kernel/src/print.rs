@@ -0,0 +1,352 @@
+//! Kernel console output.
+//!
+//! `kprintln!` writes through the machine's print backend and additionally
+//! tees every byte into a fixed-size in-memory ring, so the most recent log
+//! lines survive into a crash dump: the panic handler flushes the ring to the
+//! console as the last thing it does.
+
+use core::fmt::{self, Write};
+
+use hal::{Machine, Machinelike};
+
+use crate::sync::SpinLock;
+
+/// Capacity of the crash log ring in bytes.
+pub const LOG_RING_SIZE: usize = 1024;
+
+/// A byte ring that overwrites its oldest contents when full.
+pub struct LogRing<const N: usize> {
+    buf: [u8; N],
+    /// Next write position.
+    head: usize,
+    /// Number of valid bytes (saturates at `N`).
+    len: usize,
+}
+
+impl<const N: usize> LogRing<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Appends `bytes`, dropping the oldest contents when the ring is full.
+    pub fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.buf[self.head] = byte;
+            self.head = (self.head + 1) % N;
+            if self.len < N {
+                self.len += 1;
+            }
+        }
+    }
+
+    /// The ring contents in order, as the two contiguous slices (oldest
+    /// first).
+    pub fn contents(&self) -> (&[u8], &[u8]) {
+        if self.len < N {
+            (&self.buf[..self.len], &[])
+        } else {
+            (&self.buf[self.head..], &self.buf[..self.head])
+        }
+    }
+}
+
+impl<const N: usize> Default for LogRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static LOG_RING: SpinLock<LogRing<LOG_RING_SIZE>> = SpinLock::new(LogRing::new());
+
+/// The console device selected from boot data, for machine layers that need
+/// its base address and baud rate. `None` until [`init_console`] ran (or
+/// when the bootloader described no console, in which case output falls back
+/// to the machine's compile-time default).
+static CONSOLE: SpinLock<Option<interface::ConsoleInfo>> = SpinLock::new(None);
+
+/// Selects the console from the boot information instead of a compile-time
+/// assumption. Called once from `kernel_init`.
+pub fn init_console(info: &interface::BootInfo) {
+    *CONSOLE.lock() = info.console();
+}
+
+/// The boot-selected console, if the bootloader described one.
+pub fn console() -> Option<interface::ConsoleInfo> {
+    *CONSOLE.lock()
+}
+
+/// Severity of a log line. Ordered from least to most verbose so levels
+/// compare against [`MAX_LOG_LEVEL`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+impl LogLevel {
+    /// Decodes a raw level word from userspace.
+    pub fn from_u32(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(Self::Error),
+            1 => Some(Self::Warn),
+            2 => Some(Self::Info),
+            3 => Some(Self::Debug),
+            _ => None,
+        }
+    }
+
+    /// The level name as it appears in the line prefix.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+        }
+    }
+}
+
+/// The most verbose level that is emitted, fixed at build time through
+/// `OSIRIS_MAX_LOGLEVEL` (0 = error only … 3 = debug; default info). Lines
+/// above it are dropped before any formatting happens.
+pub const MAX_LOG_LEVEL: LogLevel = match option_env!("OSIRIS_MAX_LOGLEVEL") {
+    Some(raw) => match raw.as_bytes() {
+        b"0" => LogLevel::Error,
+        b"1" => LogLevel::Warn,
+        b"2" => LogLevel::Info,
+        b"3" => LogLevel::Debug,
+        _ => panic!("OSIRIS_MAX_LOGLEVEL must be 0-3"),
+    },
+    None => LogLevel::Info,
+};
+
+/// Whether log lines are prefixed with a `[seconds.millis]` monotonic
+/// timestamp, fixed at build time through `OSIRIS_LOG_TIMESTAMPS` (0/1).
+/// Off by default: machines without a usable clock source would otherwise
+/// stamp every line with garbage.
+pub const LOG_TIMESTAMPS: bool = match option_env!("OSIRIS_LOG_TIMESTAMPS") {
+    Some(raw) => match raw.as_bytes() {
+        b"0" => false,
+        b"1" => true,
+        _ => panic!("OSIRIS_LOG_TIMESTAMPS must be 0 or 1"),
+    },
+    None => false,
+};
+
+/// Emits one log line on behalf of a task, prefixed `[task:N][LEVEL]` — and
+/// `[seconds.millis]` when `timestamp_ns` is given. Filtered against
+/// [`MAX_LOG_LEVEL`], so userspace debug chatter costs nothing in a
+/// release-leveled kernel. Formatting streams through `format_args!` and
+/// never allocates.
+///
+/// The clock is passed in rather than read here so host tests can pin it.
+pub fn log_line_at(task: usize, level: LogLevel, msg: &str, timestamp_ns: Option<u64>) {
+    if level > MAX_LOG_LEVEL {
+        return;
+    }
+    match timestamp_ns {
+        Some(now_ns) => {
+            let ms = now_ns / 1_000_000;
+            crate::kprintln!(
+                "[{}.{:03}][task:{task}][{}] {msg}",
+                ms / 1_000,
+                ms % 1_000,
+                level.name()
+            );
+        }
+        None => crate::kprintln!("[task:{task}][{}] {msg}", level.name()),
+    }
+}
+
+/// Emits one log line with this build's timestamp setting: the machine's
+/// monotonic clock when [`LOG_TIMESTAMPS`] is on, no prefix otherwise.
+pub fn log_line(task: usize, level: LogLevel, msg: &str) {
+    log_line_at(task, level, msg, LOG_TIMESTAMPS.then(Machine::now_ns));
+}
+
+/// The console writer behind `kprint!`/`kprintln!`.
+pub struct Writer;
+
+impl fmt::Write for Writer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        Machine::print(s);
+        LOG_RING.lock().push(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// A fixed-capacity formatting buffer on the stack.
+///
+/// Formats into `N` bytes and flushes them to the underlying writer whenever
+/// the buffer fills, so arbitrarily long formatted output streams through
+/// without heap and without truncation. Bytes are only flushed on UTF-8
+/// character boundaries.
+pub struct StackWriter<'a, const N: usize> {
+    sink: &'a mut dyn fmt::Write,
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<'a, const N: usize> StackWriter<'a, N> {
+    pub fn new(sink: &'a mut dyn fmt::Write) -> Self {
+        // The buffer must hold at least one UTF-8 character.
+        const { assert!(N >= 4) };
+        Self {
+            sink,
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Writes the buffered bytes through to the sink.
+    pub fn flush(&mut self) -> fmt::Result {
+        if self.len == 0 {
+            return Ok(());
+        }
+        // SAFETY-free: only whole characters are ever buffered.
+        let s = core::str::from_utf8(&self.buf[..self.len]).map_err(|_| fmt::Error)?;
+        self.sink.write_str(s)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl<const N: usize> fmt::Write for StackWriter<'_, N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for ch in s.chars() {
+            let mut utf8 = [0u8; 4];
+            let encoded = ch.encode_utf8(&mut utf8).as_bytes();
+            if self.len + encoded.len() > N {
+                self.flush()?;
+            }
+            self.buf[self.len..self.len + encoded.len()].copy_from_slice(encoded);
+            self.len += encoded.len();
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Drop for StackWriter<'_, N> {
+    fn drop(&mut self) {
+        // Best effort: anything still buffered goes out with the writer.
+        let _ = self.flush();
+    }
+}
+
+/// Writes the ring contents (the most recent log output) to the console.
+/// Called from the panic handler so a crash dump ends with the tail of the
+/// log even if earlier UART output was lost.
+pub fn flush_log_ring() {
+    let ring = LOG_RING.lock();
+    let (first, second) = ring.contents();
+    for part in [first, second] {
+        if let Ok(s) = core::str::from_utf8(part) {
+            Machine::print(s);
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    let _ = Writer.write_fmt(args);
+}
+
+/// Prints to the kernel console.
+#[macro_export]
+macro_rules! kprint {
+    ($($arg:tt)*) => {
+        $crate::print::_print(format_args!($($arg)*))
+    };
+}
+
+/// Prints to the kernel console, with a trailing newline.
+#[macro_export]
+macro_rules! kprintln {
+    () => { $crate::kprint!("\n") };
+    ($($arg:tt)*) => {{
+        $crate::print::_print(format_args!($($arg)*));
+        $crate::kprint!("\n");
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::TestingMachine;
+
+    #[test]
+    fn stack_writer_streams_output_longer_than_its_buffer() {
+        let mut out = String::new();
+        {
+            let mut writer = StackWriter::<16>::new(&mut out);
+            for i in 0..20 {
+                write!(writer, "chunk {i:02} — ").unwrap();
+            }
+        }
+        // Everything arrives in order, including the multi-byte dashes that
+        // must not be split across flushes.
+        for i in 0..20 {
+            assert!(out.contains(&format!("chunk {i:02} — ")));
+        }
+        assert_eq!(out.chars().filter(|&c| c == '—').count(), 20);
+    }
+
+    // One test: the selected console is process-wide state.
+    #[test]
+    fn console_selection_follows_boot_info() {
+        let mut info = interface::BootInfo::empty();
+        init_console(&info);
+        assert_eq!(console(), None);
+
+        info.console =
+            interface::ConsoleInfo::new(interface::ConsoleKind::Uart, 0x4000_8000, 115_200);
+        init_console(&info);
+        let selected = console().expect("console");
+        assert_eq!(selected.kind(), Some(interface::ConsoleKind::Uart));
+        assert_eq!(selected.baud, 115_200);
+    }
+
+    // One test: the capture buffer is process-wide, so concurrent capture
+    // tests would interleave.
+    #[test]
+    fn leveled_logging_and_ring_flush() {
+        // A task log line gets the `[task:N][LEVEL]` prefix; levels more
+        // verbose than MAX_LOG_LEVEL (default: info) are dropped.
+        TestingMachine::start_capture();
+        log_line(3, LogLevel::Error, "boom");
+        log_line(3, LogLevel::Debug, "chatty");
+        let out = TestingMachine::take_capture();
+        assert!(out.contains("[task:3][ERROR] boom"));
+        assert!(!out.contains("chatty"));
+
+        // A pinned clock yields a `[seconds.millis]` prefix; without one the
+        // line is unchanged.
+        TestingMachine::start_capture();
+        log_line_at(3, LogLevel::Warn, "timed", Some(12_345_678_901));
+        log_line_at(3, LogLevel::Warn, "untimed", None);
+        let out = TestingMachine::take_capture();
+        assert!(out.contains("[12.345][task:3][WARN] timed"));
+        assert!(out.contains("\n[task:3][WARN] untimed"));
+
+        // Fill the ring well past its capacity.
+        TestingMachine::start_capture();
+        for i in 0..100 {
+            kprintln!("log line number {i:04}");
+        }
+        TestingMachine::take_capture();
+
+        // The flush must contain the newest lines and have dropped the oldest.
+        TestingMachine::start_capture();
+        flush_log_ring();
+        let dump = TestingMachine::take_capture();
+        assert!(dump.contains("log line number 0099"));
+        assert!(!dump.contains("log line number 0000"));
+        assert!(dump.len() <= LOG_RING_SIZE);
+    }
+}
@@ -0,0 +1,916 @@
+//! The scheduler: task bookkeeping and (eventually) picking what runs next.
+
+pub mod periodic;
+pub mod task;
+
+use hal::{Machine, Machinelike};
+
+use crate::sync::SpinLock;
+use task::{Task, TaskId, TaskState, WaitObject, MAX_PRIORITY, MAX_WAIT_OBJECTS, PRIORITY_LEVELS};
+
+/// Maximum number of concurrently existing tasks.
+pub const MAX_TASKS: usize = 8;
+
+/// What the fault path should do with a faulting task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultDisposition {
+    /// Unwind the task to its registered fault handler at this entry point.
+    HandledBy(usize),
+    /// No (usable) handler: the task is killed.
+    Killed,
+}
+
+/// Why a `waitpid` request cannot be satisfied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitError {
+    /// The target slot holds no waitable task: it never existed, was already
+    /// reaped, or is the caller/idle task.
+    NoSuchTask,
+    /// Another task is already waiting on the target; a second waiter would
+    /// race it for the reap.
+    AlreadyWaited,
+}
+
+/// Why a runtime priority change was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityError {
+    /// The priority is outside `0..=MAX_PRIORITY`.
+    OutOfRange,
+    /// The target slot holds no task.
+    NoSuchTask,
+    /// Only a privileged task may change another task's priority.
+    NotPermitted,
+}
+
+/// Scheduler counters for performance tuning. All counters are cumulative
+/// since boot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchedStats {
+    /// Context switches where the running task actually changed.
+    pub context_switches: u64,
+    /// Cycles spent in the idle task, from the same cycle counter that feeds
+    /// per-task CPU time.
+    pub idle_cycles: u64,
+    /// Scheduling decisions taken, including those that kept the same task.
+    pub reschedules: u64,
+}
+
+/// The fixed-size task table. All scheduler logic lives on this type so host
+/// tests can drive a private instance.
+pub struct TaskTable {
+    tasks: [Option<Task>; MAX_TASKS],
+    current: Option<TaskId>,
+    /// The dedicated idle task; scheduled only when nothing else is ready.
+    idle: Option<TaskId>,
+    /// Cycle counter reading when the current task's time slice began.
+    slice_start: u32,
+    /// Last slot scheduled per priority level. Selection within a level
+    /// resumes after this slot, which makes each level a strict round-robin
+    /// queue over the fixed task table.
+    rr_cursor: [usize; PRIORITY_LEVELS],
+    /// Cumulative scheduler counters.
+    stats: SchedStats,
+}
+
+impl TaskTable {
+    pub const fn new() -> Self {
+        const NONE: Option<Task> = None;
+        Self {
+            tasks: [NONE; MAX_TASKS],
+            current: None,
+            idle: None,
+            slice_start: 0,
+            // Selection starts *after* the cursor, so this makes the first
+            // pick at every level begin with slot 0.
+            rr_cursor: [MAX_TASKS - 1; PRIORITY_LEVELS],
+            stats: SchedStats {
+                context_switches: 0,
+                idle_cycles: 0,
+                reschedules: 0,
+            },
+        }
+    }
+
+    /// Creates a task in the first free slot.
+    pub fn create_task(&mut self) -> Option<TaskId> {
+        let slot = self.tasks.iter().position(|t| t.is_none())?;
+        let id = TaskId(slot);
+        self.tasks[slot] = Some(Task::new(id));
+        Some(id)
+    }
+
+    pub fn task(&self, id: TaskId) -> Option<&Task> {
+        self.tasks.get(id.0)?.as_ref()
+    }
+
+    pub fn task_mut(&mut self, id: TaskId) -> Option<&mut Task> {
+        self.tasks.get_mut(id.0)?.as_mut()
+    }
+
+    /// Creates the dedicated idle task. Idempotent.
+    pub fn init_idle_task(&mut self) -> Option<TaskId> {
+        if self.idle.is_none() {
+            self.idle = self.create_task();
+        }
+        self.idle
+    }
+
+    /// Whether any task other than the idle task is ready to run. The idle
+    /// task never counts as ready work for preemption decisions.
+    pub fn has_ready_work(&self) -> bool {
+        self.tasks
+            .iter()
+            .flatten()
+            .any(|task| Some(task.id) != self.idle && task.state == TaskState::Ready)
+    }
+
+    /// Picks the next task to run: the highest priority level with a ready
+    /// task wins, and within a level selection strictly round-robins so no
+    /// equal-priority task is skipped. Falls back to the idle task when
+    /// nothing else is ready.
+    pub fn next_task(&self) -> Option<TaskId> {
+        for level in (0..PRIORITY_LEVELS).rev() {
+            let start = self.rr_cursor[level] + 1;
+            for offset in 0..MAX_TASKS {
+                let id = TaskId((start + offset) % MAX_TASKS);
+                if Some(id) == self.idle {
+                    continue;
+                }
+                let ready = self.task(id).is_some_and(|task| {
+                    task.state == TaskState::Ready
+                        && task.effective_priority().min(MAX_PRIORITY) as usize == level
+                });
+                if ready {
+                    return Some(id);
+                }
+            }
+        }
+        self.idle
+    }
+
+    pub fn current(&self) -> Option<TaskId> {
+        self.current
+    }
+
+    pub fn set_current(&mut self, id: TaskId) {
+        self.current = Some(id);
+        self.note_scheduled(id);
+    }
+
+    /// Rotates the scheduled task's priority level past it, so the next pick
+    /// at that level continues with its successor.
+    fn note_scheduled(&mut self, id: TaskId) {
+        if let Some(task) = self.task(id) {
+            let level = task.effective_priority().min(MAX_PRIORITY) as usize;
+            self.rr_cursor[level] = id.0;
+        }
+    }
+
+    /// Accounts the ending time slice to the outgoing task and starts the
+    /// next one's. `now_cycles` is the (wrapping) 32-bit cycle counter; the
+    /// wrapping subtraction keeps the elapsed count correct across a wrap,
+    /// as long as a single slice is shorter than one counter period.
+    pub fn on_context_switch(&mut self, now_cycles: u32, next: TaskId) {
+        let elapsed = now_cycles.wrapping_sub(self.slice_start) as u64;
+        if let Some(task) = self.current.and_then(|id| self.task_mut(id)) {
+            task.cycles_run += elapsed;
+        }
+        if self.current.is_some() && self.current == self.idle {
+            self.stats.idle_cycles += elapsed;
+        }
+        self.stats.reschedules += 1;
+        if self.current != Some(next) {
+            self.stats.context_switches += 1;
+        }
+        self.current = Some(next);
+        self.slice_start = now_cycles;
+        self.note_scheduled(next);
+    }
+
+    /// Parks the outgoing task's stack pointer until it is scheduled again.
+    /// Returns `false` when the slot holds no task.
+    pub fn save_stack(&mut self, id: TaskId, sp: usize) -> bool {
+        match self.task_mut(id) {
+            Some(task) => {
+                task.saved_sp = sp;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The stack pointer parked for `id`, zero while the task has never
+    /// been switched out.
+    pub fn saved_stack(&self, id: TaskId) -> Option<usize> {
+        self.task(id).map(|task| task.saved_sp)
+    }
+
+    /// The cumulative scheduler counters.
+    pub fn stats(&self) -> SchedStats {
+        self.stats
+    }
+
+    /// Cumulative cycles `id` has spent running.
+    pub fn cpu_time(&self, id: TaskId) -> Option<u64> {
+        self.task(id).map(|task| task.cycles_run)
+    }
+
+    /// Sets the base priority of `id`, clamped to [`MAX_PRIORITY`].
+    pub fn set_priority(&mut self, id: TaskId, priority: u8) -> bool {
+        match self.task_mut(id) {
+            Some(task) => {
+                task.base_priority = priority.min(MAX_PRIORITY);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets the CPU affinity mask of `id` (bit `i` = CPU `i`). The mask is
+    /// stored but not consulted by [`Self::next_task`] while the target is
+    /// single-core; an empty mask could never be scheduled anywhere and is a
+    /// configuration bug.
+    pub fn set_affinity(&mut self, id: TaskId, mask: u8) -> bool {
+        BUG_ON!(mask == 0, "empty cpu affinity mask");
+        match self.task_mut(id) {
+            Some(task) => {
+                task.cpu_affinity = mask;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Marks `id` as privileged, allowing it to act on other tasks.
+    pub fn set_privileged(&mut self, id: TaskId, privileged: bool) -> bool {
+        match self.task_mut(id) {
+            Some(task) => {
+                task.privileged = privileged;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Changes `target`'s base priority on behalf of `requester`, the
+    /// permission-checked form of [`Self::set_priority`] behind the
+    /// `setpriority` syscall. A task may always change its own priority;
+    /// changing another's requires privilege. An active
+    /// priority-inheritance boost stays in place — the change affects the
+    /// base priority only, and the next reschedule acts on the result.
+    pub fn change_priority(
+        &mut self,
+        requester: TaskId,
+        target: TaskId,
+        priority: u8,
+    ) -> Result<(), PriorityError> {
+        if priority > MAX_PRIORITY {
+            return Err(PriorityError::OutOfRange);
+        }
+        if requester != target && !self.task(requester).is_some_and(|t| t.privileged) {
+            return Err(PriorityError::NotPermitted);
+        }
+        let task = self.task_mut(target).ok_or(PriorityError::NoSuchTask)?;
+        task.base_priority = priority;
+        Ok(())
+    }
+
+    /// The priority the scheduler acts on for `id`, including any
+    /// priority-inheritance boost.
+    pub fn effective_priority(&self, id: TaskId) -> Option<u8> {
+        self.task(id).map(Task::effective_priority)
+    }
+
+    /// Raises `id`'s effective priority to at least `to` (priority
+    /// inheritance). Never lowers an existing boost.
+    pub fn boost_priority(&mut self, id: TaskId, to: u8) {
+        let to = to.min(MAX_PRIORITY);
+        if let Some(task) = self.task_mut(id) {
+            task.boosted_priority = Some(task.boosted_priority.map_or(to, |b| b.max(to)));
+        }
+    }
+
+    /// Drops `id`'s priority-inheritance boost, reverting to the base
+    /// priority.
+    pub fn clear_boost(&mut self, id: TaskId) {
+        if let Some(task) = self.task_mut(id) {
+            task.boosted_priority = None;
+        }
+    }
+
+    /// Blocks `id` until any of `objects` is satisfied (select-style). At
+    /// most [`MAX_WAIT_OBJECTS`] conditions can be waited on at once.
+    pub fn block_on_any(&mut self, id: TaskId, objects: &[WaitObject]) {
+        BUG_ON!(objects.len() > MAX_WAIT_OBJECTS, "wait set too large");
+        let Some(task) = self.task_mut(id) else {
+            return;
+        };
+        task.wait_set = [None; MAX_WAIT_OBJECTS];
+        for (slot, &object) in task.wait_set.iter_mut().zip(objects) {
+            *slot = Some(object);
+        }
+        task.wake_reason = None;
+        task.state = TaskState::Blocked;
+    }
+
+    /// Blocks `id` for at least `ns` from `now_ns` (nanosleep semantics),
+    /// returning the deadline the caller must arrange a wake for via the
+    /// timer wheel. A zero `ns` leaves the task ready — it just yields.
+    pub fn sleep(&mut self, id: TaskId, now_ns: u64, ns: u64) -> Option<u64> {
+        if ns == 0 {
+            return None;
+        }
+        let deadline = now_ns.saturating_add(ns);
+        self.block_on_any(id, &[WaitObject::Timeout(deadline)]);
+        Some(deadline)
+    }
+
+    /// Signals that `object` is satisfied (a message arrived, a semaphore was
+    /// released): every task waiting on it wakes with it as the reason.
+    pub fn notify(&mut self, object: WaitObject) {
+        for task in self.tasks.iter_mut().flatten() {
+            if task.state == TaskState::Blocked && task.wait_set.contains(&Some(object)) {
+                Self::wake(task, object);
+            }
+        }
+    }
+
+    /// Wakes every task whose timeout has passed at `now_ns`.
+    pub fn wake_expired(&mut self, now_ns: u64) {
+        for task in self.tasks.iter_mut().flatten() {
+            if task.state != TaskState::Blocked {
+                continue;
+            }
+            let expired = task.wait_set.iter().flatten().copied().find(
+                |object| matches!(object, WaitObject::Timeout(deadline) if *deadline <= now_ns),
+            );
+            if let Some(object) = expired {
+                Self::wake(task, object);
+            }
+        }
+    }
+
+    /// The wait object that last woke `id`, reported back to the waiter.
+    pub fn wake_reason(&self, id: TaskId) -> Option<WaitObject> {
+        self.task(id)?.wake_reason
+    }
+
+    fn wake(task: &mut Task, reason: WaitObject) {
+        task.wait_set = [None; MAX_WAIT_OBJECTS];
+        task.wake_reason = Some(reason);
+        task.state = TaskState::Ready;
+    }
+
+    /// Terminates `id` with `code`. The task becomes a zombie holding the
+    /// code until a waiter reaps it, and anyone blocked on its exit wakes.
+    pub fn exit_task(&mut self, id: TaskId, code: i32) {
+        if let Some(task) = self.task_mut(id) {
+            task.exit_code = Some(code);
+            task.state = TaskState::Zombie;
+        }
+        self.notify(WaitObject::TaskExit(id.0));
+    }
+
+    /// Waits for `target` to exit. An already-exited target is reaped — its
+    /// slot frees up — and its exit code returned; a live target blocks
+    /// `waiter` until the exit, after which the retried wait finds the
+    /// zombie. Only one task may wait on a given target at a time.
+    pub fn wait_for_exit(
+        &mut self,
+        waiter: TaskId,
+        target: TaskId,
+    ) -> Result<Option<i32>, WaitError> {
+        // Waiting on yourself or the idle task could never finish.
+        if target == waiter || Some(target) == self.idle {
+            return Err(WaitError::NoSuchTask);
+        }
+        let Some(task) = self.task(target) else {
+            return Err(WaitError::NoSuchTask);
+        };
+
+        if task.state == TaskState::Zombie {
+            let code = task.exit_code.unwrap_or(0);
+            self.tasks[target.0] = None;
+            return Ok(Some(code));
+        }
+
+        let object = WaitObject::TaskExit(target.0);
+        let taken = self
+            .tasks
+            .iter()
+            .flatten()
+            .any(|task| task.id != waiter && task.wait_set.contains(&Some(object)));
+        if taken {
+            return Err(WaitError::AlreadyWaited);
+        }
+        self.block_on_any(waiter, &[object]);
+        Ok(None)
+    }
+
+    /// Installs `entry` as the task's handler for recoverable faults.
+    pub fn set_fault_handler(&mut self, id: TaskId, entry: usize) -> bool {
+        match self.task_mut(id) {
+            Some(task) => {
+                task.fault_handler = Some(entry);
+                task.in_fault_handler = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Decides how to handle a recoverable fault in `id`.
+    ///
+    /// If the task registered a handler and is not already inside it, the
+    /// task is unwound to the handler; a fault inside the handler (or with no
+    /// handler registered) kills the task.
+    pub fn handle_task_fault(&mut self, id: TaskId) -> FaultDisposition {
+        let Some(task) = self.task_mut(id) else {
+            return FaultDisposition::Killed;
+        };
+        match task.fault_handler {
+            Some(entry) if !task.in_fault_handler => {
+                task.in_fault_handler = true;
+                FaultDisposition::HandledBy(entry)
+            }
+            _ => {
+                // A killed task exits as if it had called `exit(-1)`, so a
+                // parent waiting on it is woken instead of hanging forever.
+                self.exit_task(id, -1);
+                FaultDisposition::Killed
+            }
+        }
+    }
+}
+
+impl Default for TaskTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The global task table.
+static TASKS: SpinLock<TaskTable> = SpinLock::new(TaskTable::new());
+
+/// Runs `f` with the global task table locked.
+pub fn with_tasks<R>(f: impl FnOnce(&mut TaskTable) -> R) -> R {
+    f(&mut TASKS.lock())
+}
+
+/// Initializes the scheduler: creates the idle task.
+pub fn init() {
+    with_tasks(|tasks| {
+        BUG_ON!(tasks.init_idle_task().is_none(), "no slot for idle task");
+    });
+}
+
+/// Body of the idle task: sleep until the next interrupt. Installed as the
+/// idle task's entry point by the context-switch layer.
+pub fn idle_main() -> ! {
+    loop {
+        #[cfg(target_arch = "arm")]
+        // SAFETY: wfi has no side effects beyond waiting for an interrupt.
+        unsafe {
+            core::arch::asm!("wfi")
+        };
+        #[cfg(not(target_arch = "arm"))]
+        core::hint::spin_loop();
+    }
+}
+
+/// Picks the next task to run, draining deferred interrupt work first so
+/// softirq handlers observe (and can influence) task states before the
+/// decision. The reschedule entry point for the context-switch layer.
+pub fn pick_next() -> Option<TaskId> {
+    crate::softirq::run_pending();
+    with_tasks(|tasks| tasks.next_task())
+}
+
+/// Context-switch entry point, called from the machine's `PendSV_Handler`
+/// with the outgoing task's stack pointer (frame already saved). Parks it,
+/// makes a scheduling decision and returns the stack pointer to resume —
+/// `psp` unchanged when the decision keeps the current task or the incoming
+/// task has never been switched out.
+#[no_mangle]
+pub extern "C" fn schedule_next(psp: *mut u32) -> *mut u32 {
+    crate::softirq::run_pending();
+    with_tasks(|tasks| {
+        if let Some(current) = tasks.current() {
+            tasks.save_stack(current, psp as usize);
+        }
+        let Some(next) = tasks.next_task() else {
+            return psp;
+        };
+        // The HAL exposes no cycle counter yet; the ns clock's low word
+        // feeds the same wrapping time-slice arithmetic.
+        tasks.on_context_switch(Machine::now_ns() as u32, next);
+        match tasks.saved_stack(next) {
+            Some(sp) if sp != 0 => sp as *mut u32,
+            _ => psp,
+        }
+    })
+}
+
+/// Cumulative cycles `id` has spent running, for profiling.
+pub fn task_cpu_time(id: TaskId) -> Option<u64> {
+    with_tasks(|tasks| tasks.cpu_time(id))
+}
+
+/// The global scheduler counters, for profiling.
+pub fn stats() -> SchedStats {
+    with_tasks(|tasks| tasks.stats())
+}
+
+/// Timer-wheel callback waking every task whose sleep deadline has passed.
+/// Armed by the nanosleep syscall.
+pub fn wake_expired_tasks(now_ns: u64) {
+    with_tasks(|tasks| tasks.wake_expired(now_ns));
+}
+
+/// Installs a fault handler for the currently running task. Returns `false`
+/// when no task is current (boot context).
+pub fn set_current_fault_handler(entry: usize) -> bool {
+    with_tasks(|tasks| match tasks.current() {
+        Some(id) => tasks.set_fault_handler(id, entry),
+        None => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_time_accumulates_across_slices() {
+        let mut tasks = TaskTable::new();
+        let a = tasks.create_task().unwrap();
+        let b = tasks.create_task().unwrap();
+
+        // a runs 0..1000, b runs 1000..1500, a runs 1500..1700.
+        tasks.on_context_switch(0, a);
+        tasks.on_context_switch(1_000, b);
+        tasks.on_context_switch(1_500, a);
+        tasks.on_context_switch(1_700, b);
+
+        assert_eq!(tasks.cpu_time(a), Some(1_000 + 200));
+        assert_eq!(tasks.cpu_time(b), Some(500));
+    }
+
+    #[test]
+    fn cpu_time_handles_counter_wrap() {
+        let mut tasks = TaskTable::new();
+        let a = tasks.create_task().unwrap();
+
+        // The slice spans the 32-bit counter wrap.
+        tasks.on_context_switch(u32::MAX - 99, a);
+        tasks.on_context_switch(100, a);
+        assert_eq!(tasks.cpu_time(a), Some(200));
+    }
+
+    #[test]
+    fn stats_count_switches_idle_time_and_reschedules() {
+        let mut tasks = TaskTable::new();
+        let idle = tasks.init_idle_task().unwrap();
+        let a = tasks.create_task().unwrap();
+        assert_eq!(tasks.stats(), SchedStats::default());
+
+        // a runs 0..1000, idle runs 1000..1600, a again, then a keeps the
+        // CPU through one reschedule that picks the same task.
+        tasks.on_context_switch(0, a);
+        tasks.on_context_switch(1_000, idle);
+        tasks.on_context_switch(1_600, a);
+        tasks.on_context_switch(1_900, a);
+
+        let stats = tasks.stats();
+        // The first switch has no outgoing task but still changes `current`.
+        assert_eq!(stats.context_switches, 3);
+        assert_eq!(stats.idle_cycles, 600);
+        assert_eq!(stats.reschedules, 4);
+    }
+
+    #[test]
+    fn equal_priority_tasks_rotate_strictly() {
+        let mut tasks = TaskTable::new();
+        let a = tasks.create_task().unwrap();
+        let b = tasks.create_task().unwrap();
+        let c = tasks.create_task().unwrap();
+
+        // Six slices: every task runs exactly twice, in creation order.
+        let mut order = Vec::new();
+        for slice in 0..6u32 {
+            let next = tasks.next_task().unwrap();
+            order.push(next);
+            tasks.on_context_switch(slice * 100, next);
+        }
+        assert_eq!(order, vec![a, b, c, a, b, c]);
+    }
+
+    #[test]
+    fn affinity_any_schedules_exactly_as_before() {
+        let mut tasks = TaskTable::new();
+        let a = tasks.create_task().unwrap();
+        let b = tasks.create_task().unwrap();
+        let c = tasks.create_task().unwrap();
+
+        // Explicit "any" masks and a pin to CPU 0 are stored but must not
+        // change single-core scheduling.
+        assert!(tasks.set_affinity(a, task::AFFINITY_ANY));
+        assert!(tasks.set_affinity(b, 0b0000_0001));
+        assert_eq!(tasks.task(b).unwrap().cpu_affinity, 0b0000_0001);
+        assert_eq!(tasks.task(c).unwrap().cpu_affinity, task::AFFINITY_ANY);
+
+        let mut order = Vec::new();
+        for slice in 0..6u32 {
+            let next = tasks.next_task().unwrap();
+            order.push(next);
+            tasks.on_context_switch(slice * 100, next);
+        }
+        assert_eq!(order, vec![a, b, c, a, b, c]);
+    }
+
+    #[test]
+    fn higher_priority_arrival_jumps_the_queue() {
+        let mut tasks = TaskTable::new();
+        let a = tasks.create_task().unwrap();
+        let b = tasks.create_task().unwrap();
+        let c = tasks.create_task().unwrap();
+        tasks.on_context_switch(0, tasks.next_task().unwrap()); // a runs
+
+        // A higher-priority task becomes ready: it preempts the rotation...
+        let urgent = tasks.create_task().unwrap();
+        assert!(tasks.set_priority(urgent, task::DEFAULT_PRIORITY + 4));
+        assert_eq!(tasks.next_task(), Some(urgent));
+        tasks.on_context_switch(100, urgent);
+        // ...and keeps the CPU while it stays ready.
+        assert_eq!(tasks.next_task(), Some(urgent));
+
+        // Once it blocks, the round-robin resumes where it left off: after
+        // a, not back at the start.
+        tasks.block_on_any(urgent, &[WaitObject::Mailbox(0)]);
+        assert_eq!(tasks.next_task(), Some(b));
+        tasks.on_context_switch(200, b);
+        assert_eq!(tasks.next_task(), Some(c));
+        tasks.on_context_switch(300, c);
+        assert_eq!(tasks.next_task(), Some(a));
+    }
+
+    #[test]
+    fn registered_handler_runs_on_fault() {
+        let mut tasks = TaskTable::new();
+        let id = tasks.create_task().unwrap();
+        assert!(tasks.set_fault_handler(id, 0x8000_1234));
+
+        assert_eq!(
+            tasks.handle_task_fault(id),
+            FaultDisposition::HandledBy(0x8000_1234)
+        );
+    }
+
+    #[test]
+    fn fault_in_handler_kills_the_task() {
+        let mut tasks = TaskTable::new();
+        let id = tasks.create_task().unwrap();
+        tasks.set_fault_handler(id, 0x8000_1234);
+
+        assert!(matches!(
+            tasks.handle_task_fault(id),
+            FaultDisposition::HandledBy(_)
+        ));
+        // A second fault while the handler is active is fatal.
+        assert_eq!(tasks.handle_task_fault(id), FaultDisposition::Killed);
+        assert_eq!(tasks.task(id).unwrap().state, TaskState::Zombie);
+    }
+
+    #[test]
+    fn sleep_wakes_no_earlier_than_the_deadline_and_zero_yields() {
+        let mut tasks = TaskTable::new();
+        let id = tasks.create_task().unwrap();
+
+        // Zero nanoseconds never blocks: the task just yields.
+        let mut now_ns = 1_000;
+        assert_eq!(tasks.sleep(id, now_ns, 0), None);
+        assert_eq!(tasks.task(id).unwrap().state, TaskState::Ready);
+
+        // A real sleep blocks until the mock clock passes the deadline.
+        assert_eq!(tasks.sleep(id, now_ns, 500), Some(1_500));
+        assert_eq!(tasks.task(id).unwrap().state, TaskState::Blocked);
+
+        now_ns = 1_499;
+        tasks.wake_expired(now_ns);
+        assert_eq!(tasks.task(id).unwrap().state, TaskState::Blocked);
+
+        now_ns = 1_500;
+        tasks.wake_expired(now_ns);
+        assert_eq!(tasks.task(id).unwrap().state, TaskState::Ready);
+        assert_eq!(tasks.wake_reason(id), Some(WaitObject::Timeout(1_500)));
+    }
+
+    #[test]
+    fn select_wakes_on_timeout_first() {
+        let mut tasks = TaskTable::new();
+        let id = tasks.create_task().unwrap();
+        tasks.block_on_any(id, &[WaitObject::Mailbox(0), WaitObject::Timeout(100)]);
+
+        tasks.wake_expired(50);
+        assert_eq!(tasks.task(id).unwrap().state, TaskState::Blocked);
+
+        tasks.wake_expired(150);
+        assert_eq!(tasks.task(id).unwrap().state, TaskState::Ready);
+        assert_eq!(tasks.wake_reason(id), Some(WaitObject::Timeout(100)));
+
+        // A late message must not wake (or re-reason) the task again.
+        tasks.notify(WaitObject::Mailbox(0));
+        assert_eq!(tasks.wake_reason(id), Some(WaitObject::Timeout(100)));
+    }
+
+    #[test]
+    fn select_wakes_on_message_first() {
+        let mut tasks = TaskTable::new();
+        let id = tasks.create_task().unwrap();
+        tasks.block_on_any(id, &[WaitObject::Mailbox(0), WaitObject::Timeout(100)]);
+
+        tasks.notify(WaitObject::Mailbox(1));
+        assert_eq!(tasks.task(id).unwrap().state, TaskState::Blocked);
+
+        tasks.notify(WaitObject::Mailbox(0));
+        assert_eq!(tasks.task(id).unwrap().state, TaskState::Ready);
+        assert_eq!(tasks.wake_reason(id), Some(WaitObject::Mailbox(0)));
+
+        // The stale timeout no longer applies once the task woke.
+        tasks.wake_expired(150);
+        assert_eq!(tasks.wake_reason(id), Some(WaitObject::Mailbox(0)));
+    }
+
+    #[test]
+    fn idle_task_runs_only_when_nothing_is_ready() {
+        let mut tasks = TaskTable::new();
+        let idle = tasks.init_idle_task().unwrap();
+        let a = tasks.create_task().unwrap();
+        let b = tasks.create_task().unwrap();
+
+        // Idle never counts as ready work.
+        tasks.task_mut(a).unwrap().state = TaskState::Blocked;
+        tasks.task_mut(b).unwrap().state = TaskState::Blocked;
+        assert!(!tasks.has_ready_work());
+        assert_eq!(tasks.next_task(), Some(idle));
+
+        // As soon as a task becomes ready, idle loses the CPU.
+        tasks.set_current(idle);
+        tasks.task_mut(b).unwrap().state = TaskState::Ready;
+        assert!(tasks.has_ready_work());
+        assert_eq!(tasks.next_task(), Some(b));
+    }
+
+    #[test]
+    fn waiting_parent_blocks_until_the_child_exits_and_reaps_the_code() {
+        let mut tasks = TaskTable::new();
+        let parent = tasks.create_task().unwrap();
+        let child = tasks.create_task().unwrap();
+
+        // The child is still alive: the parent blocks on its exit.
+        assert_eq!(tasks.wait_for_exit(parent, child), Ok(None));
+        assert_eq!(tasks.task(parent).unwrap().state, TaskState::Blocked);
+
+        // The child exits: the parent wakes with the exit as the reason, and
+        // the retried wait returns the code and reaps the record.
+        tasks.exit_task(child, 17);
+        assert_eq!(tasks.task(parent).unwrap().state, TaskState::Ready);
+        assert_eq!(tasks.wake_reason(parent), Some(WaitObject::TaskExit(child.0)));
+        assert_eq!(tasks.wait_for_exit(parent, child), Ok(Some(17)));
+
+        // Reaped: the slot is free again and a second wait errors.
+        assert!(tasks.task(child).is_none());
+        assert_eq!(
+            tasks.wait_for_exit(parent, child),
+            Err(WaitError::NoSuchTask)
+        );
+    }
+
+    #[test]
+    fn already_exited_child_is_reaped_without_blocking() {
+        let mut tasks = TaskTable::new();
+        let parent = tasks.create_task().unwrap();
+        let child = tasks.create_task().unwrap();
+
+        tasks.exit_task(child, -3);
+        assert_eq!(tasks.wait_for_exit(parent, child), Ok(Some(-3)));
+        assert_eq!(tasks.task(parent).unwrap().state, TaskState::Ready);
+    }
+
+    #[test]
+    fn only_one_task_may_wait_on_a_child() {
+        let mut tasks = TaskTable::new();
+        let first = tasks.create_task().unwrap();
+        let second = tasks.create_task().unwrap();
+        let child = tasks.create_task().unwrap();
+
+        assert_eq!(tasks.wait_for_exit(first, child), Ok(None));
+        assert_eq!(
+            tasks.wait_for_exit(second, child),
+            Err(WaitError::AlreadyWaited)
+        );
+        // The first waiter still gets woken and reaps normally.
+        tasks.exit_task(child, 0);
+        assert_eq!(tasks.wait_for_exit(first, child), Ok(Some(0)));
+    }
+
+    #[test]
+    fn waiting_on_self_idle_or_an_empty_slot_errors() {
+        let mut tasks = TaskTable::new();
+        let idle = tasks.init_idle_task().unwrap();
+        let id = tasks.create_task().unwrap();
+
+        assert_eq!(tasks.wait_for_exit(id, id), Err(WaitError::NoSuchTask));
+        assert_eq!(tasks.wait_for_exit(id, idle), Err(WaitError::NoSuchTask));
+        assert_eq!(
+            tasks.wait_for_exit(id, TaskId(MAX_TASKS - 1)),
+            Err(WaitError::NoSuchTask)
+        );
+    }
+
+    #[test]
+    fn killed_task_reports_a_failure_exit_code() {
+        let mut tasks = TaskTable::new();
+        let parent = tasks.create_task().unwrap();
+        let child = tasks.create_task().unwrap();
+
+        assert_eq!(tasks.wait_for_exit(parent, child), Ok(None));
+        assert_eq!(tasks.handle_task_fault(child), FaultDisposition::Killed);
+        assert_eq!(tasks.wait_for_exit(parent, child), Ok(Some(-1)));
+    }
+
+    #[test]
+    fn lowering_the_running_tasks_priority_yields_to_ready_work() {
+        let mut tasks = TaskTable::new();
+        let a = tasks.create_task().unwrap();
+        let b = tasks.create_task().unwrap();
+        tasks.set_priority(a, 9);
+        tasks.set_priority(b, 5);
+        tasks.on_context_switch(0, a);
+        assert_eq!(tasks.next_task(), Some(a));
+
+        // a drops below b: the very next scheduling decision picks b.
+        assert_eq!(tasks.change_priority(a, a, 2), Ok(()));
+        assert_eq!(tasks.next_task(), Some(b));
+    }
+
+    #[test]
+    fn priority_changes_are_permission_and_range_checked() {
+        let mut tasks = TaskTable::new();
+        let a = tasks.create_task().unwrap();
+        let b = tasks.create_task().unwrap();
+
+        // Another task's priority needs privilege.
+        assert_eq!(
+            tasks.change_priority(a, b, 3),
+            Err(PriorityError::NotPermitted)
+        );
+        assert!(tasks.set_privileged(a, true));
+        assert_eq!(tasks.change_priority(a, b, 3), Ok(()));
+        assert_eq!(tasks.effective_priority(b), Some(3));
+
+        assert_eq!(
+            tasks.change_priority(a, a, MAX_PRIORITY + 1),
+            Err(PriorityError::OutOfRange)
+        );
+        assert_eq!(
+            tasks.change_priority(a, TaskId(MAX_TASKS - 1), 3),
+            Err(PriorityError::NoSuchTask)
+        );
+    }
+
+    #[test]
+    fn runtime_priority_change_leaves_an_inheritance_boost_in_place() {
+        let mut tasks = TaskTable::new();
+        let a = tasks.create_task().unwrap();
+        tasks.set_priority(a, 8);
+        tasks.boost_priority(a, 12);
+
+        // The change rewrites the base priority; the boost still governs
+        // scheduling until it is dropped.
+        assert_eq!(tasks.change_priority(a, a, 3), Ok(()));
+        assert_eq!(tasks.effective_priority(a), Some(12));
+        tasks.clear_boost(a);
+        assert_eq!(tasks.effective_priority(a), Some(3));
+    }
+
+    #[test]
+    fn fault_without_handler_kills_the_task() {
+        let mut tasks = TaskTable::new();
+        let id = tasks.create_task().unwrap();
+        assert_eq!(tasks.handle_task_fault(id), FaultDisposition::Killed);
+    }
+
+    #[test]
+    fn parked_stack_pointers_round_trip() {
+        let mut tasks = TaskTable::new();
+        let a = tasks.create_task().unwrap();
+        // Zero until the task is first switched out.
+        assert_eq!(tasks.saved_stack(a), Some(0));
+        assert!(tasks.save_stack(a, 0x2000_0400));
+        assert_eq!(tasks.saved_stack(a), Some(0x2000_0400));
+        // Empty slots park nothing.
+        assert!(!tasks.save_stack(TaskId(7), 0x2000_0400));
+        assert_eq!(tasks.saved_stack(TaskId(7)), None);
+    }
+}
@@ -0,0 +1,243 @@
+//! Deadline-miss detection for periodic tasks.
+//!
+//! A task declared periodic is released every `period_ns` and must mark its
+//! activation complete before the next release. A periodic check on the
+//! kernel timer wheel invokes the registered overrun action once per missed
+//! deadline — a soft-real-time aid for logging and metrics: nothing is
+//! throttled or killed here (that is the heartbeat supervisor's job).
+
+use hal::{Machine, Machinelike};
+
+use crate::sched::task::TaskId;
+use crate::sync::SpinLock;
+
+/// Maximum number of tasks monitored at once.
+pub const MAX_PERIODIC: usize = 8;
+
+const NS_PER_MS: u64 = 1_000_000;
+
+/// Invoked (outside the monitor lock) once per missed deadline.
+pub type OverrunAction = fn(task: TaskId);
+
+struct Periodic {
+    task: TaskId,
+    period_ns: u64,
+    /// Deadline of the current activation — the next release time.
+    deadline_ns: u64,
+    /// Whether the current activation has completed.
+    completed: bool,
+    action: OverrunAction,
+}
+
+/// Monitor state. All logic lives on this type so host tests can drive a
+/// private instance with a mock clock.
+pub struct PeriodicMonitor {
+    slots: [Option<Periodic>; MAX_PERIODIC],
+}
+
+impl PeriodicMonitor {
+    pub const fn new() -> Self {
+        const NONE: Option<Periodic> = None;
+        Self {
+            slots: [NONE; MAX_PERIODIC],
+        }
+    }
+
+    /// Declares `task` periodic with the first release one period after
+    /// `now_ns`. Re-registering re-arms the task's slot in place. Returns
+    /// `false` for a zero period or when all slots are taken.
+    pub fn register(
+        &mut self,
+        task: TaskId,
+        period_ns: u64,
+        now_ns: u64,
+        action: OverrunAction,
+    ) -> bool {
+        if period_ns == 0 {
+            return false;
+        }
+        let slot = self
+            .slots
+            .iter()
+            .position(|s| matches!(s, Some(p) if p.task == task))
+            .or_else(|| self.slots.iter().position(|s| s.is_none()));
+        let Some(slot) = slot else {
+            return false;
+        };
+        self.slots[slot] = Some(Periodic {
+            task,
+            period_ns,
+            deadline_ns: now_ns + period_ns,
+            completed: false,
+            action,
+        });
+        true
+    }
+
+    /// Drops the periodic declaration for `task`. Unknown tasks are ignored.
+    pub fn unregister(&mut self, task: TaskId) {
+        for slot in &mut self.slots {
+            if matches!(slot, Some(p) if p.task == task) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Marks the current activation of `task` complete. Returns `false`
+    /// when `task` was never declared periodic.
+    pub fn complete(&mut self, task: TaskId) -> bool {
+        for periodic in self.slots.iter_mut().flatten() {
+            if periodic.task == task {
+                periodic.completed = true;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Advances every monitored task past the releases reached at `now_ns`,
+    /// counting each release whose activation did not complete as one miss.
+    /// The misses are returned instead of acted on so the caller can drop
+    /// the monitor lock before invoking the actions.
+    pub fn check(&mut self, now_ns: u64) -> [Option<(TaskId, u32, OverrunAction)>; MAX_PERIODIC] {
+        let mut overruns = [None; MAX_PERIODIC];
+        for (slot, entry) in self.slots.iter_mut().enumerate() {
+            let Some(periodic) = entry else {
+                continue;
+            };
+            let mut misses = 0;
+            while periodic.deadline_ns <= now_ns {
+                if !periodic.completed {
+                    misses += 1;
+                }
+                // A new release: a fresh activation begins.
+                periodic.completed = false;
+                periodic.deadline_ns += periodic.period_ns;
+            }
+            if misses > 0 {
+                overruns[slot] = Some((periodic.task, misses, periodic.action));
+            }
+        }
+        overruns
+    }
+}
+
+impl Default for PeriodicMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The global monitor.
+static MONITOR: SpinLock<PeriodicMonitor> = SpinLock::new(PeriodicMonitor::new());
+
+/// Fallback period of the monitor check when `OSIRIS_PERIODIC_CHECK_MS` is
+/// not configured.
+const DEFAULT_CHECK_MS: u64 = 10;
+
+/// Arms the periodic deadline check on the timer wheel.
+pub fn init() {
+    let ms = option_env!("OSIRIS_PERIODIC_CHECK_MS")
+        .and_then(|raw| raw.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_CHECK_MS);
+    let check_ns = ms * NS_PER_MS;
+    crate::time::arm(Machine::now_ns() + check_ns, Some(check_ns), check_overruns);
+}
+
+/// Declares the currently running task periodic with the given overrun
+/// action. Returns `false` when no task is current or no slot is free.
+pub fn register_current(period_ns: u64, action: OverrunAction) -> bool {
+    let Some(task) = crate::sched::with_tasks(|tasks| tasks.current()) else {
+        return false;
+    };
+    MONITOR.lock().register(task, period_ns, Machine::now_ns(), action)
+}
+
+/// Marks the current task's activation for this period complete.
+pub fn complete_current() -> bool {
+    let Some(task) = crate::sched::with_tasks(|tasks| tasks.current()) else {
+        return false;
+    };
+    MONITOR.lock().complete(task)
+}
+
+/// Timer-wheel callback: invokes each overrun action once per missed
+/// deadline, with the monitor lock already dropped.
+fn check_overruns(now_ns: u64) {
+    let overruns = MONITOR.lock().check(now_ns);
+    for (task, misses, action) in overruns.into_iter().flatten() {
+        for _ in 0..misses {
+            action(task);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Invocation count of [`count_overrun`]. Process-wide, so only the one
+    /// test below may touch it.
+    static FIRED: AtomicU32 = AtomicU32::new(0);
+
+    fn count_overrun(_task: TaskId) {
+        FIRED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn ignore_overrun(_task: TaskId) {}
+
+    /// Drives `monitor` the way the timer-wheel callback does: one action
+    /// invocation per miss.
+    fn run_check(monitor: &mut PeriodicMonitor, now_ns: u64) {
+        for (task, misses, action) in monitor.check(now_ns).into_iter().flatten() {
+            for _ in 0..misses {
+                action(task);
+            }
+        }
+    }
+
+    #[test]
+    fn overrun_action_fires_exactly_once_per_miss() {
+        let mut monitor = PeriodicMonitor::new();
+        let task = TaskId(1);
+        assert!(monitor.register(task, 100, 0, count_overrun));
+
+        // The first activation completes in time: its release is quiet.
+        assert!(monitor.complete(task));
+        run_check(&mut monitor, 100);
+        assert_eq!(FIRED.load(Ordering::SeqCst), 0);
+
+        // The next activation overruns: one invocation at the release...
+        run_check(&mut monitor, 150);
+        assert_eq!(FIRED.load(Ordering::SeqCst), 0);
+        run_check(&mut monitor, 200);
+        assert_eq!(FIRED.load(Ordering::SeqCst), 1);
+        // ...and not again while no new release has passed.
+        run_check(&mut monitor, 210);
+        assert_eq!(FIRED.load(Ordering::SeqCst), 1);
+
+        // Two further releases missed in one check: once per miss.
+        run_check(&mut monitor, 400);
+        assert_eq!(FIRED.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn registration_rearms_in_place_and_unregister_clears() {
+        let mut monitor = PeriodicMonitor::new();
+        let task = TaskId(3);
+        assert!(!monitor.register(task, 0, 0, ignore_overrun));
+        assert!(monitor.register(task, 100, 0, ignore_overrun));
+        // Re-registering re-arms the same slot rather than taking a second.
+        assert!(monitor.register(task, 200, 50, ignore_overrun));
+
+        assert!(monitor.check(240).iter().all(Option::is_none));
+        let misses: Vec<_> = monitor.check(250).into_iter().flatten().collect();
+        assert_eq!(misses.len(), 1);
+        assert_eq!((misses[0].0, misses[0].1), (task, 1));
+
+        monitor.unregister(task);
+        assert!(!monitor.complete(task));
+        assert!(monitor.check(1_000).iter().all(Option::is_none));
+    }
+}
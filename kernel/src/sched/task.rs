@@ -0,0 +1,119 @@
+//! Task records.
+
+use crate::uspace::TaskMemory;
+
+/// Identifies a task for the lifetime of the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(pub usize);
+
+/// Number of distinct priority levels the scheduler keeps ready queues for.
+/// Priorities are `0..PRIORITY_LEVELS`; setters clamp to [`MAX_PRIORITY`].
+pub const PRIORITY_LEVELS: usize = 16;
+
+/// The highest assignable priority.
+pub const MAX_PRIORITY: u8 = (PRIORITY_LEVELS - 1) as u8;
+
+/// Base priority given to tasks that don't ask for one. Higher values run
+/// first.
+pub const DEFAULT_PRIORITY: u8 = 8;
+
+/// Affinity mask allowing a task to run on any CPU.
+pub const AFFINITY_ANY: u8 = u8::MAX;
+
+/// Maximum number of wait objects a task can block on at once.
+pub const MAX_WAIT_OBJECTS: usize = 4;
+
+/// One condition a blocked task can wait for. A task blocking on several at
+/// once (select-style) wakes on whichever is satisfied first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitObject {
+    /// A mailbox (by index) becoming non-empty.
+    Mailbox(usize),
+    /// A point in time (ns since boot) passing.
+    Timeout(u64),
+    /// A semaphore (by index) becoming available.
+    Semaphore(usize),
+    /// A mutex (by table index) being released.
+    Mutex(usize),
+    /// A task (by slot index) exiting.
+    TaskExit(usize),
+}
+
+/// Lifecycle state of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    Ready,
+    Running,
+    Blocked,
+    Zombie,
+}
+
+/// One task's kernel-side record.
+#[derive(Debug)]
+pub struct Task {
+    pub id: TaskId,
+    pub state: TaskState,
+    pub memory: TaskMemory,
+    /// Userspace entry point to unwind to on a recoverable fault, installed
+    /// via `syscall_set_faulthandler`.
+    pub fault_handler: Option<usize>,
+    /// Set while the task is executing its fault handler; a fault in that
+    /// window kills the task.
+    pub in_fault_handler: bool,
+    /// Cumulative cycles this task has spent running, credited on context
+    /// switch.
+    pub cycles_run: u64,
+    /// Stack pointer parked by the context-switch layer while the task is
+    /// not running. Zero until the task has been switched out once.
+    pub saved_sp: usize,
+    /// What the task is blocked on, while in [`TaskState::Blocked`].
+    pub wait_set: [Option<WaitObject>; MAX_WAIT_OBJECTS],
+    /// The wait object that woke the task, until it blocks again.
+    pub wake_reason: Option<WaitObject>,
+    /// Scheduling priority the task was created with (higher runs first).
+    pub base_priority: u8,
+    /// Priority-inheritance boost, set while the task holds a lock that a
+    /// higher-priority task is blocked on.
+    pub boosted_priority: Option<u8>,
+    /// Bitmask of CPUs the task may run on (bit `i` = CPU `i`), from
+    /// `#[service(cpu = N)]`. Stored and validated on today's single-core
+    /// target; pick-next will consult it once multicore exists.
+    pub cpu_affinity: u8,
+    /// The code passed to `exit`, held by the zombie record until a waiter
+    /// reaps it via `waitpid`.
+    pub exit_code: Option<i32>,
+    /// Whether the task may act on other tasks (e.g. change their
+    /// priority). Granted by the kernel when spawning system services;
+    /// plain tasks are unprivileged.
+    pub privileged: bool,
+}
+
+impl Task {
+    pub fn new(id: TaskId) -> Self {
+        Self {
+            id,
+            state: TaskState::Ready,
+            memory: TaskMemory::new(),
+            fault_handler: None,
+            in_fault_handler: false,
+            cycles_run: 0,
+            saved_sp: 0,
+            wait_set: [None; MAX_WAIT_OBJECTS],
+            wake_reason: None,
+            base_priority: DEFAULT_PRIORITY,
+            boosted_priority: None,
+            cpu_affinity: AFFINITY_ANY,
+            exit_code: None,
+            privileged: false,
+        }
+    }
+
+    /// The priority the scheduler should act on: the base priority, unless a
+    /// priority-inheritance boost raises it.
+    pub fn effective_priority(&self) -> u8 {
+        match self.boosted_priority {
+            Some(boost) => boost.max(self.base_priority),
+            None => self.base_priority,
+        }
+    }
+}
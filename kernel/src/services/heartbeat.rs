@@ -0,0 +1,239 @@
+//! Service heartbeats: detects hung services and recovers them.
+//!
+//! A service registers an expectation to check in every `interval_ns` (via
+//! `syscall_heartbeat`); a periodic check on the kernel timer wheel flags
+//! every service that has gone silent past its interval. Depending on the
+//! registered policy a miss is only logged, or the service is unwound to its
+//! fault handler — the same recovery path a recoverable fault takes, so a
+//! restarted service comes back up through the entry it registered.
+
+use hal::{Machine, Machinelike};
+
+use crate::sched::task::TaskId;
+use crate::sched::FaultDisposition;
+use crate::sync::SpinLock;
+
+/// Maximum number of supervised services.
+pub const MAX_SERVICES: usize = 8;
+
+const NS_PER_MS: u64 = 1_000_000;
+
+/// What to do with a service that missed its heartbeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissPolicy {
+    /// Log the miss and keep the service running.
+    Log,
+    /// Unwind the service to its fault handler (killing it when it has none).
+    Restart,
+}
+
+struct Heartbeat {
+    task: TaskId,
+    interval_ns: u64,
+    deadline_ns: u64,
+    policy: MissPolicy,
+    /// Set once a miss was reported, so a silent service is flagged once per
+    /// silence instead of on every check.
+    missed: bool,
+}
+
+/// Supervisor state. All logic lives on this type so host tests can drive a
+/// private instance.
+pub struct Supervisor {
+    services: [Option<Heartbeat>; MAX_SERVICES],
+}
+
+impl Supervisor {
+    pub const fn new() -> Self {
+        const NONE: Option<Heartbeat> = None;
+        Self {
+            services: [NONE; MAX_SERVICES],
+        }
+    }
+
+    /// Registers (or re-arms) the heartbeat expectation for `task`, counting
+    /// from `now_ns`. Returns `false` when all slots are taken.
+    pub fn register(
+        &mut self,
+        task: TaskId,
+        interval_ns: u64,
+        policy: MissPolicy,
+        now_ns: u64,
+    ) -> bool {
+        let slot = self
+            .services
+            .iter()
+            .position(|s| matches!(s, Some(h) if h.task == task))
+            .or_else(|| self.services.iter().position(|s| s.is_none()));
+        let Some(slot) = slot else {
+            return false;
+        };
+        self.services[slot] = Some(Heartbeat {
+            task,
+            interval_ns,
+            deadline_ns: now_ns + interval_ns,
+            policy,
+            missed: false,
+        });
+        true
+    }
+
+    /// Drops the heartbeat expectation for `task`. Unknown tasks are ignored.
+    pub fn unregister(&mut self, task: TaskId) {
+        for slot in &mut self.services {
+            if matches!(slot, Some(h) if h.task == task) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Records a check-in, pushing the deadline out by one interval. Returns
+    /// `false` when `task` never registered.
+    pub fn checkin(&mut self, task: TaskId, now_ns: u64) -> bool {
+        for heartbeat in self.services.iter_mut().flatten() {
+            if heartbeat.task == task {
+                heartbeat.deadline_ns = now_ns + heartbeat.interval_ns;
+                heartbeat.missed = false;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Collects the services newly past their deadline at `now_ns`. Returned
+    /// instead of acted on so the caller can drop the supervisor lock first.
+    pub fn check(&mut self, now_ns: u64) -> [Option<(TaskId, MissPolicy)>; MAX_SERVICES] {
+        let mut misses = [None; MAX_SERVICES];
+        for (slot, entry) in self.services.iter_mut().enumerate() {
+            let Some(heartbeat) = entry else {
+                continue;
+            };
+            if heartbeat.missed || now_ns < heartbeat.deadline_ns {
+                continue;
+            }
+            heartbeat.missed = true;
+            misses[slot] = Some((heartbeat.task, heartbeat.policy));
+        }
+        misses
+    }
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The global supervisor.
+static SUPERVISOR: SpinLock<Supervisor> = SpinLock::new(Supervisor::new());
+
+/// Fallback period of the supervisor check when `OSIRIS_HEARTBEAT_CHECK_MS`
+/// is not configured.
+const DEFAULT_CHECK_MS: u64 = 100;
+
+/// Arms the periodic supervisor check on the timer wheel.
+pub fn init() {
+    let ms = option_env!("OSIRIS_HEARTBEAT_CHECK_MS")
+        .and_then(|raw| raw.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_CHECK_MS);
+    let check_ns = ms * NS_PER_MS;
+    crate::time::arm(Machine::now_ns() + check_ns, Some(check_ns), check_services);
+}
+
+/// Backend of `syscall_heartbeat` for the currently running task: a non-zero
+/// interval registers (or re-arms) the expectation, zero checks in.
+pub fn heartbeat_current(interval_ns: u64, policy: MissPolicy) -> bool {
+    let Some(task) = crate::sched::with_tasks(|tasks| tasks.current()) else {
+        return false;
+    };
+    let now_ns = Machine::now_ns();
+    let mut supervisor = SUPERVISOR.lock();
+    if interval_ns == 0 {
+        supervisor.checkin(task, now_ns)
+    } else {
+        supervisor.register(task, interval_ns, policy, now_ns)
+    }
+}
+
+/// Timer-wheel callback: flags every newly silent service and applies its
+/// miss policy.
+fn check_services(now_ns: u64) {
+    let misses = SUPERVISOR.lock().check(now_ns);
+    for (task, policy) in misses.into_iter().flatten() {
+        match policy {
+            MissPolicy::Log => {
+                crate::kprintln!("heartbeat: task {} missed its heartbeat", task.0);
+            }
+            MissPolicy::Restart => {
+                match crate::sched::with_tasks(|tasks| tasks.handle_task_fault(task)) {
+                    FaultDisposition::HandledBy(entry) => crate::kprintln!(
+                        "heartbeat: task {} missed its heartbeat, restarting at {:#x}",
+                        task.0,
+                        entry
+                    ),
+                    FaultDisposition::Killed => crate::kprintln!(
+                        "heartbeat: task {} missed its heartbeat and has no handler, killed",
+                        task.0
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sched::TaskTable;
+
+    #[test]
+    fn silent_service_is_flagged_once_and_recovers() {
+        let mut supervisor = Supervisor::new();
+        let chatty = TaskId(1);
+        let silent = TaskId(2);
+        assert!(supervisor.register(chatty, 100, MissPolicy::Log, 0));
+        assert!(supervisor.register(silent, 100, MissPolicy::Restart, 0));
+
+        // Both within their interval: nothing to report.
+        assert!(supervisor.check(50).iter().all(Option::is_none));
+
+        // Only one keeps checking in; the other is flagged, exactly once.
+        assert!(supervisor.checkin(chatty, 90));
+        let misses: Vec<_> = supervisor.check(120).into_iter().flatten().collect();
+        assert_eq!(misses, vec![(silent, MissPolicy::Restart)]);
+        // A miss is reported once per silence, not on every check.
+        assert!(supervisor.checkin(chatty, 180));
+        assert!(supervisor.check(200).iter().all(Option::is_none));
+        supervisor.unregister(chatty);
+
+        // A late check-in re-arms the expectation.
+        assert!(supervisor.checkin(silent, 250));
+        assert!(supervisor.check(300).iter().all(Option::is_none));
+        let misses: Vec<_> = supervisor.check(360).into_iter().flatten().collect();
+        assert_eq!(misses, vec![(silent, MissPolicy::Restart)]);
+
+        // Unregistered tasks neither check in nor get flagged.
+        supervisor.unregister(silent);
+        assert!(!supervisor.checkin(silent, 400));
+        assert!(supervisor.check(1_000).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn restart_policy_unwinds_to_the_fault_handler() {
+        let mut tasks = TaskTable::new();
+        let service = tasks.create_task().unwrap();
+        assert!(tasks.set_fault_handler(service, 0x800_1234));
+
+        let mut supervisor = Supervisor::new();
+        assert!(supervisor.register(service, 100, MissPolicy::Restart, 0));
+        let misses: Vec<_> = supervisor.check(150).into_iter().flatten().collect();
+        assert_eq!(misses, vec![(service, MissPolicy::Restart)]);
+
+        // Applying the policy takes the recoverable-fault path: the service
+        // is unwound to the restart entry it registered.
+        assert_eq!(
+            tasks.handle_task_fault(service),
+            FaultDisposition::HandledBy(0x800_1234)
+        );
+    }
+}
@@ -0,0 +1,4 @@
+//! Long-running kernel services.
+
+pub mod heartbeat;
+pub mod watchdog;
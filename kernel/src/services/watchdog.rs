@@ -0,0 +1,109 @@
+//! The software watchdog: resets the machine when periodic kicks stop.
+//!
+//! Enabled via the `OSIRIS_WATCHDOG_MS` config option; tasks kick it through
+//! `syscall_watchdog_kick`. A periodic timer on the kernel timer wheel checks
+//! the kick deadline and calls `Machine::reset()` once it is missed.
+
+use hal::{Machine, Machinelike};
+
+use crate::sync::SpinLock;
+
+const NS_PER_MS: u64 = 1_000_000;
+
+/// Watchdog state. All logic lives on this type so host tests can drive a
+/// private instance.
+pub struct Watchdog {
+    enabled: bool,
+    period_ns: u64,
+    deadline_ns: u64,
+}
+
+impl Watchdog {
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            period_ns: 0,
+            deadline_ns: 0,
+        }
+    }
+
+    /// Starts expecting a kick every `period_ns`, counting from `now_ns`.
+    pub fn enable(&mut self, period_ns: u64, now_ns: u64) {
+        self.enabled = true;
+        self.period_ns = period_ns;
+        self.deadline_ns = now_ns + period_ns;
+    }
+
+    /// Records a kick, pushing the deadline out by one period.
+    pub fn kick(&mut self, now_ns: u64) {
+        if self.enabled {
+            self.deadline_ns = now_ns + self.period_ns;
+        }
+    }
+
+    /// Whether the kick deadline has been missed.
+    pub fn expired(&self, now_ns: u64) -> bool {
+        self.enabled && now_ns >= self.deadline_ns
+    }
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The global watchdog.
+static WATCHDOG: SpinLock<Watchdog> = SpinLock::new(Watchdog::new());
+
+/// Enables the watchdog when `OSIRIS_WATCHDOG_MS` is configured, arming the
+/// periodic deadline check on the timer wheel.
+pub fn init() {
+    let Some(ms) = option_env!("OSIRIS_WATCHDOG_MS").and_then(|raw| raw.parse::<u64>().ok())
+    else {
+        return;
+    };
+    let period_ns = ms * NS_PER_MS;
+    let now_ns = Machine::now_ns();
+    WATCHDOG.lock().enable(period_ns, now_ns);
+    // Check at half the kick period so a miss is caught within one period.
+    let check_ns = (period_ns / 2).max(NS_PER_MS);
+    crate::time::arm(now_ns + check_ns, Some(check_ns), check_deadline);
+}
+
+/// Backend of `syscall_watchdog_kick`.
+pub fn kick() {
+    WATCHDOG.lock().kick(Machine::now_ns());
+}
+
+/// Timer-wheel callback: resets the machine on a missed kick.
+fn check_deadline(now_ns: u64) {
+    if WATCHDOG.lock().expired(now_ns) {
+        Machine::flush().ok();
+        Machine::reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missed_kick_expires_timely_kick_does_not() {
+        let mut watchdog = Watchdog::new();
+        watchdog.enable(100 * NS_PER_MS, 0);
+
+        // A timely kick keeps the reset hook quiet...
+        watchdog.kick(90 * NS_PER_MS);
+        assert!(!watchdog.expired(150 * NS_PER_MS));
+
+        // ...but going silent past the deadline triggers it.
+        assert!(watchdog.expired(190 * NS_PER_MS));
+    }
+
+    #[test]
+    fn disabled_watchdog_never_expires() {
+        let watchdog = Watchdog::new();
+        assert!(!watchdog.expired(u64::MAX));
+    }
+}
@@ -0,0 +1,206 @@
+//! Deferred interrupt work (softirqs).
+//!
+//! Interrupt handlers stay minimal by enqueuing the bulk of their work here;
+//! the scheduler drains the queue after interrupt handling, right before it
+//! picks the next task. The queue is a fixed array — no heap — and enqueuing
+//! is lock-free, so it is safe from (possibly nested) interrupt context.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Maximum number of deferred work items pending at once.
+pub const MAX_PENDING: usize = 16;
+
+/// A deferred work item: the function to run and the token the raising ISR
+/// passed along (a device index, a buffer slot, ...). State beyond the token
+/// must live in globals.
+pub type SoftirqFn = fn(token: usize);
+
+/// Errors from raising deferred work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoftirqError {
+    /// All [`MAX_PENDING`] slots are taken; the work was dropped.
+    Full,
+}
+
+/// One queue slot. `ready` flips to true only after the claiming producer
+/// finished writing `work`, so the consumer never reads a half-written slot.
+struct SoftirqSlot {
+    ready: AtomicBool,
+    work: UnsafeCell<Option<(SoftirqFn, usize)>>,
+}
+
+impl SoftirqSlot {
+    const fn new() -> Self {
+        Self {
+            ready: AtomicBool::new(false),
+            work: UnsafeCell::new(None),
+        }
+    }
+}
+
+/// The bounded deferred-work queue. Producers (ISRs) are lock-free and may
+/// nest; there must be exactly one consumer — the scheduler pass. All logic
+/// lives on this type so host tests can drive a private instance.
+pub struct SoftirqQueue<const N: usize> {
+    slots: [SoftirqSlot; N],
+    /// Next slot to drain. Written only by the consumer.
+    head: AtomicUsize,
+    /// Next slot to fill. Claimed by producers with a compare-exchange.
+    tail: AtomicUsize,
+}
+
+// SAFETY: slot contents are only written by the producer that claimed the
+// slot and only read by the consumer after `ready` was published.
+unsafe impl<const N: usize> Sync for SoftirqQueue<N> {}
+
+impl<const N: usize> SoftirqQueue<N> {
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { SoftirqSlot::new() }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Enqueues `work(token)` to run in the next scheduler pass. Lock-free:
+    /// a slot is claimed by advancing `tail`, so a preempting ISR claims the
+    /// following slot instead of racing for the same one.
+    pub fn raise(&self, work: SoftirqFn, token: usize) -> Result<(), SoftirqError> {
+        loop {
+            let tail = self.tail.load(Ordering::Relaxed);
+            if tail.wrapping_sub(self.head.load(Ordering::Acquire)) >= N {
+                return Err(SoftirqError::Full);
+            }
+            if self
+                .tail
+                .compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            {
+                let slot = &self.slots[tail % N];
+                // SAFETY: the compare-exchange claimed this slot exclusively,
+                // and the capacity check above proved the consumer is done
+                // with its previous contents.
+                unsafe { *slot.work.get() = Some((work, token)) };
+                slot.ready.store(true, Ordering::Release);
+                return Ok(());
+            }
+        }
+    }
+
+    /// Runs all pending work in FIFO order, returning how many items ran.
+    /// Must only be called from the single consumer context. Work raised
+    /// while draining (including by the work itself) runs in the same pass.
+    pub fn run_pending(&self) -> usize {
+        let mut ran = 0;
+        loop {
+            let head = self.head.load(Ordering::Relaxed);
+            if head == self.tail.load(Ordering::Acquire) {
+                break;
+            }
+            let slot = &self.slots[head % N];
+            if !slot.ready.load(Ordering::Acquire) {
+                // The claiming ISR was preempted mid-enqueue; its item runs
+                // in the next pass.
+                break;
+            }
+            // SAFETY: `ready` proves the producer's write completed, and the
+            // single consumer is the only reader.
+            let work = unsafe { (*slot.work.get()).take() };
+            slot.ready.store(false, Ordering::Release);
+            self.head.store(head.wrapping_add(1), Ordering::Release);
+            // The slot is released before the work runs so the work may
+            // raise follow-up items without deadlocking on a full queue.
+            if let Some((work, token)) = work {
+                work(token);
+                ran += 1;
+            }
+        }
+        ran
+    }
+}
+
+impl<const N: usize> Default for SoftirqQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The global deferred-work queue.
+static SOFTIRQS: SoftirqQueue<MAX_PENDING> = SoftirqQueue::new();
+
+/// Enqueues deferred work from interrupt context.
+pub fn raise(work: SoftirqFn, token: usize) -> Result<(), SoftirqError> {
+    SOFTIRQS.raise(work, token)
+}
+
+/// Drains the global queue; called by the scheduler before picking the next
+/// task.
+pub fn run_pending() -> usize {
+    SOFTIRQS.run_pending()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static RAN: AtomicUsize = AtomicUsize::new(0);
+    static LAST_TOKEN: AtomicUsize = AtomicUsize::new(0);
+
+    fn record(token: usize) {
+        RAN.fetch_add(1, Ordering::SeqCst);
+        LAST_TOKEN.store(token, Ordering::SeqCst);
+    }
+
+    // One test: the recording statics are process-wide.
+    #[test]
+    fn isr_raised_work_runs_in_the_scheduler_pass() {
+        let queue = SoftirqQueue::<4>::new();
+
+        // A simulated ISR defers two items; nothing runs until the pass.
+        queue.raise(record, 7).unwrap();
+        queue.raise(record, 9).unwrap();
+        assert_eq!(RAN.load(Ordering::SeqCst), 0);
+
+        assert_eq!(queue.run_pending(), 2);
+        assert_eq!(RAN.load(Ordering::SeqCst), 2);
+        // FIFO: the last item to run carries the last token raised.
+        assert_eq!(LAST_TOKEN.load(Ordering::SeqCst), 9);
+
+        // The drained slots are reusable.
+        queue.raise(record, 1).unwrap();
+        assert_eq!(queue.run_pending(), 1);
+    }
+
+    #[test]
+    fn full_queue_rejects_instead_of_overwriting() {
+        fn nop(_token: usize) {}
+
+        let queue = SoftirqQueue::<2>::new();
+        queue.raise(nop, 0).unwrap();
+        queue.raise(nop, 1).unwrap();
+        assert_eq!(queue.raise(nop, 2), Err(SoftirqError::Full));
+
+        // Draining frees the slots again.
+        assert_eq!(queue.run_pending(), 2);
+        assert_eq!(queue.raise(nop, 3), Ok(()));
+    }
+
+    #[test]
+    fn work_raised_while_draining_runs_in_the_same_pass() {
+        fn chain(token: usize) {
+            if token > 0 {
+                super::raise(chain, token - 1).unwrap();
+            }
+        }
+
+        // Uses the global queue: `chain` must reach it without a handle.
+        raise(chain, 3).unwrap();
+        assert_eq!(run_pending(), 4);
+    }
+}
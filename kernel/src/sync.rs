@@ -0,0 +1,463 @@
+//! Kernel synchronization primitives.
+
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::sched::task::{TaskId, WaitObject};
+use crate::sched::TaskTable;
+
+/// A spin lock. On the single-core targets Osiris currently supports,
+/// contention only arises against interrupt handlers, so hold times must be
+/// short.
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the lock serializes all access to the inner value.
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+unsafe impl<T: Send> Send for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Acquires the lock, spinning until it is free.
+    pub fn lock(&self) -> SpinLockGuard<'_, T> {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        SpinLockGuard { lock: self }
+    }
+}
+
+/// Guard releasing the lock on drop.
+pub struct SpinLockGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+impl<T> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard proves exclusive access.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for SpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard proves exclusive access.
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for SpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+/// Writer-held bit of the [`RwLock`] state word.
+const WRITER: usize = 1 << (usize::BITS - 1);
+/// Writer-waiting bit: while set, no new readers are admitted.
+const WRITER_WAITING: usize = 1 << (usize::BITS - 2);
+/// The remaining bits count active readers.
+const READER_MASK: usize = WRITER_WAITING - 1;
+
+/// A spinning reader-writer lock: any number of readers, or one writer.
+///
+/// Fairness: a waiting writer sets [`WRITER_WAITING`], which stops new
+/// readers from being admitted, so writers cannot starve under a continuous
+/// stream of readers. Like [`SpinLock`], hold times must be short — the only
+/// contention on the current single-core targets comes from interrupt
+/// handlers.
+pub struct RwLock<T> {
+    state: AtomicUsize,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the state word serializes writers against readers and each other.
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+unsafe impl<T: Send> Send for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Takes a read guard if no writer holds or awaits the lock.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        let state = self.state.load(Ordering::Relaxed);
+        if state & (WRITER | WRITER_WAITING) != 0 {
+            return None;
+        }
+        self.state
+            .compare_exchange(state, state + 1, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| RwLockReadGuard { lock: self })
+    }
+
+    /// Acquires a read guard, spinning while a writer holds or awaits the
+    /// lock.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_read() {
+                return guard;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Takes the write guard if there are no readers and no writer.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        let state = self.state.load(Ordering::Relaxed);
+        if state & (READER_MASK | WRITER) != 0 {
+            return None;
+        }
+        self.state
+            .compare_exchange(state, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| RwLockWriteGuard { lock: self })
+    }
+
+    /// Acquires the write guard, announcing intent first so the reader path
+    /// stops admitting new readers while this writer drains the existing
+    /// ones.
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        loop {
+            self.state.fetch_or(WRITER_WAITING, Ordering::Relaxed);
+            if let Some(guard) = self.try_write() {
+                return guard;
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Shared-access guard; the reader count drops with it.
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the reader count keeps writers out.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Exclusive-access guard; the writer bit clears with it.
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the writer bit proves exclusive access.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the writer bit proves exclusive access.
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.fetch_and(!WRITER, Ordering::Release);
+    }
+}
+
+/// A blocking, task-aware mutex with priority inheritance.
+///
+/// While a higher-priority task waits for the lock, the holder's effective
+/// priority is boosted to the waiter's, so a medium-priority task cannot run
+/// in between and stretch the critical section (classic priority inversion).
+/// The boost is dropped on release, which wakes every waiter to retry
+/// [`Self::acquire`].
+pub struct PriorityMutex {
+    /// The wait object index contended tasks block on.
+    index: usize,
+    owner: Option<TaskId>,
+}
+
+impl PriorityMutex {
+    pub const fn new(index: usize) -> Self {
+        Self { index, owner: None }
+    }
+
+    /// The task currently holding the mutex, if any.
+    pub fn owner(&self) -> Option<TaskId> {
+        self.owner
+    }
+
+    /// Takes the mutex for `task` if it is free, returning `true`. On
+    /// contention the task is blocked on the mutex and the current owner
+    /// inherits the waiter's effective priority; the caller must retry once
+    /// woken.
+    pub fn acquire(&mut self, tasks: &mut TaskTable, task: TaskId) -> bool {
+        match self.owner {
+            None => {
+                self.owner = Some(task);
+                true
+            }
+            Some(owner) => {
+                BUG_ON!(owner == task, "recursive mutex acquire");
+                if let Some(waiter_priority) = tasks.effective_priority(task) {
+                    tasks.boost_priority(owner, waiter_priority);
+                }
+                tasks.block_on_any(task, &[WaitObject::Mutex(self.index)]);
+                false
+            }
+        }
+    }
+
+    /// Releases the mutex held by `task`, dropping its inheritance boost and
+    /// waking all waiters to contend again.
+    pub fn release(&mut self, tasks: &mut TaskTable, task: TaskId) {
+        BUG_ON!(self.owner != Some(task), "mutex released by non-owner");
+        self.owner = None;
+        tasks.clear_boost(task);
+        tasks.notify(WaitObject::Mutex(self.index));
+    }
+}
+
+/// Number of kernel mutexes addressable from userspace by id.
+pub const MAX_USER_MUTEXES: usize = 8;
+
+/// Why a userspace mutex operation was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutexError {
+    /// The id does not address a slot of the fixed mutex table.
+    InvalidId,
+    /// The caller already holds the mutex; blocking on it could never end.
+    AlreadyHeld,
+    /// Only the owning task may unlock a mutex.
+    NotOwner,
+}
+
+/// The fixed table of kernel mutexes userspace addresses by id through
+/// `mutex_lock` / `mutex_unlock`. Tasks in different address spaces cannot
+/// share a [`SpinLock`], so the kernel mediates: contended lockers block in
+/// the scheduler and the holder inherits their priority. All logic lives on
+/// this type so host tests can drive a private instance.
+pub struct UserMutexes {
+    mutexes: [PriorityMutex; MAX_USER_MUTEXES],
+}
+
+impl UserMutexes {
+    pub const fn new() -> Self {
+        let mut mutexes = [const { PriorityMutex::new(0) }; MAX_USER_MUTEXES];
+        let mut id = 0;
+        while id < MAX_USER_MUTEXES {
+            mutexes[id] = PriorityMutex::new(id);
+            id += 1;
+        }
+        Self { mutexes }
+    }
+
+    /// Locks mutex `id` for `task`. `Ok(true)` means the lock was taken;
+    /// `Ok(false)` means the task blocked behind the owner (which inherited
+    /// the waiter's priority) and must retry once woken.
+    pub fn lock(
+        &mut self,
+        tasks: &mut TaskTable,
+        task: TaskId,
+        id: usize,
+    ) -> Result<bool, MutexError> {
+        let mutex = self.mutexes.get_mut(id).ok_or(MutexError::InvalidId)?;
+        if mutex.owner() == Some(task) {
+            return Err(MutexError::AlreadyHeld);
+        }
+        Ok(mutex.acquire(tasks, task))
+    }
+
+    /// Unlocks mutex `id`, which must be held by `task`.
+    pub fn unlock(
+        &mut self,
+        tasks: &mut TaskTable,
+        task: TaskId,
+        id: usize,
+    ) -> Result<(), MutexError> {
+        let mutex = self.mutexes.get_mut(id).ok_or(MutexError::InvalidId)?;
+        if mutex.owner() != Some(task) {
+            return Err(MutexError::NotOwner);
+        }
+        mutex.release(tasks, task);
+        Ok(())
+    }
+}
+
+impl Default for UserMutexes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The global userspace mutex table. Always taken after the task table, never
+/// before, so the two locks cannot deadlock.
+static USER_MUTEXES: SpinLock<UserMutexes> = SpinLock::new(UserMutexes::new());
+
+/// Runs `f` with the global userspace mutex table locked.
+pub fn with_user_mutexes<R>(f: impl FnOnce(&mut UserMutexes) -> R) -> R {
+    f(&mut USER_MUTEXES.lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sched::task::TaskState;
+
+    #[test]
+    fn spinlock_serializes_access() {
+        let lock = SpinLock::new(0u32);
+        *lock.lock() += 1;
+        *lock.lock() += 1;
+        assert_eq!(*lock.lock(), 2);
+    }
+
+    #[test]
+    fn rwlock_admits_concurrent_readers() {
+        let lock = RwLock::new(7u32);
+        let first = lock.read();
+        let second = lock.try_read().expect("readers must coexist");
+        assert_eq!((*first, *second), (7, 7));
+    }
+
+    #[test]
+    fn rwlock_writer_excludes_readers_and_vice_versa() {
+        let lock = RwLock::new(0u32);
+        {
+            let mut writer = lock.write();
+            *writer = 5;
+            assert!(lock.try_read().is_none());
+            assert!(lock.try_write().is_none());
+        }
+        // Released: readers come back and see the write.
+        let reader = lock.read();
+        assert_eq!(*reader, 5);
+        assert!(lock.try_write().is_none());
+        drop(reader);
+        assert!(lock.try_write().is_some());
+    }
+
+    #[test]
+    fn rwlock_waiting_writer_blocks_new_readers() {
+        let lock = RwLock::new(0u32);
+        let reader = lock.read();
+        // Announce a writer the way `write` does: new readers must back off
+        // even though the lock is still only read-held.
+        lock.state.fetch_or(super::WRITER_WAITING, Ordering::Relaxed);
+        assert!(lock.try_read().is_none());
+        drop(reader);
+        assert!(lock.try_write().is_some());
+    }
+
+    #[test]
+    fn priority_inheritance_resolves_classic_inversion() {
+        let mut tasks = TaskTable::new();
+        let low = tasks.create_task().unwrap();
+        let medium = tasks.create_task().unwrap();
+        let high = tasks.create_task().unwrap();
+        tasks.set_priority(low, 1);
+        tasks.set_priority(medium, 5);
+        tasks.set_priority(high, 9);
+
+        let mut mutex = PriorityMutex::new(0);
+        assert!(mutex.acquire(&mut tasks, low));
+
+        // The high-priority task contends: it blocks and the holder inherits
+        // its priority, so the medium task can no longer preempt the holder.
+        assert!(!mutex.acquire(&mut tasks, high));
+        assert_eq!(tasks.task(high).unwrap().state, TaskState::Blocked);
+        assert!(
+            tasks.effective_priority(low).unwrap() > tasks.effective_priority(medium).unwrap()
+        );
+
+        // Release: the boost is dropped and the waiter wakes and acquires.
+        mutex.release(&mut tasks, low);
+        assert_eq!(tasks.effective_priority(low).unwrap(), 1);
+        assert_eq!(tasks.task(high).unwrap().state, TaskState::Ready);
+        assert!(mutex.acquire(&mut tasks, high));
+    }
+
+    #[test]
+    fn user_mutexes_track_ownership_and_wake_contenders() {
+        let mut tasks = TaskTable::new();
+        let mut mutexes = UserMutexes::new();
+        let a = tasks.create_task().unwrap();
+        let b = tasks.create_task().unwrap();
+
+        // a takes mutex 0; b contends and blocks on it.
+        assert_eq!(mutexes.lock(&mut tasks, a, 0), Ok(true));
+        assert_eq!(mutexes.lock(&mut tasks, b, 0), Ok(false));
+        assert_eq!(tasks.task(b).unwrap().state, TaskState::Blocked);
+
+        // Unlock by the non-owner is refused and wakes nobody.
+        assert_eq!(mutexes.unlock(&mut tasks, b, 0), Err(MutexError::NotOwner));
+        assert_eq!(tasks.task(b).unwrap().state, TaskState::Blocked);
+
+        // The owner unlocks: b wakes and its retried lock succeeds.
+        assert_eq!(mutexes.unlock(&mut tasks, a, 0), Ok(()));
+        assert_eq!(tasks.task(b).unwrap().state, TaskState::Ready);
+        assert_eq!(mutexes.lock(&mut tasks, b, 0), Ok(true));
+    }
+
+    #[test]
+    fn user_mutex_slots_are_independent() {
+        let mut tasks = TaskTable::new();
+        let mut mutexes = UserMutexes::new();
+        let a = tasks.create_task().unwrap();
+        let b = tasks.create_task().unwrap();
+
+        assert_eq!(mutexes.lock(&mut tasks, a, 0), Ok(true));
+        assert_eq!(mutexes.lock(&mut tasks, b, 1), Ok(true));
+        assert_eq!(tasks.task(b).unwrap().state, TaskState::Ready);
+    }
+
+    #[test]
+    fn user_mutex_rejects_bad_ids_and_relocking() {
+        let mut tasks = TaskTable::new();
+        let mut mutexes = UserMutexes::new();
+        let a = tasks.create_task().unwrap();
+
+        assert_eq!(
+            mutexes.lock(&mut tasks, a, MAX_USER_MUTEXES),
+            Err(MutexError::InvalidId)
+        );
+        assert_eq!(
+            mutexes.unlock(&mut tasks, a, MAX_USER_MUTEXES),
+            Err(MutexError::InvalidId)
+        );
+
+        assert_eq!(mutexes.lock(&mut tasks, a, 0), Ok(true));
+        // A recursive lock would block the owner on itself forever.
+        assert_eq!(mutexes.lock(&mut tasks, a, 0), Err(MutexError::AlreadyHeld));
+    }
+}
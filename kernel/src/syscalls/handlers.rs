@@ -0,0 +1,263 @@
+//! Kernel-side syscall handlers and their numbers.
+
+use core::ffi::c_uint;
+use hal::{Machine, Machinelike};
+
+syscall!(exit, EXIT_NUM = 0, EXIT_ARGS = 1, |args: *const c_uint| {
+    let code = unsafe { *args } as i32;
+    // The task becomes a zombie holding the code until a waiter reaps it;
+    // the svc return path schedules away from it for good.
+    crate::sched::with_tasks(|tasks| {
+        if let Some(id) = tasks.current() {
+            tasks.exit_task(id, code);
+        }
+    });
+    0
+});
+
+syscall!(
+    waitpid,
+    WAITPID_NUM = 9,
+    WAITPID_ARGS = 1,
+    |args: *const c_uint| {
+        let target = crate::sched::task::TaskId(unsafe { *args } as usize);
+        crate::sched::with_tasks(|tasks| {
+            let Some(waiter) = tasks.current() else {
+                return -1;
+            };
+            match tasks.wait_for_exit(waiter, target) {
+                // The child already exited: its record is reaped and the
+                // code comes back directly.
+                Ok(Some(code)) => code,
+                // Blocked until the child exits; the svc return path
+                // reschedules, and the retried syscall finds the zombie.
+                Ok(None) => 0,
+                Err(_) => -1,
+            }
+        })
+    }
+);
+
+syscall!(
+    mutex_lock,
+    MUTEX_LOCK_NUM = 10,
+    MUTEX_LOCK_ARGS = 1,
+    |args: *const c_uint| {
+        let id = unsafe { *args } as usize;
+        crate::sched::with_tasks(|tasks| {
+            let Some(task) = tasks.current() else {
+                return -1;
+            };
+            match crate::sync::with_user_mutexes(|mutexes| mutexes.lock(tasks, task, id)) {
+                // The lock was free and is now held.
+                Ok(true) => 0,
+                // Blocked behind the owner; the svc return path reschedules,
+                // and the woken task must retry the syscall.
+                Ok(false) => 1,
+                Err(_) => -1,
+            }
+        })
+    }
+);
+
+syscall!(
+    mutex_unlock,
+    MUTEX_UNLOCK_NUM = 11,
+    MUTEX_UNLOCK_ARGS = 1,
+    |args: *const c_uint| {
+        let id = unsafe { *args } as usize;
+        crate::sched::with_tasks(|tasks| {
+            let Some(task) = tasks.current() else {
+                return -1;
+            };
+            match crate::sync::with_user_mutexes(|mutexes| mutexes.unlock(tasks, task, id)) {
+                Ok(()) => 0,
+                // Bad id, or the caller does not own the mutex.
+                Err(_) => -1,
+            }
+        })
+    }
+);
+
+syscall!(
+    setpriority,
+    SETPRIORITY_NUM = 12,
+    SETPRIORITY_ARGS = 2,
+    |args: *const c_uint| {
+        let (target, priority) = unsafe { (*args as usize, *args.add(1)) };
+        // Reject before narrowing so 256 does not alias priority 0.
+        if priority > crate::sched::task::MAX_PRIORITY as c_uint {
+            return -1;
+        }
+        crate::sched::with_tasks(|tasks| {
+            let Some(requester) = tasks.current() else {
+                return -1;
+            };
+            let target = crate::sched::task::TaskId(target);
+            match tasks.change_priority(requester, target, priority as u8) {
+                // The change takes effect at the reschedule on svc return.
+                Ok(()) => 0,
+                Err(_) => -1,
+            }
+        })
+    }
+);
+
+syscall!(
+    nanosleep,
+    NANOSLEEP_NUM = 13,
+    NANOSLEEP_ARGS = 2,
+    |args: *const c_uint| {
+        // 64-bit nanoseconds split across two argument words, so long sleeps
+        // don't hit the overflow of a 32-bit ms-based interval.
+        let (lo, hi) = unsafe { (*args as u64, *args.add(1) as u64) };
+        let ns = hi << 32 | lo;
+        // Zero behaves like yield: rescheduling happens on svc return.
+        if ns == 0 {
+            return 0;
+        }
+        let now_ns = Machine::now_ns();
+        crate::sched::with_tasks(|tasks| {
+            let Some(id) = tasks.current() else {
+                return -1;
+            };
+            let Some(deadline) = tasks.sleep(id, now_ns, ns) else {
+                return 0;
+            };
+            // The wheel wakes the sleeper at (or after) the deadline. Arm
+            // before reporting success; a full wheel must fail rather than
+            // sleep forever.
+            if crate::time::arm(deadline, None, crate::sched::wake_expired_tasks).is_none() {
+                tasks.notify(crate::sched::task::WaitObject::Timeout(deadline));
+                return -1;
+            }
+            // Nothing can interrupt a sleep yet; once other wake sources
+            // exist, an interrupted sleep reports the remaining time here.
+            0
+        })
+    }
+);
+
+syscall!(r#yield, YIELD_NUM = 1, YIELD_ARGS = 0, |_args: *const c_uint| {
+    // Rescheduling happens on return from the svc exception.
+    0
+});
+
+syscall!(
+    watchdog_kick,
+    WATCHDOG_KICK_NUM = 3,
+    WATCHDOG_KICK_ARGS = 0,
+    |_args: *const c_uint| {
+        crate::services::watchdog::kick();
+        0
+    }
+);
+
+syscall!(
+    mempeak,
+    MEMPEAK_NUM = 4,
+    MEMPEAK_ARGS = 0,
+    |_args: *const c_uint| {
+        // Peak heap usage in bytes, clamped to the return type's range.
+        crate::mem::with_heap(|heap| heap.peak_used()).min(i32::MAX as usize) as i32
+    }
+);
+
+syscall!(log, LOG_NUM = 5, LOG_ARGS = 3, |args: *const c_uint| {
+    let (raw_level, ptr, len) = unsafe {
+        (
+            *args,
+            *args.add(1) as usize as *const u8,
+            *args.add(2) as usize,
+        )
+    };
+    let Some(level) = crate::print::LogLevel::from_u32(raw_level) else {
+        return -1;
+    };
+    let valid = crate::sched::with_tasks(|tasks| {
+        let task = tasks.current().and_then(|id| tasks.task(id));
+        match task {
+            Some(task) => crate::uspace::validate_user_slice(&task.memory, ptr, len).is_ok(),
+            None => false,
+        }
+    });
+    if !valid {
+        return -1;
+    }
+    // SAFETY: the slice was just validated against the task's segments.
+    let bytes = unsafe { core::slice::from_raw_parts(ptr, len) };
+    let Ok(msg) = core::str::from_utf8(bytes) else {
+        return -1;
+    };
+    let task = crate::sched::with_tasks(|tasks| tasks.current().map(|id| id.0)).unwrap_or(0);
+    crate::print::log_line(task, level, msg);
+    0
+});
+
+syscall!(
+    heartbeat,
+    HEARTBEAT_NUM = 6,
+    HEARTBEAT_ARGS = 2,
+    |args: *const c_uint| {
+        let (interval_ms, restart) = unsafe { (*args as u64, *args.add(1) != 0) };
+        let policy = if restart {
+            crate::services::heartbeat::MissPolicy::Restart
+        } else {
+            crate::services::heartbeat::MissPolicy::Log
+        };
+        // A zero interval is a check-in; anything else (re-)registers.
+        if crate::services::heartbeat::heartbeat_current(interval_ms * 1_000_000, policy) {
+            0
+        } else {
+            -1
+        }
+    }
+);
+
+syscall!(
+    heapcheck,
+    HEAPCHECK_NUM = 7,
+    HEAPCHECK_ARGS = 0,
+    |_args: *const c_uint| {
+        // Debug aid: walks the heap's free list looking for clobbered
+        // headers, so corruption surfaces where it happened instead of at
+        // the next allocation.
+        match crate::mem::with_heap(|heap| heap.check_integrity()) {
+            Ok(()) => 0,
+            Err(_) => -1,
+        }
+    }
+);
+
+syscall!(
+    schedstats,
+    SCHEDSTATS_NUM = 8,
+    SCHEDSTATS_ARGS = 1,
+    |args: *const c_uint| {
+        let which = unsafe { *args };
+        let stats = crate::sched::stats();
+        // One counter per call, selected by the argument; each is clamped to
+        // the return type's range like mempeak.
+        let value = match which {
+            0 => stats.context_switches,
+            1 => stats.idle_cycles,
+            2 => stats.reschedules,
+            _ => return -1,
+        };
+        value.min(i32::MAX as u64) as i32
+    }
+);
+
+syscall!(
+    set_faulthandler,
+    SET_FAULTHANDLER_NUM = 2,
+    SET_FAULTHANDLER_ARGS = 1,
+    |args: *const c_uint| {
+        let entry = unsafe { *args } as usize;
+        if crate::sched::set_current_fault_handler(entry) {
+            0
+        } else {
+            -1
+        }
+    }
+);
@@ -0,0 +1,139 @@
+//! Syscall numbering, argument decoding and dispatch.
+//!
+//! Each syscall is declared through [`syscall!`], which generates an
+//! `extern "C"` wrapper decoding the raw argument words plus `<NAME>_NUM` /
+//! `<NAME>_ARGS` constants. The declarations are collected into a const
+//! dispatch table indexed by syscall number.
+
+use core::ffi::{c_int, c_uint};
+
+/// Maximum number of syscall slots in the dispatch table.
+pub const MAX_SYSCALLS: usize = 32;
+
+/// Maximum number of raw argument words passed from the svc stub.
+pub const MAX_ARGS: usize = 4;
+
+/// Returned for a syscall number with no registered handler.
+pub const ENOSYS: c_int = -38;
+
+/// One entry of the dispatch table.
+#[derive(Clone, Copy)]
+pub struct SyscallEntry {
+    /// The generated wrapper for the handler.
+    pub handler: extern "C" fn(*const c_uint) -> c_int,
+    /// Number of argument words the handler consumes.
+    pub args: usize,
+}
+
+/// Declares a syscall: its number, its raw arity and the wrapper body that
+/// decodes the argument words and calls the kernel-side handler.
+///
+/// The `<NAME>_NUM` / `<NAME>_ARGS` constant names are spelled out by the
+/// caller since `macro_rules!` cannot concatenate identifiers.
+#[macro_export]
+macro_rules! syscall {
+    ($name:ident, $num_const:ident = $num:expr, $args_const:ident = $args:expr, $wrapper:expr) => {
+        pub const $num_const: u8 = $num;
+        pub const $args_const: usize = $args;
+
+        pub extern "C" fn $name(args: *const core::ffi::c_uint) -> core::ffi::c_int {
+            let wrapper: fn(*const core::ffi::c_uint) -> core::ffi::c_int = $wrapper;
+            wrapper(args)
+        }
+    };
+}
+
+/// Builds a `[Option<SyscallEntry>; MAX_SYSCALLS]` table from a list of
+/// `number => (wrapper, args)` pairs, verifying numbers are in range and
+/// unique and that no handler exceeds [`MAX_ARGS`] at compile time.
+#[macro_export]
+macro_rules! syscall_table {
+    ($($num:expr => ($handler:path, $args:expr)),* $(,)?) => {{
+        let mut table: [Option<$crate::syscalls::SyscallEntry>; $crate::syscalls::MAX_SYSCALLS] =
+            [None; $crate::syscalls::MAX_SYSCALLS];
+        $(
+            assert!(($num as usize) < $crate::syscalls::MAX_SYSCALLS, "syscall number out of range");
+            assert!(($args as usize) <= $crate::syscalls::MAX_ARGS, "syscall arity exceeds MAX_ARGS");
+            assert!(table[$num as usize].is_none(), "duplicate syscall number");
+            table[$num as usize] = Some($crate::syscalls::SyscallEntry {
+                handler: $handler,
+                args: $args,
+            });
+        )*
+        table
+    }};
+}
+
+pub mod handlers;
+
+/// The kernel's dispatch table.
+pub static SYSCALL_TABLE: [Option<SyscallEntry>; MAX_SYSCALLS] = syscall_table! {
+    handlers::EXIT_NUM => (handlers::exit, handlers::EXIT_ARGS),
+    handlers::YIELD_NUM => (handlers::r#yield, handlers::YIELD_ARGS),
+    handlers::SET_FAULTHANDLER_NUM => (handlers::set_faulthandler, handlers::SET_FAULTHANDLER_ARGS),
+    handlers::WATCHDOG_KICK_NUM => (handlers::watchdog_kick, handlers::WATCHDOG_KICK_ARGS),
+    handlers::MEMPEAK_NUM => (handlers::mempeak, handlers::MEMPEAK_ARGS),
+    handlers::LOG_NUM => (handlers::log, handlers::LOG_ARGS),
+    handlers::HEARTBEAT_NUM => (handlers::heartbeat, handlers::HEARTBEAT_ARGS),
+    handlers::HEAPCHECK_NUM => (handlers::heapcheck, handlers::HEAPCHECK_ARGS),
+    handlers::SCHEDSTATS_NUM => (handlers::schedstats, handlers::SCHEDSTATS_ARGS),
+    handlers::WAITPID_NUM => (handlers::waitpid, handlers::WAITPID_ARGS),
+    handlers::MUTEX_LOCK_NUM => (handlers::mutex_lock, handlers::MUTEX_LOCK_ARGS),
+    handlers::MUTEX_UNLOCK_NUM => (handlers::mutex_unlock, handlers::MUTEX_UNLOCK_ARGS),
+    handlers::SETPRIORITY_NUM => (handlers::setpriority, handlers::SETPRIORITY_ARGS),
+    handlers::NANOSLEEP_NUM => (handlers::nanosleep, handlers::NANOSLEEP_ARGS),
+};
+
+/// Dispatches a syscall by number against a given table. `args` must point at
+/// the entry's declared number of argument words.
+pub fn dispatch_in(
+    table: &[Option<SyscallEntry>; MAX_SYSCALLS],
+    num: u8,
+    args: *const c_uint,
+) -> c_int {
+    let Some(entry) = table.get(num as usize).copied().flatten() else {
+        return ENOSYS;
+    };
+    BUG_ON!(entry.args > MAX_ARGS, "syscall {} declares invalid arity", num);
+    (entry.handler)(args)
+}
+
+/// Dispatches a syscall against the kernel's table. Called from the svc
+/// exception path.
+pub fn dispatch(num: u8, args: *const c_uint) -> c_int {
+    dispatch_in(&SYSCALL_TABLE, num, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern "C" fn add_two(args: *const c_uint) -> c_int {
+        unsafe { (*args + *args.add(1)) as c_int }
+    }
+
+    extern "C" fn fourty_two(_args: *const c_uint) -> c_int {
+        42
+    }
+
+    #[test]
+    fn dispatch_routes_by_number() {
+        let table = syscall_table! {
+            3 => (add_two, 2),
+            7 => (fourty_two, 0),
+        };
+        let args: [c_uint; 2] = [20, 3];
+        assert_eq!(dispatch_in(&table, 3, args.as_ptr()), 23);
+        assert_eq!(dispatch_in(&table, 7, args.as_ptr()), 42);
+    }
+
+    #[test]
+    fn dispatch_unknown_number_errors() {
+        let table = syscall_table! {
+            3 => (add_two, 2),
+        };
+        let args: [c_uint; 2] = [0, 0];
+        assert_eq!(dispatch_in(&table, 4, args.as_ptr()), ENOSYS);
+        assert_eq!(dispatch_in(&table, 255, args.as_ptr()), ENOSYS);
+    }
+}
@@ -0,0 +1,151 @@
+//! Kernel timers: a small fixed-slot timer wheel driven from the tick path.
+
+use crate::sync::SpinLock;
+
+/// Maximum number of concurrently armed timers.
+pub const MAX_TIMERS: usize = 8;
+
+/// A timer callback. Runs on the tick path with the wheel unlocked; state
+/// must live in globals.
+pub type TimerCallback = fn(now_ns: u64);
+
+/// Handle to an armed timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerId(usize);
+
+struct Timer {
+    deadline_ns: u64,
+    /// `Some` makes the timer periodic with this period.
+    period_ns: Option<u64>,
+    callback: TimerCallback,
+}
+
+/// The fixed-slot timer wheel. All logic lives on this type so host tests
+/// can drive a private instance.
+pub struct TimerWheel {
+    timers: [Option<Timer>; MAX_TIMERS],
+}
+
+impl TimerWheel {
+    pub const fn new() -> Self {
+        const NONE: Option<Timer> = None;
+        Self {
+            timers: [NONE; MAX_TIMERS],
+        }
+    }
+
+    /// Arms a timer firing at `deadline_ns`, re-armed every `period_ns` when
+    /// periodic. Returns `None` when all slots are taken.
+    pub fn arm(
+        &mut self,
+        deadline_ns: u64,
+        period_ns: Option<u64>,
+        callback: TimerCallback,
+    ) -> Option<TimerId> {
+        let slot = self.timers.iter().position(|t| t.is_none())?;
+        self.timers[slot] = Some(Timer {
+            deadline_ns,
+            period_ns,
+            callback,
+        });
+        Some(TimerId(slot))
+    }
+
+    /// Disarms a timer. Unknown ids are ignored.
+    pub fn cancel(&mut self, id: TimerId) {
+        if let Some(slot) = self.timers.get_mut(id.0) {
+            *slot = None;
+        }
+    }
+
+    /// Collects the callbacks of all timers expired at `now_ns`, re-arming
+    /// periodic ones. The callbacks are returned instead of invoked so the
+    /// caller can drop the wheel lock first.
+    fn expire(&mut self, now_ns: u64) -> [Option<TimerCallback>; MAX_TIMERS] {
+        let mut fired = [None; MAX_TIMERS];
+        for (slot, entry) in self.timers.iter_mut().enumerate() {
+            let Some(timer) = entry else {
+                continue;
+            };
+            if now_ns < timer.deadline_ns {
+                continue;
+            }
+            fired[slot] = Some(timer.callback);
+            match timer.period_ns {
+                Some(period) => timer.deadline_ns += period,
+                None => *entry = None,
+            }
+        }
+        fired
+    }
+
+    /// Fires every timer expired at `now_ns`.
+    pub fn on_tick(&mut self, now_ns: u64) {
+        for callback in self.expire(now_ns).into_iter().flatten() {
+            callback(now_ns);
+        }
+    }
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The global timer wheel.
+static TIMERS: SpinLock<TimerWheel> = SpinLock::new(TimerWheel::new());
+
+/// Arms a timer on the global wheel.
+pub fn arm(deadline_ns: u64, period_ns: Option<u64>, callback: TimerCallback) -> Option<TimerId> {
+    TIMERS.lock().arm(deadline_ns, period_ns, callback)
+}
+
+/// Disarms a timer on the global wheel.
+pub fn cancel(id: TimerId) {
+    TIMERS.lock().cancel(id);
+}
+
+/// Drives the global wheel; called from the periodic tick. Callbacks run
+/// with the wheel unlocked so they may arm or cancel timers.
+pub fn on_tick(now_ns: u64) {
+    let fired = TIMERS.lock().expire(now_ns);
+    for callback in fired.into_iter().flatten() {
+        callback(now_ns);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static FIRED_AT: AtomicU64 = AtomicU64::new(0);
+
+    fn record(now_ns: u64) {
+        FIRED_AT.store(now_ns, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn one_shot_fires_once_periodic_rearms() {
+        let mut wheel = TimerWheel::new();
+        let _shot = wheel.arm(100, None, record).unwrap();
+        let periodic = wheel.arm(100, Some(50), record).unwrap();
+
+        assert!(wheel.expire(99).iter().all(Option::is_none));
+        assert_eq!(wheel.expire(100).iter().flatten().count(), 2);
+        // The one-shot is gone; the periodic timer fires again at 150.
+        assert_eq!(wheel.expire(150).iter().flatten().count(), 1);
+
+        wheel.cancel(periodic);
+        assert!(wheel.expire(500).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn on_tick_invokes_callbacks_with_now() {
+        let mut wheel = TimerWheel::new();
+        wheel.arm(10, None, record).unwrap();
+        wheel.on_tick(25);
+        assert_eq!(FIRED_AT.load(Ordering::SeqCst), 25);
+    }
+}
@@ -0,0 +1,136 @@
+//! Fault-trapped copies between kernel and user memory.
+//!
+//! Pointer validation alone is not enough: a segment can be torn down between
+//! the check and the access, and a bad page faults only when touched. The
+//! copies here run with a *trap armed*: the architecture's data-fault handler
+//! calls [`absorb_copy_fault`] first, and when it reports the fault as
+//! absorbed, skips the faulting access and resumes the copy loop instead of
+//! escalating. The loop then observes the recorded fault and the copy returns
+//! [`KernelError::Fault`] — the kernel survives.
+//!
+//! The copy is byte-wise and volatile so every access is independently
+//! restartable; there is no partial multi-word state for the fault handler to
+//! reconstruct.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::{validate_user_slice, validate_user_slice_mut, TaskMemory};
+use crate::KernelError;
+
+/// Set while a user copy is in progress; checked by the fault path.
+static TRAP_ARMED: AtomicBool = AtomicBool::new(false);
+
+/// Set by [`absorb_copy_fault`] when a fault hit an armed copy.
+static TRAP_FAULTED: AtomicBool = AtomicBool::new(false);
+
+/// Called from the architecture data-fault path. Returns `true` when the
+/// fault hit an in-progress user copy and was absorbed: the handler must then
+/// skip the faulting access and return, *not* escalate or kill the task.
+pub fn absorb_copy_fault() -> bool {
+    if TRAP_ARMED.load(Ordering::Acquire) {
+        TRAP_FAULTED.store(true, Ordering::Release);
+        true
+    } else {
+        false
+    }
+}
+
+/// Copies `dst.len()` bytes from user memory at `src` into `dst`.
+///
+/// The source is validated against the task's segments first; a stale pointer
+/// and a fault mid-copy both come back as [`KernelError::Fault`].
+pub fn copy_from_user(
+    mem: &TaskMemory,
+    dst: &mut [u8],
+    src: *const u8,
+) -> Result<(), KernelError> {
+    validate_user_slice(mem, src, dst.len()).map_err(|_| KernelError::Fault)?;
+    trapped_copy(dst.as_mut_ptr(), src, dst.len())
+}
+
+/// Copies `src.len()` bytes from `src` into user memory at `dst`, which must
+/// lie in a writable segment of the task.
+pub fn copy_to_user(mem: &TaskMemory, dst: *mut u8, src: &[u8]) -> Result<(), KernelError> {
+    validate_user_slice_mut(mem, dst, src.len()).map_err(|_| KernelError::Fault)?;
+    trapped_copy(dst, src.as_ptr(), src.len())
+}
+
+/// The armed copy loop shared by both directions.
+fn trapped_copy(dst: *mut u8, src: *const u8, len: usize) -> Result<(), KernelError> {
+    TRAP_FAULTED.store(false, Ordering::Release);
+    TRAP_ARMED.store(true, Ordering::Release);
+    for i in 0..len {
+        #[cfg(test)]
+        tests::fault_injection(i);
+        // A fault absorbed during the previous access aborts the copy; the
+        // bytes already copied are not undone, matching what the caller can
+        // assume from an `Err` (contents unspecified).
+        if TRAP_FAULTED.load(Ordering::Acquire) {
+            break;
+        }
+        unsafe {
+            core::ptr::write_volatile(dst.add(i), core::ptr::read_volatile(src.add(i)));
+        }
+    }
+    TRAP_ARMED.store(false, Ordering::Release);
+    if TRAP_FAULTED.swap(false, Ordering::AcqRel) {
+        Err(KernelError::Fault)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uspace::{Segment, SegmentPerms};
+    use std::sync::atomic::AtomicUsize;
+
+    /// Byte index at which the next copy "faults" (`usize::MAX`: never).
+    /// Stands in for the architecture fault handler on the host.
+    static FAULT_AT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+    pub(super) fn fault_injection(index: usize) {
+        if index == FAULT_AT.load(Ordering::Relaxed) {
+            FAULT_AT.store(usize::MAX, Ordering::Relaxed);
+            assert!(absorb_copy_fault(), "armed copy must absorb the fault");
+        }
+    }
+
+    /// One test function: the injection point is process-wide state.
+    #[test]
+    fn user_copies_validate_and_survive_faults() {
+        let user: Vec<u8> = (0..64).collect();
+        let mut mem = TaskMemory::new();
+        mem.add_segment(Segment {
+            range: user.as_ptr() as usize..user.as_ptr() as usize + user.len(),
+            perms: SegmentPerms::RW,
+        })
+        .unwrap();
+
+        // A clean round trip in both directions.
+        let mut buf = [0u8; 16];
+        copy_from_user(&mem, &mut buf, user.as_ptr()).unwrap();
+        assert_eq!(buf, core::array::from_fn(|i| i as u8));
+        copy_to_user(&mem, user.as_ptr() as *mut u8, &buf).unwrap();
+
+        // Out-of-segment pointers fail validation up front.
+        assert_eq!(
+            copy_from_user(&mem, &mut buf, 0x10 as *const u8),
+            Err(KernelError::Fault)
+        );
+
+        // A fault absorbed mid-copy surfaces as an error, not a panic, and
+        // the trap is disarmed again afterwards.
+        FAULT_AT.store(8, Ordering::Relaxed);
+        assert_eq!(
+            copy_from_user(&mem, &mut buf, user.as_ptr()),
+            Err(KernelError::Fault)
+        );
+        assert!(!absorb_copy_fault(), "trap must be disarmed after the copy");
+
+        // The failed copy leaves no sticky state behind: a later copy
+        // succeeds again.
+        copy_from_user(&mem, &mut buf, user.as_ptr()).unwrap();
+    }
+}
@@ -0,0 +1,248 @@
+//! Instantiation of the init application.
+//!
+//! The init binary's `.text`/`.rodata` is immutable, so when several
+//! instances of it are spawned they all map the same image range as a shared
+//! read-only segment; only `.data`/`.bss` is allocated (and initialized)
+//! per instance. A single instance is laid out exactly the same way, just
+//! with one data allocation.
+
+use core::ops::Range;
+use core::ptr::NonNull;
+
+use interface::InitArgs;
+
+use crate::mem::{with_heap, HeapError};
+use crate::uspace::{Segment, SegmentPerms, TaskMemory};
+
+/// Fallback width of the guard gap between task regions when
+/// `OSIRIS_MEM_GUARD_SIZE` is not configured.
+const DEFAULT_GUARD_SIZE: usize = 32;
+
+/// Pattern the guard gap is filled with, distinct from the heap's free-block
+/// poison so a corrupted guard is attributable.
+pub const GUARD_POISON: u8 = 0xA5;
+
+/// Bytes of guard gap appended to every per-instance allocation. The gap
+/// belongs to the allocation (so the allocator never hands it out) but lies
+/// outside every segment, so the software pointer checks — and an MPU map
+/// derived from the segments, where one exists — treat it as no-access.
+pub fn guard_size() -> usize {
+    option_env!("OSIRIS_MEM_GUARD_SIZE")
+        .and_then(|raw| raw.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_GUARD_SIZE)
+}
+
+/// Loader for init instances: one shared text mapping, per-instance data.
+pub struct InitLoader {
+    /// The immutable `.text`/`.rodata` of the image, mapped by every
+    /// instance.
+    text: Range<usize>,
+    /// The `.data` initialization template inside the image; its length plus
+    /// `bss_size` is the per-instance allocation.
+    data_template: Range<usize>,
+    bss_size: usize,
+}
+
+impl InitLoader {
+    pub const fn new(text: Range<usize>, data_template: Range<usize>, bss_size: usize) -> Self {
+        Self {
+            text,
+            data_template,
+            bss_size,
+        }
+    }
+
+    /// Bytes of writable memory each instance needs: `.data` + `.bss` plus
+    /// the [`InitArgs`] block appended after them.
+    fn data_size(&self) -> usize {
+        self.args_offset() + core::mem::size_of::<InitArgs>()
+    }
+
+    /// Offset of the [`InitArgs`] block within the data segment: directly
+    /// after `.bss` (rounded up to the block's alignment), the well-known
+    /// location the init app reads its environment from without a syscall.
+    pub fn args_offset(&self) -> usize {
+        (self.data_template.len() + self.bss_size)
+            .next_multiple_of(core::mem::align_of::<InitArgs>())
+    }
+
+    /// Builds the memory map for one more instance: the shared read-only
+    /// text plus a freshly allocated writable data segment, with `.data`
+    /// copied from the template, `.bss` zeroed and `args` written after it.
+    /// A poisoned guard gap of [`guard_size`] bytes trails the segment so a
+    /// silent overrun into the neighbouring region becomes a detectable
+    /// fault instead.
+    pub fn instantiate(&self, args: &InitArgs) -> Result<TaskMemory, HeapError> {
+        let data = with_heap(|heap| heap.malloc(self.data_size() + guard_size()))?;
+        // SAFETY: the template lies in the loaded image; the destination was
+        // just allocated with room for template + bss + args + guard, and the
+        // args offset is aligned (malloc returns aligned blocks).
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                self.data_template.start as *const u8,
+                data.as_ptr(),
+                self.data_template.len(),
+            );
+            core::ptr::write_bytes(
+                data.as_ptr().add(self.data_template.len()),
+                0,
+                self.args_offset() - self.data_template.len(),
+            );
+            core::ptr::write(data.as_ptr().add(self.args_offset()) as *mut InitArgs, *args);
+            core::ptr::write_bytes(
+                data.as_ptr().add(self.data_size()),
+                GUARD_POISON,
+                guard_size(),
+            );
+        }
+
+        let mut memory = TaskMemory::new();
+        let text = memory.add_segment(Segment {
+            range: self.text.clone(),
+            perms: SegmentPerms::RX,
+        });
+        let data_base = data.as_ptr() as usize;
+        let data = memory.add_segment(Segment {
+            range: data_base..data_base + self.data_size(),
+            perms: SegmentPerms::RW,
+        });
+        // RX text and RW data cannot violate W^X.
+        BUG_ON!(text.is_err() || data.is_err(), "init segments rejected");
+        Ok(memory)
+    }
+
+    /// Releases an instance's writable memory again, guard gap included. The
+    /// shared text is never freed while the image stays loaded.
+    pub fn release(&self, memory: &TaskMemory) {
+        for segment in memory.segments().filter(|s| s.perms.write) {
+            if let Some(ptr) = NonNull::new(segment.range.start as *mut u8) {
+                with_heap(|heap| heap.free(ptr, segment.range.len() + guard_size()));
+            }
+        }
+    }
+
+    /// Whether every writable segment's trailing guard still holds its
+    /// poison pattern — `false` means the task overran its region.
+    pub fn guard_intact(&self, memory: &TaskMemory) -> bool {
+        memory.segments().filter(|s| s.perms.write).all(|segment| {
+            (0..guard_size()).all(|offset| {
+                // SAFETY: the guard bytes trail the segment inside the same
+                // allocation and stay owned until `release`.
+                unsafe { *((segment.range.end + offset) as *const u8) == GUARD_POISON }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Donates a leaked host allocation to the global heap and returns a
+    /// fake image: 64 bytes of "text" followed by 16 bytes of ".data".
+    fn fake_image() -> InitLoader {
+        let arena: &'static mut [u64] = Vec::leak(vec![0u64; 128]);
+        let start = arena.as_ptr() as usize;
+        let heap_start = start + 128;
+        with_heap(|heap| unsafe { heap.add_range(heap_start..start + 128 * 8) }).unwrap();
+
+        // Recognizable .data template.
+        arena[8] = 0x1122_3344_5566_7788;
+        InitLoader::new(start..start + 64, start + 64..start + 80, 16)
+    }
+
+    #[test]
+    fn instances_share_text_but_not_data() {
+        let loader = fake_image();
+        let first = loader.instantiate(&InitArgs::empty()).unwrap();
+        let second = loader.instantiate(&InitArgs::empty()).unwrap();
+
+        let text_base = |mem: &TaskMemory| {
+            mem.segments()
+                .find(|s| s.perms.execute)
+                .map(|s| s.range.start)
+                .unwrap()
+        };
+        let data_base = |mem: &TaskMemory| {
+            mem.segments()
+                .find(|s| s.perms.write)
+                .map(|s| s.range.start)
+                .unwrap()
+        };
+
+        assert_eq!(text_base(&first), text_base(&second));
+        assert_ne!(data_base(&first), data_base(&second));
+
+        // Each instance got its own initialized copy of .data.
+        let template = unsafe { *(data_base(&first) as *const u64) };
+        assert_eq!(template, 0x1122_3344_5566_7788);
+        unsafe { *(data_base(&first) as *mut u64) = 0 };
+        assert_eq!(unsafe { *(data_base(&second) as *const u64) }, 0x1122_3344_5566_7788);
+
+        loader.release(&first);
+        loader.release(&second);
+    }
+
+    #[test]
+    fn task_regions_are_separated_by_an_unallocatable_guard() {
+        let loader = fake_image();
+        let first = loader.instantiate(&InitArgs::empty()).unwrap();
+        let second = loader.instantiate(&InitArgs::empty()).unwrap();
+
+        let data_range = |mem: &TaskMemory| {
+            mem.segments()
+                .find(|s| s.perms.write)
+                .map(|s| s.range.clone())
+                .unwrap()
+        };
+        let (a, b) = (data_range(&first), data_range(&second));
+
+        // Adjacent regions keep at least the guard between them, whichever
+        // order the heap placed them in.
+        assert!(a.end + guard_size() <= b.start || b.end + guard_size() <= a.start);
+
+        // The gap belongs to the instance: further allocations never land in
+        // it, and it holds the poison pattern.
+        let probe = with_heap(|heap| heap.malloc(8)).unwrap().as_ptr() as usize;
+        for guard in [a.end..a.end + guard_size(), b.end..b.end + guard_size()] {
+            assert!(!guard.contains(&probe));
+        }
+        assert!(loader.guard_intact(&first) && loader.guard_intact(&second));
+
+        // An overrun past the segment end is detectable.
+        unsafe { *(a.end as *mut u8) = 0 };
+        assert!(!loader.guard_intact(&first));
+        assert!(loader.guard_intact(&second));
+
+        with_heap(|heap| heap.free(NonNull::new(probe as *mut u8).unwrap(), 8));
+        loader.release(&first);
+        loader.release(&second);
+    }
+
+    #[test]
+    fn init_args_land_right_after_bss() {
+        let loader = fake_image();
+        let mut args = InitArgs::empty();
+        args.task_id = 3;
+        args.heap_budget = 4096;
+        args.set_cmdline(b"loglevel=debug").unwrap();
+
+        let memory = loader.instantiate(&args).unwrap();
+        let data = memory.segments().find(|s| s.perms.write).unwrap();
+
+        // The fake image has a 16-byte template and 16 bytes of .bss, so the
+        // args block begins at offset 32 and the segment covers it fully.
+        assert_eq!(loader.args_offset(), 32);
+        assert_eq!(
+            data.range.len(),
+            loader.args_offset() + core::mem::size_of::<InitArgs>()
+        );
+
+        let stored = unsafe { &*((data.range.start + loader.args_offset()) as *const InitArgs) };
+        assert_eq!(stored.task_id, 3);
+        assert_eq!(stored.heap_budget, 4096);
+        assert_eq!(stored.cmdline(), b"loglevel=debug");
+
+        loader.release(&memory);
+    }
+}
@@ -0,0 +1,249 @@
+//! Userspace memory: per-task segments and validation of user pointers
+//! before the kernel touches them.
+
+pub mod copy;
+pub mod init;
+
+use core::ops::Range;
+
+/// Maximum number of memory segments a task can own (text, data, stack, ...).
+pub const MAX_SEGMENTS: usize = 4;
+
+/// Errors from user-pointer validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UspaceError {
+    /// The slice is not fully contained in any of the task's segments.
+    InvalidPointer,
+    /// The slice lies in a segment the task may not write to.
+    ReadOnly,
+    /// The segment would be writable and executable at once (W^X).
+    WritableExecutable,
+}
+
+/// Access permissions of one segment. Everything is readable; write and
+/// execute must never be combined (W^X), which [`TaskMemory::add_segment`]
+/// enforces — `.text` maps RX, `.data` and stacks RW and never executable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentPerms {
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl SegmentPerms {
+    /// Read-only data (`.rodata`).
+    pub const R: Self = Self {
+        write: false,
+        execute: false,
+    };
+    /// Read-write data (`.data`, `.bss`, stacks).
+    pub const RW: Self = Self {
+        write: true,
+        execute: false,
+    };
+    /// Executable code (`.text`).
+    pub const RX: Self = Self {
+        write: false,
+        execute: true,
+    };
+
+    /// Whether the combination satisfies W^X.
+    pub fn is_wx_safe(self) -> bool {
+        !(self.write && self.execute)
+    }
+}
+
+/// One contiguous region of a task's address space.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub range: Range<usize>,
+    pub perms: SegmentPerms,
+}
+
+impl Segment {
+    fn contains(&self, start: usize, len: usize) -> bool {
+        let Some(end) = start.checked_add(len) else {
+            return false;
+        };
+        start >= self.range.start && end <= self.range.end
+    }
+}
+
+/// The memory layout of one task.
+#[derive(Debug, Default, Clone)]
+pub struct TaskMemory {
+    segments: [Option<Segment>; MAX_SEGMENTS],
+}
+
+impl TaskMemory {
+    pub const fn new() -> Self {
+        Self {
+            segments: [None, None, None, None],
+        }
+    }
+
+    /// Registers a segment, rejecting W+X permissions — this is the single
+    /// chokepoint a future mmap-style syscall would also go through.
+    /// Overflowing the fixed table is a kernel bug since the segment count
+    /// is decided at task setup.
+    pub fn add_segment(&mut self, segment: Segment) -> Result<(), UspaceError> {
+        if !segment.perms.is_wx_safe() {
+            return Err(UspaceError::WritableExecutable);
+        }
+        let slot = self.segments.iter_mut().find(|s| s.is_none());
+        BUG_ON!(slot.is_none(), "task segment table full");
+        if let Some(slot) = slot {
+            *slot = Some(segment);
+        }
+        Ok(())
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = &Segment> {
+        self.segments.iter().flatten()
+    }
+}
+
+/// Validates that the kernel may *read* `len` bytes at `ptr` on behalf of the
+/// task: the slice must lie fully within one of the task's segments.
+pub fn validate_user_slice(
+    mem: &TaskMemory,
+    ptr: *const u8,
+    len: usize,
+) -> Result<(), UspaceError> {
+    let start = ptr as usize;
+    if mem.segments().any(|s| s.contains(start, len)) {
+        Ok(())
+    } else {
+        Err(UspaceError::InvalidPointer)
+    }
+}
+
+/// Validates that the kernel may *write* `len` bytes at `ptr` on behalf of
+/// the task: additionally requires the containing segment to be writable.
+pub fn validate_user_slice_mut(
+    mem: &TaskMemory,
+    ptr: *mut u8,
+    len: usize,
+) -> Result<(), UspaceError> {
+    let start = ptr as usize;
+    match mem.segments().find(|s| s.contains(start, len)) {
+        Some(segment) if segment.perms.write => Ok(()),
+        Some(_) => Err(UspaceError::ReadOnly),
+        None => Err(UspaceError::InvalidPointer),
+    }
+}
+
+/// Iterates the boot command line as `key=value` pairs.
+///
+/// Whitespace separates entries; an entry without `=` yields an empty value
+/// (flag style). Invalid UTF-8 yields no entries.
+pub fn parse_cmdline(raw: &[u8]) -> impl Iterator<Item = (&str, &str)> {
+    core::str::from_utf8(raw)
+        .unwrap_or("")
+        .split_ascii_whitespace()
+        .map(|entry| match entry.split_once('=') {
+            Some((key, value)) => (key, value),
+            None => (entry, ""),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_memory() -> TaskMemory {
+        let mut mem = TaskMemory::new();
+        mem.add_segment(Segment {
+            range: 0x1000..0x2000,
+            perms: SegmentPerms::RX,
+        })
+        .unwrap();
+        mem.add_segment(Segment {
+            range: 0x2000..0x3000,
+            perms: SegmentPerms::RW,
+        })
+        .unwrap();
+        mem
+    }
+
+    #[test]
+    fn writable_executable_segment_is_rejected() {
+        let mut mem = TaskMemory::new();
+        let result = mem.add_segment(Segment {
+            range: 0x1000..0x2000,
+            perms: SegmentPerms {
+                write: true,
+                execute: true,
+            },
+        });
+        assert_eq!(result, Err(UspaceError::WritableExecutable));
+        // The rejected segment must not have been registered.
+        assert_eq!(mem.segments().count(), 0);
+    }
+
+    #[test]
+    fn read_only_pointer_passes_read_check() {
+        let mem = task_memory();
+        assert_eq!(validate_user_slice(&mem, 0x1100 as *const u8, 64), Ok(()));
+    }
+
+    #[test]
+    fn read_only_pointer_fails_write_check() {
+        let mem = task_memory();
+        assert_eq!(
+            validate_user_slice_mut(&mem, 0x1100 as *mut u8, 64),
+            Err(UspaceError::ReadOnly)
+        );
+    }
+
+    #[test]
+    fn writable_pointer_passes_both_checks() {
+        let mem = task_memory();
+        assert_eq!(validate_user_slice(&mem, 0x2100 as *const u8, 64), Ok(()));
+        assert_eq!(validate_user_slice_mut(&mem, 0x2100 as *mut u8, 64), Ok(()));
+    }
+
+    #[test]
+    fn parse_cmdline_yields_key_value_pairs() {
+        let mut args = interface::Args::empty();
+        args.set_cmdline(b"console=lpuart1 loglevel=debug quiet").unwrap();
+
+        let pairs: Vec<_> = parse_cmdline(args.cmdline()).collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("console", "lpuart1"),
+                ("loglevel", "debug"),
+                ("quiet", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_cmdline_handles_empty_input() {
+        let args = interface::Args::empty();
+        assert_eq!(parse_cmdline(args.cmdline()).count(), 0);
+    }
+
+    #[test]
+    fn oversized_cmdline_is_rejected() {
+        let mut args = interface::Args::empty();
+        let too_long = [b'a'; interface::CMDLINE_MAX + 1];
+        assert!(args.set_cmdline(&too_long).is_err());
+        // The args stay empty after a rejected set.
+        assert_eq!(args.cmdline(), b"");
+    }
+
+    #[test]
+    fn out_of_segment_pointer_fails() {
+        let mem = task_memory();
+        assert_eq!(
+            validate_user_slice(&mem, 0x4000 as *const u8, 1),
+            Err(UspaceError::InvalidPointer)
+        );
+        // Straddling a segment boundary is also invalid.
+        assert_eq!(
+            validate_user_slice(&mem, 0x1ff0 as *const u8, 0x20),
+            Err(UspaceError::InvalidPointer)
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! Small helpers shared across the kernel.
+
+/// Panics with a kernel bug message when the condition holds.
+///
+/// Use this for invariants that indicate kernel bugs, not recoverable errors.
+#[macro_export]
+macro_rules! BUG_ON {
+    ($cond:expr) => {
+        if $cond {
+            panic!(concat!("kernel BUG: ", stringify!($cond)));
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if $cond {
+            panic!("kernel BUG: {}", format_args!($($arg)+));
+        }
+    };
+}
+
+/// Rounds `value` up to the next multiple of `align`. `align` must be a power of two.
+pub const fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_up_rounds_to_multiple() {
+        assert_eq!(align_up(0, 8), 0);
+        assert_eq!(align_up(1, 8), 8);
+        assert_eq!(align_up(8, 8), 8);
+        assert_eq!(align_up(9, 4), 12);
+    }
+}
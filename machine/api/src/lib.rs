@@ -0,0 +1,124 @@
+//! The machine abstraction the kernel is written against.
+//!
+//! Every supported machine (a real chip or the host-side testing machine)
+//! provides an implementation of [`Machinelike`]; `machine/select` picks the
+//! right one at compile time.
+
+#![cfg_attr(not(test), no_std)]
+
+/// Errors surfaced by machine implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MachineError {
+    /// The requested value is outside what the hardware supports.
+    InvalidConfig,
+    /// The operation is not supported on this machine.
+    Unsupported,
+}
+
+/// A fault reported by the hardware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    HardFault,
+    MemManage,
+    BusFault,
+    UsageFault,
+}
+
+/// Why the machine last reset, decoded from hardware flags during init.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResetCause {
+    /// No flag recorded — a cold power-on, or the flags were already
+    /// cleared before the kernel looked.
+    #[default]
+    PowerOn,
+    /// An independent or window watchdog expired.
+    Watchdog,
+    /// A software-requested reset ([`Machinelike::reset`]).
+    Software,
+    /// The supply dipped below the brown-out threshold. Chips whose
+    /// brown-out flag also latches on a cold power-on report that here too.
+    BrownOut,
+    /// The external reset pin was pulled.
+    Pin,
+}
+
+/// The optional hardware features a machine can support. A plain bitset so
+/// machines can assemble it in const context without external crates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// No optional features at all.
+    pub const NONE: Self = Self(0);
+    /// A hardware entropy source backs [`Machinelike::random_u32`].
+    pub const RNG: Self = Self(1 << 0);
+    /// A memory protection unit is present.
+    pub const MPU: Self = Self(1 << 1);
+    /// A hardware floating-point unit is present.
+    pub const FPU: Self = Self(1 << 2);
+    /// A cycle counter (DWT or equivalent) backs precise delays.
+    pub const DWT: Self = Self(1 << 3);
+
+    /// The raw bitset, for storage in atomics.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Rebuilds a set from [`Self::bits`].
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Whether every feature in `other` is supported.
+    pub const fn supports(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl core::ops::BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// The interface a machine must provide to the kernel.
+pub trait Machinelike {
+    /// One-time hardware bring-up (clocks, console, timers).
+    fn init();
+
+    /// Writes a string to the machine's console.
+    fn print(s: &str);
+
+    /// Blocks until all previously printed bytes have left the console
+    /// hardware. A no-op on machines that print synchronously.
+    fn flush() -> Result<(), MachineError>;
+
+    /// Resets the machine. Does not return.
+    fn reset() -> !;
+
+    /// Monotonic time since boot in nanoseconds.
+    fn now_ns() -> u64;
+
+    /// Busy-waits for at least `ns` nanoseconds. For short, precise driver
+    /// delays; anything long enough to schedule around belongs on a timer.
+    fn delay_ns(ns: u64);
+
+    /// Configures the periodic scheduler tick to fire at `hz`.
+    ///
+    /// Returns [`MachineError::InvalidConfig`] when the frequency cannot be
+    /// produced by the tick hardware.
+    fn configure_tick(hz: u32) -> Result<(), MachineError>;
+
+    /// The optional features this machine supports. Kernel code gates
+    /// optional behavior on this instead of probing each feature itself.
+    fn capabilities() -> Capabilities;
+
+    /// One word of entropy from the machine's randomness source.
+    ///
+    /// Returns `None` when no usable source exists (hardware absent, clock
+    /// off, or the source reports an error); implementations must not
+    /// fabricate weak values instead.
+    fn random_u32() -> Option<u32>;
+}
@@ -0,0 +1,182 @@
+//! Fault exception status: capturing and decoding the SCB fault registers.
+//!
+//! On Cortex-M the CFSR (configurable fault status), HFSR (hard fault
+//! status), MMFAR and BFAR registers pin down the precise cause of a fault.
+//! [`FaultStatus`] snapshots them at fault entry and its `Display` renders a
+//! human-readable cause, which is what ends up in crash dumps.
+
+use core::fmt;
+
+use hal_api::Fault;
+
+/// CFSR: MMFSR (bits 0-7), BFSR (8-15) and UFSR (16-31) packed together.
+#[cfg(target_arch = "arm")]
+const CFSR: *const u32 = 0xE000_ED28 as *const u32;
+/// HFSR: hard fault status.
+#[cfg(target_arch = "arm")]
+const HFSR: *const u32 = 0xE000_ED2C as *const u32;
+/// MMFAR: faulting address of a memory-management fault (if MMARVALID).
+#[cfg(target_arch = "arm")]
+const MMFAR: *const u32 = 0xE000_ED34 as *const u32;
+/// BFAR: faulting address of a bus fault (if BFARVALID).
+#[cfg(target_arch = "arm")]
+const BFAR: *const u32 = 0xE000_ED38 as *const u32;
+
+/// A snapshot of the fault status registers, taken on fault entry.
+#[derive(Debug, Clone, Copy)]
+pub struct FaultStatus {
+    pub kind: Fault,
+    pub cfsr: u32,
+    pub hfsr: u32,
+    pub mmfar: u32,
+    pub bfar: u32,
+}
+
+impl FaultStatus {
+    /// Captures the live registers for a fault of `kind`. Off-target the
+    /// registers read as zero, leaving only the fault kind.
+    pub fn capture(kind: Fault) -> Self {
+        #[cfg(target_arch = "arm")]
+        // SAFETY: the SCB status registers are always readable.
+        unsafe {
+            Self {
+                kind,
+                cfsr: crate::reg::read_reg(CFSR),
+                hfsr: crate::reg::read_reg(HFSR),
+                mmfar: crate::reg::read_reg(MMFAR),
+                bfar: crate::reg::read_reg(BFAR),
+            }
+        }
+        #[cfg(not(target_arch = "arm"))]
+        Self {
+            kind,
+            cfsr: 0,
+            hfsr: 0,
+            mmfar: 0,
+            bfar: 0,
+        }
+    }
+}
+
+/// The decoded causes, one per set status bit: `(mask, description)`.
+const MM_CAUSES: &[(u32, &str)] = &[
+    (1 << 0, "instruction access violation"),
+    (1 << 1, "data access violation"),
+    (1 << 3, "fault on exception return unstacking"),
+    (1 << 4, "fault on exception entry stacking"),
+];
+
+const BUS_CAUSES: &[(u32, &str)] = &[
+    (1 << 8, "instruction bus error"),
+    (1 << 9, "precise data bus error"),
+    (1 << 10, "imprecise data bus error"),
+    (1 << 11, "bus error on exception return unstacking"),
+    (1 << 12, "bus error on exception entry stacking"),
+];
+
+const USAGE_CAUSES: &[(u32, &str)] = &[
+    (1 << 16, "undefined instruction"),
+    (1 << 17, "invalid EPSR state"),
+    (1 << 18, "invalid exception return"),
+    (1 << 19, "coprocessor access with no coprocessor"),
+    (1 << 24, "unaligned access"),
+    (1 << 25, "division by zero"),
+];
+
+/// MMARVALID: `mmfar` holds the faulting address.
+const MMARVALID: u32 = 1 << 7;
+/// BFARVALID: `bfar` holds the faulting address.
+const BFARVALID: u32 = 1 << 15;
+/// HFSR.FORCED: the hard fault escalated from a configurable fault.
+const FORCED: u32 = 1 << 30;
+
+impl fmt::Display for FaultStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, causes) = match self.kind {
+            Fault::HardFault => {
+                write!(f, "Hard fault")?;
+                if self.hfsr & FORCED != 0 {
+                    write!(f, " (escalated from a configurable fault)")?;
+                }
+                // An escalated fault still carries its cause bits in CFSR;
+                // decode all banks.
+                let mut first = true;
+                for causes in [MM_CAUSES, BUS_CAUSES, USAGE_CAUSES] {
+                    decode_causes(f, self.cfsr, causes, &mut first)?;
+                }
+                return Ok(());
+            }
+            Fault::MemManage => ("MemManage fault", MM_CAUSES),
+            Fault::BusFault => ("Bus fault", BUS_CAUSES),
+            Fault::UsageFault => ("Usage fault", USAGE_CAUSES),
+        };
+        write!(f, "{name}")?;
+        decode_causes(f, self.cfsr, causes, &mut true)?;
+        if self.kind == Fault::MemManage && self.cfsr & MMARVALID != 0 {
+            write!(f, " at {:#010x}", self.mmfar)?;
+        }
+        if self.kind == Fault::BusFault && self.cfsr & BFARVALID != 0 {
+            write!(f, " at {:#010x}", self.bfar)?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends `: cause, cause` for every set status bit. `first` tracks across
+/// calls whether the leading colon is still owed.
+fn decode_causes(
+    f: &mut fmt::Formatter<'_>,
+    cfsr: u32,
+    causes: &[(u32, &str)],
+    first: &mut bool,
+) -> fmt::Result {
+    for &(mask, description) in causes {
+        if cfsr & mask != 0 {
+            write!(f, "{} {description}", if *first { ":" } else { "," })?;
+            *first = false;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(kind: Fault, cfsr: u32, mmfar: u32, bfar: u32) -> FaultStatus {
+        FaultStatus {
+            kind,
+            cfsr,
+            hfsr: 0,
+            mmfar,
+            bfar,
+        }
+    }
+
+    #[test]
+    fn precise_bus_fault_decodes_with_address() {
+        let fault = status(Fault::BusFault, (1 << 9) | BFARVALID, 0, 0x2000_0100);
+        assert_eq!(
+            fault.to_string(),
+            "Bus fault: precise data bus error at 0x20000100"
+        );
+    }
+
+    #[test]
+    fn usage_fault_lists_every_set_cause() {
+        let fault = status(Fault::UsageFault, (1 << 16) | (1 << 24), 0, 0);
+        assert_eq!(
+            fault.to_string(),
+            "Usage fault: undefined instruction, unaligned access"
+        );
+    }
+
+    #[test]
+    fn escalated_hard_fault_mentions_the_original_cause() {
+        let mut fault = status(Fault::HardFault, 1 << 25, 0, 0);
+        fault.hfsr = 1 << 30;
+        let rendered = fault.to_string();
+        assert!(rendered.starts_with("Hard fault (escalated"));
+        assert!(rendered.contains("division by zero"));
+    }
+}
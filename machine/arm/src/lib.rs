@@ -0,0 +1,104 @@
+//! The ARM Cortex-M machine, currently targeting the stm32l4xx family.
+
+#![cfg_attr(not(test), no_std)]
+
+pub mod dwt;
+pub mod excep;
+pub mod mpu;
+pub mod reg;
+pub mod reset;
+pub mod rng;
+pub mod sched;
+pub mod stm32l4xx;
+pub mod systick;
+pub mod tick;
+pub mod uart;
+
+use hal_api::{Capabilities, MachineError, Machinelike, ResetCause};
+
+/// The ARM machine implementation. Hardware access is gated on
+/// `target_arch = "arm"` so the crate still builds (and its pure helpers
+/// test) on the host.
+pub struct ArmMachine;
+
+impl ArmMachine {
+    /// Ticks elapsed since boot, widened to 64 bits across the HAL
+    /// counter's 32-bit wrap. One tick is one SysTick period (see
+    /// [`Machinelike::configure_tick`]).
+    pub fn tick_count() -> u64 {
+        tick::tick_count()
+    }
+
+    /// Why the machine last reset, captured (and cleared in hardware)
+    /// during [`Machinelike::init`] — so the kernel can log
+    /// "last reset: watchdog" and react to abnormal boots.
+    pub fn reset_cause() -> ResetCause {
+        reset::cause()
+    }
+}
+
+impl Machinelike for ArmMachine {
+    fn init() {
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            stm32l4xx::HAL_Init();
+            stm32l4xx::SystemClock_Config();
+        }
+        reset::capture();
+        dwt::enable();
+    }
+
+    fn print(s: &str) {
+        // Enqueues into the TX ring; the TXE interrupt does the writing.
+        uart::write(s);
+    }
+
+    fn flush() -> Result<(), MachineError> {
+        // Blocks until the TX ring and the shift register have drained.
+        uart::flush();
+        Ok(())
+    }
+
+    fn reset() -> ! {
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            stm32l4xx::NVIC_SystemReset();
+        }
+        #[cfg(not(target_arch = "arm"))]
+        unreachable!("ArmMachine::reset on a non-arm target");
+    }
+
+    fn now_ns() -> u64 {
+        // Coarse tick-derived time: one SysTick period of resolution. A
+        // DWT-based fine-grained clock can interpolate between ticks later.
+        tick::ticks_to_ns(tick::tick_count(), tick::tick_hz())
+    }
+
+    fn delay_ns(ns: u64) {
+        dwt::wait_cycles(dwt::dwt_read, dwt::ns_to_cycles(ns, stm32l4xx::HCLK_HZ));
+    }
+
+    fn random_u32() -> Option<u32> {
+        rng::random_u32()
+    }
+
+    fn capabilities() -> Capabilities {
+        // The stm32l4xx family has a true RNG, an MPU, a single-precision
+        // FPU and the DWT cycle counter.
+        Capabilities::RNG | Capabilities::MPU | Capabilities::FPU | Capabilities::DWT
+    }
+
+    fn configure_tick(hz: u32) -> Result<(), MachineError> {
+        let reload = systick::reload_for(stm32l4xx::HCLK_HZ, hz)?;
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            stm32l4xx::HAL_SYSTICK_Config(reload + 1);
+        }
+        #[cfg(not(target_arch = "arm"))]
+        let _ = reload;
+        // HAL_GetTick advances once per SysTick interrupt, so the tick
+        // clock's rate must follow the hardware's.
+        tick::set_tick_hz(hz);
+        Ok(())
+    }
+}
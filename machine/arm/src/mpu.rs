@@ -0,0 +1,143 @@
+//! Cortex-M MPU region configuration.
+//!
+//! The register encoding is kept in pure functions so it is host-testable;
+//! only the final register writes are arm-gated.
+
+use hal_api::MachineError;
+
+/// MPU register block (ARMv7-M).
+#[cfg(target_arch = "arm")]
+mod regs {
+    pub const MPU_CTRL: *mut u32 = 0xE000_ED94 as *mut u32;
+    pub const MPU_RBAR: *mut u32 = 0xE000_ED9C as *mut u32;
+    pub const MPU_RASR: *mut u32 = 0xE000_EDA0 as *mut u32;
+}
+
+/// Access permissions of an MPU region, from the unprivileged (task) side.
+/// Execution is only permitted for [`RegionPerms::ReadOnlyExec`], keeping
+/// regions W^X by construction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionPerms {
+    NoAccess,
+    ReadOnly,
+    /// Read-only and executable (`.text`).
+    ReadOnlyExec,
+    ReadWrite,
+}
+
+impl RegionPerms {
+    /// The AP field (RASR bits 26:24).
+    fn ap_bits(self) -> u32 {
+        match self {
+            RegionPerms::NoAccess => 0b001, // privileged RW, unprivileged none
+            RegionPerms::ReadOnly | RegionPerms::ReadOnlyExec => 0b110,
+            RegionPerms::ReadWrite => 0b011,
+        }
+    }
+
+    /// The XN bit (RASR bit 28).
+    fn xn_bit(self) -> u32 {
+        match self {
+            RegionPerms::ReadOnlyExec => 0,
+            _ => 1 << 28,
+        }
+    }
+}
+
+/// Number of MPU regions on the STM32L4 (ARMv7-M PMSA).
+pub const MPU_REGIONS: u8 = 8;
+
+/// Smallest region size the MPU supports.
+pub const MIN_REGION_SIZE: u32 = 32;
+
+/// Computes the RBAR/RASR register values for a region.
+///
+/// The ARMv7-M MPU requires the size to be a power of two of at least 32
+/// bytes and the base to be aligned to the size; violations return
+/// [`MachineError::InvalidConfig`].
+pub fn encode_region(
+    index: u8,
+    base: u32,
+    size: u32,
+    perms: RegionPerms,
+) -> Result<(u32, u32), MachineError> {
+    if index >= MPU_REGIONS {
+        return Err(MachineError::InvalidConfig);
+    }
+    if size < MIN_REGION_SIZE || !size.is_power_of_two() || !base.is_multiple_of(size) {
+        return Err(MachineError::InvalidConfig);
+    }
+
+    // RBAR: base address | VALID | region index.
+    let rbar = base | (1 << 4) | index as u32;
+    // RASR: XN | AP | SIZE | ENABLE. SIZE encodes log2(size) - 1.
+    let size_field = (size.trailing_zeros() - 1) << 1;
+    let rasr = perms.xn_bit() | (perms.ap_bits() << 24) | size_field | 1;
+    Ok((rbar, rasr))
+}
+
+/// Programs one MPU region. The caller is responsible for a barrier (DSB/ISB)
+/// after reprogramming regions of the running task.
+pub fn configure_mpu_region(
+    index: u8,
+    base: u32,
+    size: u32,
+    perms: RegionPerms,
+) -> Result<(), MachineError> {
+    let (rbar, rasr) = encode_region(index, base, size, perms)?;
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        crate::reg::write_reg(regs::MPU_RBAR, rbar);
+        crate::reg::write_reg(regs::MPU_RASR, rasr);
+    }
+    #[cfg(not(target_arch = "arm"))]
+    let _ = (rbar, rasr);
+    Ok(())
+}
+
+/// Enables the MPU with the default memory map for privileged code, so the
+/// kernel keeps running while task regions are restricted.
+pub fn enable_mpu() {
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        // PRIVDEFENA | ENABLE.
+        crate::reg::write_reg(regs::MPU_CTRL, (1 << 2) | 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_region_computes_rbar_and_rasr() {
+        let (rbar, rasr) = encode_region(2, 0x2000_0000, 1024, RegionPerms::ReadWrite).unwrap();
+        assert_eq!(rbar, 0x2000_0000 | (1 << 4) | 2);
+        // SIZE field for 1 KiB: log2(1024) - 1 = 9, in bits 5:1.
+        assert_eq!(rasr & 0x3E, 9 << 1);
+        // AP = 0b011, XN set, ENABLE set.
+        assert_eq!((rasr >> 24) & 0b111, 0b011);
+        assert_ne!(rasr & (1 << 28), 0);
+        assert_ne!(rasr & 1, 0);
+    }
+
+    #[test]
+    fn text_region_is_executable() {
+        let (_, rasr) =
+            encode_region(0, 0x0800_0000, 4096, RegionPerms::ReadOnlyExec).unwrap();
+        assert_eq!(rasr & (1 << 28), 0);
+        assert_eq!((rasr >> 24) & 0b111, 0b110);
+    }
+
+    #[test]
+    fn invalid_regions_are_rejected() {
+        // Not a power of two.
+        assert!(encode_region(0, 0, 48, RegionPerms::ReadOnly).is_err());
+        // Too small.
+        assert!(encode_region(0, 0, 16, RegionPerms::ReadOnly).is_err());
+        // Base not aligned to size.
+        assert!(encode_region(0, 0x100, 0x1000, RegionPerms::ReadOnly).is_err());
+        // Region index out of range.
+        assert!(encode_region(8, 0, 1024, RegionPerms::ReadOnly).is_err());
+    }
+}
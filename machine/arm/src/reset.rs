@@ -0,0 +1,79 @@
+//! Reset-cause reporting from the RCC `CSR` flags.
+//!
+//! The flags latch across resets, so init reads them once, keeps the
+//! snapshot for later queries and clears the hardware via `RMVF` — a later
+//! reboot then sees only its own reset's flags, not stale ones.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use hal_api::ResetCause;
+
+/// `RCC_CSR`, holding the reset flags in its top byte.
+#[cfg(target_arch = "arm")]
+const RCC_CSR: *mut u32 = 0x4002_1094 as *mut u32;
+
+/// `RCC_CSR` bits: the remove-flags strobe and the reset flags.
+pub const RMVF: u32 = 1 << 23;
+pub const PINRSTF: u32 = 1 << 26;
+pub const BORRSTF: u32 = 1 << 27;
+pub const SFTRSTF: u32 = 1 << 28;
+pub const IWDGRSTF: u32 = 1 << 29;
+pub const WWDGRSTF: u32 = 1 << 30;
+
+/// The `CSR` snapshot taken by [`capture`], decoded on query.
+static FLAGS: AtomicU32 = AtomicU32::new(0);
+
+/// Decodes a `CSR` snapshot into the most telling cause. Hardware sets
+/// several flags per reset (a watchdog reset also pulls the pin flag), so
+/// the specific causes take precedence over the generic ones.
+pub fn decode(csr: u32) -> ResetCause {
+    if csr & (IWDGRSTF | WWDGRSTF) != 0 {
+        ResetCause::Watchdog
+    } else if csr & SFTRSTF != 0 {
+        ResetCause::Software
+    } else if csr & BORRSTF != 0 {
+        // The L4's brown-out flag also latches on a cold power-on; the two
+        // are indistinguishable without a backup-domain marker.
+        ResetCause::BrownOut
+    } else if csr & PINRSTF != 0 {
+        ResetCause::Pin
+    } else {
+        ResetCause::PowerOn
+    }
+}
+
+/// Reads and clears the hardware flags. Called once from `init`; querying
+/// before that (or off target) reports [`ResetCause::PowerOn`].
+pub fn capture() {
+    #[cfg(target_arch = "arm")]
+    // SAFETY: RCC_CSR address per the STM32L4 reference manual; single-core,
+    // and init runs before anything else touches RCC.
+    unsafe {
+        let csr = crate::reg::read_reg(RCC_CSR);
+        FLAGS.store(csr, Ordering::Relaxed);
+        crate::reg::modify_reg(RCC_CSR, 0, RMVF);
+    }
+}
+
+/// The cause captured at init.
+pub fn cause() -> ResetCause {
+    decode(FLAGS.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthetic_csr_flags_decode_with_watchdog_first() {
+        // A watchdog reset also pulls the pin flag; the watchdog wins.
+        assert_eq!(decode(IWDGRSTF | PINRSTF), ResetCause::Watchdog);
+        assert_eq!(decode(WWDGRSTF), ResetCause::Watchdog);
+        assert_eq!(decode(SFTRSTF | PINRSTF), ResetCause::Software);
+        assert_eq!(decode(BORRSTF | PINRSTF), ResetCause::BrownOut);
+        assert_eq!(decode(PINRSTF), ResetCause::Pin);
+        // Nothing latched (or already cleared): a plain power-on.
+        assert_eq!(decode(0), ResetCause::PowerOn);
+        assert_eq!(cause(), ResetCause::PowerOn);
+    }
+}
@@ -0,0 +1,79 @@
+//! The STM32L4 true random number generator.
+//!
+//! The RNG peripheral samples an analog noise source clocked from the 48 MHz
+//! domain that `SystemClock_Config` brings up. A draw enables the peripheral
+//! clock, waits for a word to become ready and checks the error flags: a
+//! seed or clock error means the hardware cannot vouch for the sample, so
+//! the draw reports no entropy instead of a weak value.
+
+/// RNG register block.
+const RNG_CR: *mut u32 = 0x5006_0800 as *mut u32;
+const RNG_SR: *mut u32 = 0x5006_0804 as *mut u32;
+#[cfg(target_arch = "arm")]
+const RNG_DR: *mut u32 = 0x5006_0808 as *mut u32;
+
+/// RNG enable in `RNG_CR`.
+const RNGEN: u32 = 1 << 2;
+
+/// `RNG_SR` flags: data ready, clock error, seed error.
+const DRDY: u32 = 1 << 0;
+const CECS: u32 = 1 << 1;
+const SECS: u32 = 1 << 2;
+
+/// RNG peripheral clock enable in `RCC_AHB2ENR`.
+#[cfg(target_arch = "arm")]
+const RCC_AHB2ENR: *mut u32 = 0x4002_104C as *mut u32;
+#[cfg(target_arch = "arm")]
+const RCC_RNGEN: u32 = 1 << 18;
+
+/// Polls of `RNG_SR` before giving up on a draw. A word arrives within 42
+/// RNG-clock cycles; this bound is generous even with slow AHB clocks.
+const READY_POLLS: u32 = 10_000;
+
+/// Whether an `RNG_SR` snapshot carries a trustworthy, ready sample.
+fn sample_ready(sr: u32) -> bool {
+    sr & DRDY != 0 && sr & (CECS | SECS) == 0
+}
+
+/// Draws one word from the hardware RNG. `None` when the source reports an
+/// error or never becomes ready. Off target there is no RNG at all.
+pub fn random_u32() -> Option<u32> {
+    #[cfg(target_arch = "arm")]
+    // SAFETY: RNG/RCC register addresses per the STM32L4 reference manual;
+    // single-core, so no concurrent writer of these registers.
+    unsafe {
+        crate::reg::modify_reg(RCC_AHB2ENR, 0, RCC_RNGEN);
+        crate::reg::modify_reg(RNG_CR, 0, RNGEN);
+        for _ in 0..READY_POLLS {
+            let sr = crate::reg::read_reg(RNG_SR);
+            if sr & (CECS | SECS) != 0 {
+                return None;
+            }
+            if sample_ready(sr) {
+                return Some(crate::reg::read_reg(RNG_DR));
+            }
+        }
+        None
+    }
+    #[cfg(not(target_arch = "arm"))]
+    {
+        // Referenced so the host build (with the arm path compiled out)
+        // doesn't flag the register plumbing as dead.
+        let _ = (RNG_CR, RNG_SR, RNGEN, READY_POLLS, sample_ready(DRDY));
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_ready_requires_drdy_and_clean_errors() {
+        assert!(sample_ready(DRDY));
+        assert!(!sample_ready(0));
+        // Error flags disqualify a sample even with data ready.
+        assert!(!sample_ready(DRDY | CECS));
+        assert!(!sample_ready(DRDY | SECS));
+    }
+}
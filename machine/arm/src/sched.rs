@@ -0,0 +1,200 @@
+//! Context switching on Cortex-M: the PendSV handler and FPU state.
+//!
+//! The hardware stacks r0-r3, r12, lr, pc, xPSR (and, with lazy FP stacking,
+//! s0-s15/FPSCR) on exception entry; PendSV saves the remaining callee-saved
+//! registers plus EXC_RETURN. FP-using tasks are detected through bit 4 of
+//! EXC_RETURN (FType), which the core clears when the task has an active FP
+//! context (CONTROL.FPCA), so tasks that never touch the FPU don't pay for
+//! saving s16-s31. EXC_RETURN lives *in* each task's frame: outgoing and
+//! incoming tasks may differ in FP usage, so the restore and the exception
+//! return are keyed off the incoming frame's own value, never the outgoing
+//! one.
+
+/// CPACR: coprocessor access control, CP10/CP11 gate the FPU.
+#[cfg(target_arch = "arm")]
+const CPACR: *mut u32 = 0xE000_ED88 as *mut u32;
+
+/// Grants full access to the FPU (CP10/CP11) for privileged and unprivileged
+/// code and leaves lazy stacking (FPCCR.ASPEN/LSPEN, set out of reset) on.
+pub fn fpu_init() {
+    #[cfg(target_arch = "arm")]
+    // SAFETY: CPACR is a core register; setting the CP10/CP11 fields to
+    // 0b11 (full access) has no other effect.
+    unsafe {
+        crate::reg::modify_reg(CPACR, 0b1111 << 20, 0b1111 << 20);
+    }
+}
+
+/// Words PendSV saves beyond the hardware frame: r4-r11 plus EXC_RETURN.
+pub const CORE_SAVED_WORDS: usize = 9;
+
+/// Additional words for an FP-using task: s16-s31.
+pub const FP_SAVED_WORDS: usize = 16;
+
+/// Bit 4 of EXC_RETURN (FType): clear when the task has an active FP
+/// context.
+pub const EXC_RETURN_FTYPE: u32 = 1 << 4;
+
+/// Whether an EXC_RETURN value denotes an active FP context.
+pub fn fp_active(exc_return: u32) -> bool {
+    exc_return & EXC_RETURN_FTYPE == 0
+}
+
+/// Size in words of the software-saved part of a context frame.
+pub fn saved_frame_words(fp_active: bool) -> usize {
+    if fp_active {
+        CORE_SAVED_WORDS + FP_SAVED_WORDS
+    } else {
+        CORE_SAVED_WORDS
+    }
+}
+
+/// A software-saved frame as the restore path reads it back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestoredFrame {
+    /// r4-r11.
+    pub regs: [u32; 8],
+    /// The EXC_RETURN the handler exits through for this task.
+    pub exc_return: u32,
+    /// s16-s31, present exactly when [`Self::exc_return`] has FType clear.
+    pub fp: Option<[u32; FP_SAVED_WORDS]>,
+    /// Words consumed from the frame.
+    pub words: usize,
+}
+
+/// Host model of the save path, for verifying the layout the assembly below
+/// produces. Writes the words as they end up in memory from the final stack
+/// pointer upwards — the FP bank is pushed first (`vstmdb`), then r4-r11
+/// and EXC_RETURN, so the core bank sits below s16-s31. The FP bank is
+/// included exactly when `exc_return` has FType clear. Returns the word
+/// count.
+pub fn model_saved_frame(
+    regs: &[u32; 8],
+    exc_return: u32,
+    fp: &[u32; FP_SAVED_WORDS],
+    out: &mut [u32; CORE_SAVED_WORDS + FP_SAVED_WORDS],
+) -> usize {
+    out[..8].copy_from_slice(regs);
+    out[8] = exc_return;
+    if fp_active(exc_return) {
+        out[CORE_SAVED_WORDS..].copy_from_slice(fp);
+        CORE_SAVED_WORDS + FP_SAVED_WORDS
+    } else {
+        CORE_SAVED_WORDS
+    }
+}
+
+/// Host model of the restore path: consumes a frame laid out by
+/// [`model_saved_frame`] and keys both the FP restore and the exception
+/// return off the EXC_RETURN word stored in the *incoming* frame, exactly
+/// like the assembly — the outgoing task's value must play no part.
+pub fn model_restored_frame(frame: &[u32; CORE_SAVED_WORDS + FP_SAVED_WORDS]) -> RestoredFrame {
+    let mut regs = [0u32; 8];
+    regs.copy_from_slice(&frame[..8]);
+    let exc_return = frame[8];
+    let fp = fp_active(exc_return).then(|| {
+        let mut fp = [0u32; FP_SAVED_WORDS];
+        fp.copy_from_slice(&frame[CORE_SAVED_WORDS..]);
+        fp
+    });
+    RestoredFrame {
+        regs,
+        exc_return,
+        fp,
+        words: saved_frame_words(fp_active(exc_return)),
+    }
+}
+
+// The PendSV handler. `schedule_next` (kernel side) receives the outgoing
+// task's stack pointer and returns the incoming one.
+#[cfg(target_arch = "arm")]
+core::arch::global_asm!(
+    r#"
+    .syntax unified
+    .global PendSV_Handler
+    .type PendSV_Handler, %function
+PendSV_Handler:
+    mrs     r0, psp
+    // FType (bit 4) is clear when the outgoing task has an active FP
+    // context; only then does s16-s31 need saving (s0-s15 are lazily
+    // stacked by hardware).
+    tst     lr, #0x10
+    it      eq
+    vstmdbeq r0!, {{s16-s31}}
+    // EXC_RETURN is saved with the frame: the incoming task may differ in
+    // FP usage, so its own value must drive the restore below.
+    stmdb   r0!, {{r4-r11, lr}}
+    bl      schedule_next
+    ldmia   r0!, {{r4-r11, lr}}
+    tst     lr, #0x10
+    it      eq
+    vldmiaeq r0!, {{s16-s31}}
+    msr     psp, r0
+    bx      lr
+    .size PendSV_Handler, . - PendSV_Handler
+"#
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Thread mode, PSP, FP context active.
+    const EXC_RETURN_FP: u32 = 0xFFFF_FFED;
+    /// Thread mode, PSP, standard frame.
+    const EXC_RETURN_STD: u32 = 0xFFFF_FFFD;
+
+    #[test]
+    fn fp_frame_adds_sixteen_words_above_the_core_bank() {
+        let regs: [u32; 8] = core::array::from_fn(|i| 0x4000 + i as u32);
+        let fp: [u32; FP_SAVED_WORDS] = core::array::from_fn(|i| 0x5000 + i as u32);
+        let mut out = [0u32; CORE_SAVED_WORDS + FP_SAVED_WORDS];
+
+        let words = model_saved_frame(&regs, EXC_RETURN_FP, &fp, &mut out);
+        assert_eq!(words, saved_frame_words(true));
+        // r4-r11 from the stack pointer upwards, then EXC_RETURN, then
+        // s16-s31.
+        assert_eq!(&out[..8], &regs);
+        assert_eq!(out[8], EXC_RETURN_FP);
+        assert_eq!(&out[CORE_SAVED_WORDS..], &fp);
+    }
+
+    #[test]
+    fn non_fp_frame_saves_only_the_core_bank() {
+        let regs: [u32; 8] = core::array::from_fn(|i| i as u32);
+        let fp = [0u32; FP_SAVED_WORDS];
+        let mut out = [0u32; CORE_SAVED_WORDS + FP_SAVED_WORDS];
+
+        let words = model_saved_frame(&regs, EXC_RETURN_STD, &fp, &mut out);
+        assert_eq!(words, saved_frame_words(false));
+        assert_eq!(&out[..8], &regs);
+        assert_eq!(out[8], EXC_RETURN_STD);
+    }
+
+    #[test]
+    fn restore_keys_fp_state_off_the_incoming_frame() {
+        // An FP task and a non-FP task, each with its own saved frame —
+        // the shape a first FP↔non-FP switch encounters.
+        let regs: [u32; 8] = core::array::from_fn(|i| 0x6000 + i as u32);
+        let fp: [u32; FP_SAVED_WORDS] = core::array::from_fn(|i| 0x7000 + i as u32);
+        let mut fp_frame = [0u32; CORE_SAVED_WORDS + FP_SAVED_WORDS];
+        let mut std_frame = [0u32; CORE_SAVED_WORDS + FP_SAVED_WORDS];
+        model_saved_frame(&regs, EXC_RETURN_FP, &fp, &mut fp_frame);
+        model_saved_frame(&regs, EXC_RETURN_STD, &fp, &mut std_frame);
+
+        // Restoring the FP task pops the FP bank and returns through its
+        // own EXC_RETURN, regardless of which task ran before.
+        let restored = model_restored_frame(&fp_frame);
+        assert_eq!(restored.regs, regs);
+        assert_eq!(restored.exc_return, EXC_RETURN_FP);
+        assert_eq!(restored.fp, Some(fp));
+        assert_eq!(restored.words, saved_frame_words(true));
+
+        // Restoring the non-FP task consumes nine words and leaves the FP
+        // bank alone.
+        let restored = model_restored_frame(&std_frame);
+        assert_eq!(restored.exc_return, EXC_RETURN_STD);
+        assert_eq!(restored.fp, None);
+        assert_eq!(restored.words, saved_frame_words(false));
+    }
+}
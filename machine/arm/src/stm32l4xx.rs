@@ -0,0 +1,24 @@
+//! Bindings to the STM32L4 C HAL and chip constants.
+
+/// Core clock frequency configured by `SystemClock_Config`.
+pub const HCLK_HZ: u32 = 80_000_000;
+
+#[cfg(target_arch = "arm")]
+extern "C" {
+    pub fn HAL_Init() -> i32;
+    pub fn SystemClock_Config();
+    pub fn HAL_SYSTICK_Config(ticks: u32) -> u32;
+    /// The HAL's tick counter; increments once per SysTick interrupt and
+    /// wraps every 2^32 ticks.
+    pub fn HAL_GetTick() -> u32;
+    pub fn NVIC_SystemReset() -> !;
+    pub fn uart_write(bytes: *const u8, len: usize);
+    /// Busy-waits until the UART TC flag reports the shift register empty.
+    pub fn uart_flush();
+    /// Busy-waits until the UART TXE flag reports the data register free.
+    pub fn uart_txe_wait();
+    /// Writes one byte to the UART data register (TXE must be set).
+    pub fn uart_write_byte(byte: u8);
+    /// Enables or disables the TXE interrupt.
+    pub fn uart_txe_enable(enable: bool);
+}
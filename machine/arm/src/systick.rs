@@ -0,0 +1,49 @@
+//! SysTick reload computation.
+//!
+//! Kept free of hardware access so the arithmetic is host-testable.
+
+use hal_api::MachineError;
+
+/// The SysTick reload register is 24 bits wide.
+pub const MAX_RELOAD: u32 = 0x00FF_FFFF;
+
+/// Computes the SysTick reload value for a tick of `hz` with the core running
+/// at `hclk` Hz.
+///
+/// Rejects frequencies of zero, above `hclk`, or so low that the required
+/// reload exceeds the 24-bit counter.
+pub fn reload_for(hclk: u32, hz: u32) -> Result<u32, MachineError> {
+    if hz == 0 || hz > hclk {
+        return Err(MachineError::InvalidConfig);
+    }
+    let reload = hclk / hz - 1;
+    if reload == 0 || reload > MAX_RELOAD {
+        return Err(MachineError::InvalidConfig);
+    }
+    Ok(reload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reload_for_common_frequencies() {
+        // 80 MHz core, 1 kHz tick -> 80_000 cycles per tick.
+        assert_eq!(reload_for(80_000_000, 1_000), Ok(79_999));
+        // 10 Hz (the old hardcoded 100 ms tick).
+        assert_eq!(reload_for(80_000_000, 10), Ok(7_999_999));
+    }
+
+    #[test]
+    fn reload_rejects_out_of_range_frequencies() {
+        assert_eq!(reload_for(80_000_000, 0), Err(MachineError::InvalidConfig));
+        // Faster than the core clock.
+        assert_eq!(
+            reload_for(80_000_000, 160_000_000),
+            Err(MachineError::InvalidConfig)
+        );
+        // Too slow for the 24-bit counter: 1 Hz at 80 MHz needs 80M cycles.
+        assert_eq!(reload_for(80_000_000, 1), Err(MachineError::InvalidConfig));
+    }
+}
@@ -0,0 +1,102 @@
+//! A 64-bit tick clock over the STM HAL's 32-bit tick counter.
+//!
+//! `HAL_GetTick` increments once per SysTick interrupt and wraps every 2^32
+//! ticks. The accumulation arithmetic is kept free of hardware access so it
+//! is host-testable; only [`tick_count`] touches the binding.
+
+use crate::uart::IrqCell;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// The tick rate `HAL_Init` configures before [`set_tick_hz`] is called:
+/// a 1 ms SysTick.
+pub const DEFAULT_TICK_HZ: u32 = 1_000;
+
+/// The currently configured tick rate, kept in sync with the SysTick setup
+/// by `configure_tick` (e.g. 1 kHz for a 1 ms tick, 10 Hz for 100 ms).
+static TICK_HZ: AtomicU32 = AtomicU32::new(DEFAULT_TICK_HZ);
+
+/// Records the tick rate the SysTick was configured with.
+pub fn set_tick_hz(hz: u32) {
+    TICK_HZ.store(hz, Ordering::SeqCst);
+}
+
+/// The configured tick rate in Hz.
+pub fn tick_hz() -> u32 {
+    TICK_HZ.load(Ordering::SeqCst)
+}
+
+/// Widens a wrapping 32-bit counter to a monotonic 64-bit count.
+pub struct TickAccumulator {
+    last: u32,
+    total: u64,
+}
+
+impl TickAccumulator {
+    pub const fn new() -> Self {
+        Self { last: 0, total: 0 }
+    }
+
+    /// Folds a fresh counter sample into the running total. The wrapping
+    /// subtraction keeps the delta correct across a 32-bit wrap, as long as
+    /// samples are at most one counter period apart (about 49 days at 1 kHz).
+    pub fn update(&mut self, now: u32) -> u64 {
+        self.total += now.wrapping_sub(self.last) as u64;
+        self.last = now;
+        self.total
+    }
+}
+
+impl Default for TickAccumulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts a tick count at `hz` to nanoseconds. Exact for rates dividing
+/// 1 GHz, which covers the 1 ms and 100 ms configurations.
+pub fn ticks_to_ns(ticks: u64, hz: u32) -> u64 {
+    ticks * (1_000_000_000 / hz as u64)
+}
+
+/// The shared accumulator, serialized against the tick interrupt.
+static ACCUM: IrqCell<TickAccumulator> = IrqCell::new(TickAccumulator::new());
+
+/// Ticks elapsed since boot, accumulated to 64 bits across the HAL
+/// counter's wrap.
+pub fn tick_count() -> u64 {
+    #[cfg(target_arch = "arm")]
+    // SAFETY: HAL_GetTick only reads the HAL's tick variable.
+    let now = unsafe { crate::stm32l4xx::HAL_GetTick() };
+    #[cfg(not(target_arch = "arm"))]
+    let now = 0;
+    ACCUM.with(|accum| accum.update(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulation_is_monotonic_across_a_32_bit_wrap() {
+        let mut accum = TickAccumulator::new();
+        assert_eq!(accum.update(10), 10);
+        assert_eq!(accum.update(u32::MAX), u32::MAX as u64);
+        // The counter wraps: 6 more ticks land at 5.
+        assert_eq!(accum.update(5), u32::MAX as u64 + 6);
+        // An unchanged counter adds nothing.
+        assert_eq!(accum.update(5), u32::MAX as u64 + 6);
+    }
+
+    #[test]
+    fn ticks_convert_exactly_for_both_tick_configurations() {
+        // 1 ms tick: one tick is a millisecond.
+        assert_eq!(ticks_to_ns(1, 1_000), 1_000_000);
+        // 100 ms tick (10 Hz): one tick is a tenth of a second.
+        assert_eq!(ticks_to_ns(1, 10), 100_000_000);
+        // Past the 32-bit range without overflowing.
+        assert_eq!(
+            ticks_to_ns(u32::MAX as u64 + 6, 1_000),
+            (u32::MAX as u64 + 6) * 1_000_000
+        );
+    }
+}
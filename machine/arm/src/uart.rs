@@ -0,0 +1,276 @@
+//! Interrupt-driven UART transmission.
+//!
+//! `ArmMachine::print` used to busy-write every byte with interrupts off for
+//! the whole string. Instead, printed bytes now go into a fixed TX ring;
+//! the USART's TXE interrupt pulls one byte per fire until the ring drains.
+//! `flush()` busy-waits until both the ring and the shift register are
+//! empty, for the paths (panic, reset) that must not lose output.
+//!
+//! A full ring either drops the rest of the write — counting the losses so
+//! a later reader can tell output went missing — or, when
+//! `OSIRIS_UART_BLOCKING` is set, spins until the interrupt makes room.
+
+/// Capacity of the TX ring in bytes.
+pub const TX_RING_SIZE: usize = 256;
+
+/// A single-producer byte queue drained one byte at a time from the TXE
+/// interrupt. Logic is hardware-free so host tests can model the drain.
+pub struct TxRing<const N: usize> {
+    buf: [u8; N],
+    /// Next write position.
+    head: usize,
+    /// Next read position; `head == tail` means empty.
+    tail: usize,
+    /// Bytes dropped because the ring was full.
+    dropped: u32,
+}
+
+impl<const N: usize> TxRing<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            head: 0,
+            tail: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    fn is_full(&self) -> bool {
+        (self.head + 1) % N == self.tail
+    }
+
+    /// Enqueues one byte. Returns `false` (and counts the byte as dropped)
+    /// when the ring is full.
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.is_full() {
+            self.dropped = self.dropped.saturating_add(1);
+            return false;
+        }
+        self.buf[self.head] = byte;
+        self.head = (self.head + 1) % N;
+        true
+    }
+
+    /// Takes the next byte to transmit; `None` once drained.
+    pub fn pop(&mut self) -> Option<u8> {
+        if self.is_empty() {
+            return None;
+        }
+        let byte = self.buf[self.tail];
+        self.tail = (self.tail + 1) % N;
+        Some(byte)
+    }
+
+    /// Bytes lost to a full ring since boot.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+impl<const N: usize> Default for TxRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a full ring blocks the writer instead of dropping bytes.
+/// Dropping is the default: print must never stall a real-time path.
+const BLOCK_ON_FULL: bool = option_env!("OSIRIS_UART_BLOCKING").is_some();
+
+/// Interior-mutable state shared between thread mode and the TXE interrupt.
+/// Access masks interrupts for just the closure — a handful of instructions
+/// per byte, instead of the old interrupts-off busy-write of whole strings.
+pub(crate) struct IrqCell<T>(core::cell::UnsafeCell<T>);
+
+// SAFETY: access is serialized by masking interrupts on the single core.
+unsafe impl<T: Send> Sync for IrqCell<T> {}
+
+impl<T> IrqCell<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Self(core::cell::UnsafeCell::new(value))
+    }
+
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let primask = disable_irqs();
+        // SAFETY: interrupts are masked and the core is single: nothing else
+        // can be inside this cell.
+        let result = f(unsafe { &mut *self.0.get() });
+        restore_irqs(primask);
+        result
+    }
+}
+
+/// Masks interrupts, returning the previous PRIMASK for [`restore_irqs`].
+#[inline]
+fn disable_irqs() -> u32 {
+    #[cfg(target_arch = "arm")]
+    // SAFETY: reading PRIMASK and masking interrupts has no other effects.
+    unsafe {
+        let primask: u32;
+        core::arch::asm!("mrs {}, primask", "cpsid i", out(reg) primask);
+        primask
+    }
+    #[cfg(not(target_arch = "arm"))]
+    0
+}
+
+/// Restores the PRIMASK state captured by [`disable_irqs`].
+#[inline]
+fn restore_irqs(primask: u32) {
+    #[cfg(target_arch = "arm")]
+    if primask & 1 == 0 {
+        // SAFETY: re-enables interrupts only if they were enabled before.
+        unsafe { core::arch::asm!("cpsie i") };
+    }
+    #[cfg(not(target_arch = "arm"))]
+    let _ = primask;
+}
+
+/// The TX ring shared between `print` and the TXE interrupt.
+static TX_RING: IrqCell<TxRing<TX_RING_SIZE>> = IrqCell::new(TxRing::new());
+
+/// Enqueues `s` for transmission and kicks the TXE interrupt on. Returns
+/// immediately; a full ring drops (or briefly blocks, see
+/// [`BLOCK_ON_FULL`]) instead of stalling with interrupts off.
+pub fn write(s: &str) {
+    for &byte in s.as_bytes() {
+        loop {
+            let pushed = TX_RING.with(|ring| ring.push(byte));
+            if pushed || !BLOCK_ON_FULL {
+                break;
+            }
+            // Blocking mode: wait for the interrupt to free a slot.
+            core::hint::spin_loop();
+        }
+    }
+    enable_txe_interrupt();
+}
+
+/// Whether the TXE interrupt can never fire right now: interrupts masked
+/// via PRIMASK, or executing in handler mode (IPSR != 0) at a priority the
+/// USART IRQ cannot preempt — the situation a panic from a fault or SysTick
+/// handler leaves us in.
+fn txe_irq_unavailable() -> bool {
+    #[cfg(target_arch = "arm")]
+    // SAFETY: reading PRIMASK and IPSR has no side effects.
+    unsafe {
+        let primask: u32;
+        let ipsr: u32;
+        core::arch::asm!("mrs {}, primask", out(reg) primask);
+        core::arch::asm!("mrs {}, ipsr", out(reg) ipsr);
+        primask & 1 != 0 || ipsr != 0
+    }
+    #[cfg(not(target_arch = "arm"))]
+    false
+}
+
+/// Busy-waits until the ring has drained and the hardware reports the last
+/// byte shifted out.
+///
+/// When the TXE interrupt cannot fire — the panic path runs with interrupts
+/// masked or from handler mode — waiting on the interrupt would hang
+/// forever, so the ring is drained by hand instead: pop each byte and write
+/// it directly once the TXE flag reports the data register free. Either way
+/// this always terminates.
+pub fn flush() {
+    if txe_irq_unavailable() {
+        while let Some(byte) = TX_RING.with(|ring| ring.pop()) {
+            #[cfg(target_arch = "arm")]
+            // SAFETY: waits for TXE first, so the data register is free.
+            unsafe {
+                crate::stm32l4xx::uart_txe_wait();
+                crate::stm32l4xx::uart_write_byte(byte);
+            }
+            #[cfg(not(target_arch = "arm"))]
+            let _ = byte;
+        }
+    } else {
+        while !TX_RING.with(|ring| ring.is_empty()) {
+            core::hint::spin_loop();
+        }
+    }
+    #[cfg(target_arch = "arm")]
+    // SAFETY: polls the transmission-complete flag; no other side effects.
+    unsafe {
+        crate::stm32l4xx::uart_flush();
+    }
+}
+
+/// TXE interrupt body: feeds the next byte to the data register, or turns
+/// the interrupt off once the ring is drained. Called from the USART IRQ
+/// handler in the C HAL glue.
+#[no_mangle]
+pub extern "C" fn uart_txe_irq() {
+    match TX_RING.with(|ring| ring.pop()) {
+        Some(byte) => {
+            #[cfg(target_arch = "arm")]
+            // SAFETY: TXE was just signalled, so the data register is free.
+            unsafe {
+                crate::stm32l4xx::uart_write_byte(byte);
+            }
+            #[cfg(not(target_arch = "arm"))]
+            let _ = byte;
+        }
+        None => disable_txe_interrupt(),
+    }
+}
+
+fn enable_txe_interrupt() {
+    #[cfg(target_arch = "arm")]
+    // SAFETY: sets the TXE interrupt enable; the ISR owns the data register.
+    unsafe {
+        crate::stm32l4xx::uart_txe_enable(true);
+    }
+}
+
+fn disable_txe_interrupt() {
+    #[cfg(target_arch = "arm")]
+    // SAFETY: clears the TXE interrupt enable.
+    unsafe {
+        crate::stm32l4xx::uart_txe_enable(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_returns_bytes_in_order_then_runs_dry() {
+        let mut ring: TxRing<8> = TxRing::new();
+        for byte in b"abc" {
+            assert!(ring.push(*byte));
+        }
+
+        // The interrupt drains one byte per fire, oldest first.
+        assert_eq!(ring.pop(), Some(b'a'));
+        assert_eq!(ring.pop(), Some(b'b'));
+        // Interleaved producer writes keep their order.
+        assert!(ring.push(b'd'));
+        assert_eq!(ring.pop(), Some(b'c'));
+        assert_eq!(ring.pop(), Some(b'd'));
+        assert_eq!(ring.pop(), None);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn full_ring_drops_and_counts() {
+        // One slot is kept free to distinguish full from empty.
+        let mut ring: TxRing<4> = TxRing::new();
+        assert!(ring.push(b'x'));
+        assert!(ring.push(b'y'));
+        assert!(ring.push(b'z'));
+        assert!(!ring.push(b'!'));
+        assert!(!ring.push(b'!'));
+        assert_eq!(ring.dropped(), 2);
+
+        // Draining makes room again; the drop counter is cumulative.
+        assert_eq!(ring.pop(), Some(b'x'));
+        assert!(ring.push(b'!'));
+        assert_eq!(ring.dropped(), 2);
+    }
+}
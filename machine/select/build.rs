@@ -0,0 +1,23 @@
+//! Turns the `OSIRIS_CHIP` selection into a `chip = "..."` cfg that lib.rs
+//! dispatches on. An unknown chip fails the build here, with the valid
+//! choices in the message, instead of surfacing as a missing-item error.
+
+include!("src/chip.rs");
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=OSIRIS_CHIP");
+    println!(
+        "cargo:rustc-check-cfg=cfg(chip, values({}))",
+        KNOWN_CHIPS
+            .iter()
+            .map(|chip| format!("\"{chip}\""))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let requested = std::env::var("OSIRIS_CHIP").ok();
+    match resolve_chip(requested.as_deref()) {
+        Ok(chip) => println!("cargo:rustc-cfg=chip=\"{chip}\""),
+        Err(msg) => panic!("{msg}"),
+    }
+}
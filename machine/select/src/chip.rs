@@ -0,0 +1,41 @@
+// Chip-family selection logic, shared between the build script (which
+// includes this file and turns `OSIRIS_CHIP` into a `chip = "..."` cfg) and
+// the host tests below. Plain comments only: `include!` pastes this file
+// into the build script, where inner doc comments would not parse.
+
+/// Chip families a build can select via `OSIRIS_CHIP`.
+pub const KNOWN_CHIPS: &[&str] = &["stm32l4xx"];
+
+/// The chip the build targets: the `OSIRIS_CHIP` value when set, defaulting
+/// to the stm32l4xx the ARM machine has always targeted. Unknown names are
+/// a configuration error naming the valid choices.
+pub fn resolve_chip(requested: Option<&str>) -> Result<&'static str, String> {
+    match requested {
+        None => Ok(KNOWN_CHIPS[0]),
+        Some(chip) => match KNOWN_CHIPS.iter().find(|&&known| known == chip) {
+            Some(&known) => Ok(known),
+            None => Err(format!(
+                "unknown chip '{chip}' in OSIRIS_CHIP; known chips: {}",
+                KNOWN_CHIPS.join(", ")
+            )),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_chip_and_the_default_resolve() {
+        assert_eq!(resolve_chip(None), Ok("stm32l4xx"));
+        assert_eq!(resolve_chip(Some("stm32l4xx")), Ok("stm32l4xx"));
+    }
+
+    #[test]
+    fn unknown_chip_is_a_clear_error() {
+        let err = resolve_chip(Some("stm32f4xx")).unwrap_err();
+        assert!(err.contains("'stm32f4xx'"));
+        assert!(err.contains("stm32l4xx"));
+    }
+}
@@ -0,0 +1,29 @@
+//! Compile-time selection of the machine implementation.
+//!
+//! Kernel code imports `hal::Machine` and never names a concrete machine.
+
+#![cfg_attr(not(test), no_std)]
+
+pub use hal_api::*;
+
+// The build script resolves `OSIRIS_CHIP` (see chip.rs) into the `chip`
+// cfg dispatched on below; the logic is compiled here too so it stays
+// host-testable.
+#[cfg(test)]
+mod chip;
+
+#[cfg(all(target_arch = "arm", chip = "stm32l4xx"))]
+pub use hal_arm::ArmMachine as Machine;
+
+// Every known chip must map to a machine crate above; the build script
+// rejects unknown `OSIRIS_CHIP` values before it gets this far.
+#[cfg(all(target_arch = "arm", not(chip = "stm32l4xx")))]
+compile_error!("no machine crate for the selected chip");
+
+#[cfg(not(target_arch = "arm"))]
+pub use hal_testing::TestingMachine as Machine;
+
+// Re-exported under its own name as well, so host tests can reach
+// testing-only helpers like output capture.
+#[cfg(not(target_arch = "arm"))]
+pub use hal_testing::TestingMachine;
@@ -0,0 +1,177 @@
+//! Static reachability analysis over the resolved dependency graph.
+//!
+//! An option is *unreachable* when no value assignment whatsoever can enable
+//! it: some dependency either does not resolve, demands a value outside its
+//! target's type domain, or targets an option that is itself unreachable.
+//! Such options are dead weight in the tree and almost always an authoring
+//! mistake.
+
+use std::collections::HashSet;
+
+use crate::node::{ConfigKey, ConfigTree, Dependency, DependencyGroup};
+
+/// Finds the options that can never become enabled, together with a
+/// human-readable reason. Results are sorted by full key for stable output.
+pub fn unreachable_options(tree: &ConfigTree) -> Vec<(ConfigKey, String)> {
+    // Fixpoint in the style of `ConfigState::update_dependency_states`, but
+    // over value *domains* instead of current values: a dependency is
+    // satisfiable iff its target is reachable and the required value lies
+    // inside the target's type.
+    let mut reachable: HashSet<ConfigKey> = tree
+        .keys()
+        .filter(|&k| tree.node(k).as_option().is_some())
+        .collect();
+    loop {
+        let next: HashSet<ConfigKey> = reachable
+            .iter()
+            .copied()
+            .filter(|&key| {
+                let Some(option) = tree.node(key).as_option() else {
+                    return false;
+                };
+                let satisfiable = |dep: &Dependency| match dep.resolved {
+                    Some(target) => {
+                        reachable.contains(&target)
+                            && tree
+                                .node(target)
+                                .as_option()
+                                .is_some_and(|t| t.ty.validate(&dep.value).is_ok())
+                    }
+                    None => false,
+                };
+                let in_group = |group: DependencyGroup| {
+                    option.depends_on.iter().filter(move |dep| dep.group == group)
+                };
+                // Mirrors `ConfigState::update_dependency_states`: all-group
+                // entries must each be satisfiable, an any-group needs one.
+                in_group(DependencyGroup::All).all(satisfiable)
+                    && (in_group(DependencyGroup::Any).next().is_none()
+                        || in_group(DependencyGroup::Any).any(satisfiable))
+            })
+            .collect();
+        if next == reachable {
+            break;
+        }
+        reachable = next;
+    }
+
+    let mut findings: Vec<(ConfigKey, String)> = tree
+        .keys()
+        .filter(|&k| tree.node(k).as_option().is_some() && !reachable.contains(&k))
+        .map(|key| (key, reason(tree, key, &reachable)))
+        .collect();
+    findings.sort_by_key(|&(key, _)| tree.full_key(key));
+    findings
+}
+
+/// Explains the first unsatisfiable dependency of an unreachable option.
+fn reason(tree: &ConfigTree, key: ConfigKey, reachable: &HashSet<ConfigKey>) -> String {
+    let Some(option) = tree.node(key).as_option() else {
+        return "not an option".to_string();
+    };
+    for dep in option
+        .depends_on
+        .iter()
+        .filter(|dep| dep.group == DependencyGroup::All)
+    {
+        match dep.resolved {
+            None => return format!("its dependency '{}' does not resolve", dep.raw_key),
+            Some(target) => {
+                let full = tree.full_key(target);
+                let Some(target_option) = tree.node(target).as_option() else {
+                    continue;
+                };
+                if target_option.ty.validate(&dep.value).is_err() {
+                    return format!("no value of '{full}' can ever equal {}", dep.value);
+                }
+                if !reachable.contains(&target) {
+                    return format!("its dependency '{full}' is itself unreachable");
+                }
+            }
+        }
+    }
+    let mut any_deps = option
+        .depends_on
+        .iter()
+        .filter(|dep| dep.group == DependencyGroup::Any)
+        .peekable();
+    if any_deps.peek().is_some()
+        && !any_deps.any(|dep| match dep.resolved {
+            Some(target) => {
+                reachable.contains(&target)
+                    && tree
+                        .node(target)
+                        .as_option()
+                        .is_some_and(|t| t.ty.validate(&dep.value).is_ok())
+            }
+            None => false,
+        })
+    {
+        return "none of its `any` dependencies can ever be satisfied".to_string();
+    }
+    "its dependency chain cannot be satisfied".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{ConfigNode, ConfigValue, Dependency};
+    use crate::testutil::{bool_option, int_option, tree_of};
+
+    #[test]
+    fn option_depending_on_an_impossible_value_is_flagged() {
+        // `feature` demands count = 99, but count's domain is [0, 8]:
+        // no assignment can ever enable it.
+        let mut feature = bool_option("feature", false, &[]);
+        if let ConfigNode::Option(o) = &mut feature {
+            o.depends_on.push(Dependency {
+                raw_key: "count".to_string(),
+                resolved: None,
+                value: ConfigValue::Int(99),
+                group: DependencyGroup::default(),
+            });
+        }
+        let tree = tree_of(vec![int_option("count", 4, 0, 8), feature]);
+
+        let findings = unreachable_options(&tree);
+        assert_eq!(findings.len(), 1);
+        let (key, reason) = &findings[0];
+        assert_eq!(tree.full_key(*key), "feature");
+        assert!(reason.contains("no value of 'count'"), "got: {reason}");
+    }
+
+    #[test]
+    fn unreachability_propagates_to_dependents() {
+        let mut dead = bool_option("dead", false, &[]);
+        if let ConfigNode::Option(o) = &mut dead {
+            o.depends_on.push(Dependency {
+                raw_key: "count".to_string(),
+                resolved: None,
+                value: ConfigValue::Int(99),
+                group: DependencyGroup::default(),
+            });
+        }
+        let tree = tree_of(vec![
+            int_option("count", 4, 0, 8),
+            dead,
+            bool_option("dependent", false, &[("dead", true)]),
+        ]);
+
+        let findings = unreachable_options(&tree);
+        let keys: Vec<String> = findings
+            .iter()
+            .map(|&(key, _)| tree.full_key(key).to_string())
+            .collect();
+        assert_eq!(keys, ["dead", "dependent"]);
+        assert!(findings[1].1.contains("itself unreachable"));
+    }
+
+    #[test]
+    fn satisfiable_chains_are_not_flagged() {
+        let tree = tree_of(vec![
+            bool_option("driver", false, &[]),
+            bool_option("feature", false, &[("driver", true)]),
+        ]);
+        assert!(unreachable_options(&tree).is_empty());
+    }
+}
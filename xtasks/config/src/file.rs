@@ -0,0 +1,862 @@
+//! Discovery and parsing of `options.toml` files.
+
+use std::path::{Path, PathBuf};
+
+use toml_edit::{ImDocument, Item, Table};
+
+use crate::node::{
+    Attribute, ConfigCategory, ConfigKey, ConfigNode, ConfigOption, ConfigTree, ConfigType,
+    ConfigValue, Dependency, DependencyGroup, DisplayBase, RebuildKind,
+};
+use crate::report::Report;
+
+/// A parsed `options.toml`, with linking information that `resolve` needs.
+#[derive(Debug)]
+pub struct ParsedFile {
+    pub path: PathBuf,
+    /// Value of `metadata.parent`: the full category key this file's nodes
+    /// attach under, or `None` for the root.
+    pub parent: Option<String>,
+    /// Top-level nodes declared by this file.
+    pub nodes: Vec<ConfigKey>,
+}
+
+/// Recursively collects every `options.toml` below `root`, skipping build
+/// output directories. The result is sorted for deterministic parse order.
+pub fn load_files(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_dir() {
+                let name = entry.file_name();
+                if name != "target" && name != ".git" {
+                    stack.push(path);
+                }
+            } else if path.file_name().is_some_and(|n| n == "options.toml") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Loads and parses every `options.toml` under `root` into one tree.
+///
+/// Parsing continues across files on error so the user sees as many problems
+/// as possible in one run.
+pub fn load_config(root: &Path) -> Result<(ConfigTree, Vec<ParsedFile>), Vec<Report>> {
+    let mut tree = ConfigTree::default();
+    let mut parsed = Vec::new();
+    let mut reports = Vec::new();
+    let mut errored = false;
+
+    let files = load_files(root).map_err(|err| vec![Report::error(err.to_string())])?;
+    for path in files {
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                reports.push(Report::error(format!("{}: {err}", path.display())));
+                errored = true;
+                continue;
+            }
+        };
+        match parse_content(&path, &content, &mut tree) {
+            Ok(file) => parsed.push(file),
+            Err(mut errs) => {
+                reports.append(&mut errs);
+                errored = true;
+            }
+        }
+    }
+
+    if errored {
+        Err(reports)
+    } else {
+        Ok((tree, parsed))
+    }
+}
+
+/// Parses one `options.toml` into nodes appended to `tree`.
+///
+/// Independent errors (several malformed options, say) are collected and
+/// reported together; only unparseable TOML stops immediately, since nothing
+/// after it can be trusted.
+pub fn parse_content(
+    path: &Path,
+    content: &str,
+    tree: &mut ConfigTree,
+) -> Result<ParsedFile, Vec<Report>> {
+    let doc: ImDocument<&str> = ImDocument::parse(content)
+        .map_err(|err: toml_edit::TomlError| vec![Report::error(format!("{}: {err}", path.display()))])?;
+
+    let parent = doc
+        .get("metadata")
+        .and_then(Item::as_table)
+        .and_then(|m| m.get("parent"))
+        .and_then(Item::as_str)
+        .map(str::to_string);
+
+    let mut file = ParsedFile {
+        path: path.to_path_buf(),
+        parent,
+        nodes: Vec::new(),
+    };
+
+    let mut reports = Vec::new();
+
+    // Categories first, so options can be placed into them by local key.
+    let mut local_categories: Vec<(String, ConfigKey)> = Vec::new();
+    if let Some(categories) = doc.get("categories").and_then(Item::as_table) {
+        for (key, item) in categories.iter() {
+            let table = match item.as_table() {
+                Some(table) => table,
+                None => {
+                    reports.push(spanned(path, content, item, "category must be a table"));
+                    continue;
+                }
+            };
+            let attributes = match parse_config_attributes(path, content, table) {
+                Ok(attributes) => attributes,
+                Err(mut errs) => {
+                    reports.append(&mut errs);
+                    continue;
+                }
+            };
+            let category = ConfigCategory {
+                key: key.to_string(),
+                name: get_str(table, "name").unwrap_or(key).to_string(),
+                description: get_str(table, "description").unwrap_or("").to_string(),
+                attributes,
+                parent: None,
+                children: Vec::new(),
+            };
+            let id = tree.push(ConfigNode::Category(category), path.to_path_buf());
+            local_categories.push((key.to_string(), id));
+            file.nodes.push(id);
+        }
+    }
+
+    if let Some(options) = doc.get("options").and_then(Item::as_table) {
+        for (key, item) in options.iter() {
+            let table = match item.as_table() {
+                Some(table) => table,
+                None => {
+                    reports.push(spanned(path, content, item, "option must be a table"));
+                    continue;
+                }
+            };
+            let option = match parse_config_option(path, content, key, table) {
+                Ok(option) => option,
+                Err(mut errs) => {
+                    reports.append(&mut errs);
+                    continue;
+                }
+            };
+            let placement = get_str(table, "category").map(str::to_string);
+            let id = tree.push(ConfigNode::Option(option), path.to_path_buf());
+            match placement {
+                Some(local) => {
+                    let Some((_, cat)) = local_categories.iter().find(|(k, _)| *k == local) else {
+                        reports.push(spanned(
+                            path,
+                            content,
+                            item,
+                            format!("option '{key}' references unknown category '{local}'"),
+                        ));
+                        continue;
+                    };
+                    let cat = *cat;
+                    if let ConfigNode::Category(c) = tree.node_mut(cat) {
+                        c.children.push(id);
+                    }
+                    if let ConfigNode::Option(o) = tree.node_mut(id) {
+                        o.parent = Some(cat);
+                    }
+                }
+                None => file.nodes.push(id),
+            }
+        }
+    }
+
+    if reports.is_empty() {
+        Ok(file)
+    } else {
+        Err(reports)
+    }
+}
+
+/// Parses a single `[options.<key>]` table into a [`ConfigOption`].
+pub fn parse_config_option(
+    path: &Path,
+    content: &str,
+    key: &str,
+    table: &Table,
+) -> Result<ConfigOption, Vec<Report>> {
+    let ty = match table.get("type") {
+        Some(item) => parse_config_type(path, content, item)?,
+        None => {
+            return Err(vec![Report::error(format!(
+                "{}: option '{key}' is missing a type",
+                path.display()
+            ))])
+        }
+    };
+    let default = match table.get("default") {
+        Some(item) => parse_config_default(path, content, item, &ty)?,
+        None => ParsedDefault {
+            base: type_default(&ty),
+            per_target: Vec::new(),
+        },
+    };
+
+    Ok(ConfigOption {
+        key: key.to_string(),
+        name: get_str(table, "name").unwrap_or(key).to_string(),
+        description: get_str(table, "description").unwrap_or("").to_string(),
+        ty,
+        default: default.base,
+        target_defaults: default.per_target,
+        depends_on: match table.get("depends_on") {
+            Some(item) => parse_config_depends(path, content, item)?,
+            None => Vec::new(),
+        },
+        attributes: parse_config_attributes(path, content, table)?,
+        rebuild: parse_config_rebuild(path, content, table)?,
+        display: parse_config_display(path, content, table)?,
+        parent: None,
+    })
+}
+
+/// Parses the optional `display = "hex" | "bin" | "dec"` attribute, choosing
+/// the base the UI renders an integer value in.
+fn parse_config_display(
+    path: &Path,
+    content: &str,
+    table: &Table,
+) -> Result<DisplayBase, Vec<Report>> {
+    let Some(item) = table.get("display") else {
+        return Ok(DisplayBase::default());
+    };
+    match item.as_str() {
+        Some("dec") => Ok(DisplayBase::Dec),
+        Some("hex") => Ok(DisplayBase::Hex),
+        Some("bin") => Ok(DisplayBase::Bin),
+        _ => Err(vec![spanned(
+            path,
+            content,
+            item,
+            "display must be \"hex\", \"bin\" or \"dec\"",
+        )]),
+    }
+}
+
+/// Parses the optional `rebuild = "full" | "incremental"` attribute.
+fn parse_config_rebuild(
+    path: &Path,
+    content: &str,
+    table: &Table,
+) -> Result<RebuildKind, Vec<Report>> {
+    let Some(item) = table.get("rebuild") else {
+        return Ok(RebuildKind::default());
+    };
+    match item.as_str() {
+        Some("incremental") => Ok(RebuildKind::Incremental),
+        Some("full") => Ok(RebuildKind::Full),
+        _ => Err(vec![spanned(
+            path,
+            content,
+            item,
+            "rebuild must be \"full\" or \"incremental\"",
+        )]),
+    }
+}
+
+/// Parses a `type = ...` item: either a bare string for types without
+/// constraints or an inline table like `{ type = "Integer", min = 0, max = 8 }`.
+pub fn parse_config_type(
+    path: &Path,
+    content: &str,
+    item: &Item,
+) -> Result<ConfigType, Vec<Report>> {
+    if let Some(name) = item.as_str() {
+        return match name {
+            "Bool" => Ok(ConfigType::Bool),
+            "Integer" => Ok(ConfigType::Integer {
+                min: i64::MIN,
+                max: i64::MAX,
+                unit: None,
+            }),
+            "String" => Ok(ConfigType::String {
+                allowed_values: None,
+            }),
+            "List" => Ok(ConfigType::List),
+            other => Err(vec![spanned(
+                path,
+                content,
+                item,
+                format!("unknown option type '{other}'"),
+            )]),
+        };
+    }
+
+    let Some(table) = item.as_table_like() else {
+        return Err(vec![spanned(path, content, item, "malformed type")]);
+    };
+    let name = table.get("type").and_then(Item::as_str).unwrap_or("");
+    match name {
+        "Bool" => Ok(ConfigType::Bool),
+        "Integer" => Ok(ConfigType::Integer {
+            min: table
+                .get("min")
+                .and_then(Item::as_integer)
+                .unwrap_or(i64::MIN),
+            max: table
+                .get("max")
+                .and_then(Item::as_integer)
+                .unwrap_or(i64::MAX),
+            unit: table
+                .get("unit")
+                .and_then(Item::as_str)
+                .map(str::to_string),
+        }),
+        "String" => Ok(ConfigType::String {
+            allowed_values: table.get("allowed_values").and_then(Item::as_array).map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            }),
+        }),
+        "List" => Ok(ConfigType::List),
+        "Flags" => match table.get("values").and_then(Item::as_array) {
+            Some(values) => Ok(ConfigType::Flags {
+                values: values
+                    .iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect(),
+            }),
+            None => Err(vec![spanned(
+                path,
+                content,
+                item,
+                "Flags requires a values array",
+            )]),
+        },
+        other => Err(vec![spanned(
+            path,
+            content,
+            item,
+            format!("unknown option type '{other}'"),
+        )]),
+    }
+}
+
+/// A parsed `default = ...` item: the base default plus any per-target
+/// overrides keyed by triple.
+pub struct ParsedDefault {
+    pub base: ConfigValue,
+    pub per_target: Vec<(String, ConfigValue)>,
+}
+
+/// Parses a `default = ...` item, checking it against the declared type.
+///
+/// A table like `default = { "thumbv7em-none-eabihf" = 1024, "*" = 256 }`
+/// declares per-target overrides: the `*` entry (or the type default when
+/// absent) becomes the base default, the rest are returned keyed by triple.
+pub fn parse_config_default(
+    path: &Path,
+    content: &str,
+    item: &Item,
+    ty: &ConfigType,
+) -> Result<ParsedDefault, Vec<Report>> {
+    // Lists also arrive as values, so any table-like default is a
+    // per-target map.
+    if let Some(table) = item.as_table_like() {
+        let mut base = None;
+        let mut per_target = Vec::new();
+        for (target, entry) in table.iter() {
+            let value = parse_default_value(path, content, entry, ty)?;
+            if target == "*" {
+                base = Some(value);
+            } else {
+                per_target.push((target.to_string(), value));
+            }
+        }
+        return Ok(ParsedDefault {
+            base: base.unwrap_or_else(|| type_default(ty)),
+            per_target,
+        });
+    }
+
+    Ok(ParsedDefault {
+        base: parse_default_value(path, content, item, ty)?,
+        per_target: Vec::new(),
+    })
+}
+
+/// Parses one default value (scalar, string or array), checking it against
+/// the declared type.
+fn parse_default_value(
+    path: &Path,
+    content: &str,
+    item: &Item,
+    ty: &ConfigType,
+) -> Result<ConfigValue, Vec<Report>> {
+    // Byte-sized integers accept a human-readable string like "64KiB".
+    if let (ConfigType::Integer { unit: Some(unit), .. }, Some(raw)) = (ty, item.as_str()) {
+        if unit == "bytes" {
+            let parsed = crate::node::parse_byte_value(raw)
+                .map_err(|msg| vec![spanned(path, content, item, msg)])?;
+            let value = ConfigValue::Int(parsed);
+            ty.validate(&value)
+                .map_err(|msg| vec![spanned(path, content, item, msg)])?;
+            return Ok(value);
+        }
+    }
+
+    let value = toml_value(item)
+        .ok_or_else(|| vec![spanned(path, content, item, "unsupported default value")])?;
+    ty.validate(&value)
+        .map_err(|msg| vec![spanned(path, content, item, msg)])?;
+    Ok(value)
+}
+
+/// Parses a `depends_on = { key = value, ... }` table.
+///
+/// The grouped form `depends_on = { any = [{ a = true }], all = [...] }`
+/// assigns each entry to its [`DependencyGroup`]; the bare form keeps its
+/// implicit all-of meaning.
+pub fn parse_config_depends(
+    path: &Path,
+    content: &str,
+    item: &Item,
+) -> Result<Vec<Dependency>, Vec<Report>> {
+    let Some(table) = item.as_table_like() else {
+        return Err(vec![spanned(path, content, item, "depends_on must be a table")]);
+    };
+
+    // Grouped syntax: only `any` / `all` keys, each an array of tables.
+    let grouped = !table.is_empty() && table.iter().all(|(key, _)| key == "any" || key == "all");
+    if grouped {
+        let mut deps = Vec::new();
+        for (group_key, entry) in table.iter() {
+            let group = if group_key == "any" {
+                DependencyGroup::Any
+            } else {
+                DependencyGroup::All
+            };
+            let Some(array) = entry.as_array() else {
+                return Err(vec![spanned(
+                    path,
+                    content,
+                    item,
+                    format!("dependency group '{group_key}' must be an array of tables"),
+                )]);
+            };
+            for element in array.iter() {
+                let Some(pairs) = element.as_inline_table() else {
+                    return Err(vec![spanned(
+                        path,
+                        content,
+                        item,
+                        format!("entries of '{group_key}' must be tables like {{ key = value }}"),
+                    )]);
+                };
+                for (key, value) in pairs.iter() {
+                    let value = toml_scalar(value).ok_or_else(|| {
+                        vec![spanned(path, content, item, "unsupported dependency value")]
+                    })?;
+                    deps.push(Dependency {
+                        raw_key: key.to_string(),
+                        resolved: None,
+                        value,
+                        group,
+                    });
+                }
+            }
+        }
+        return Ok(deps);
+    }
+
+    let mut deps = Vec::new();
+    for (key, value) in table.iter() {
+        let value = toml_value(value)
+            .ok_or_else(|| vec![spanned(path, content, item, "unsupported dependency value")])?;
+        deps.push(Dependency {
+            raw_key: key.to_string(),
+            resolved: None,
+            value,
+            group: DependencyGroup::All,
+        });
+    }
+    Ok(deps)
+}
+
+/// Parses an `attributes = ["Hidden", ...]` array.
+pub fn parse_config_attributes(
+    path: &Path,
+    content: &str,
+    table: &Table,
+) -> Result<Vec<Attribute>, Vec<Report>> {
+    let Some(item) = table.get("attributes") else {
+        return Ok(Vec::new());
+    };
+    let Some(array) = item.as_array() else {
+        return Err(vec![spanned(path, content, item, "attributes must be an array")]);
+    };
+    let mut attributes = Vec::new();
+    for value in array.iter() {
+        match value.as_str() {
+            Some("Hidden") => attributes.push(Attribute::Hidden),
+            Some("NoStore") => attributes.push(Attribute::NoStore),
+            Some("Skip") => attributes.push(Attribute::Skip),
+            Some("NoHiddenPreview") => attributes.push(Attribute::NoHiddenPreview),
+            Some("Experimental") => attributes.push(Attribute::Experimental),
+            other => {
+                return Err(vec![spanned(
+                    path,
+                    content,
+                    item,
+                    format!("unknown attribute {other:?}"),
+                )])
+            }
+        }
+    }
+    Ok(attributes)
+}
+
+fn type_default(ty: &ConfigType) -> ConfigValue {
+    match ty {
+        ConfigType::Bool => ConfigValue::Bool(false),
+        ConfigType::Integer { min, .. } => ConfigValue::Int(if *min == i64::MIN { 0 } else { *min }),
+        ConfigType::String { .. } => ConfigValue::String(String::new()),
+        ConfigType::List => ConfigValue::List(Vec::new()),
+        ConfigType::Flags { .. } => ConfigValue::List(Vec::new()),
+    }
+}
+
+fn toml_value(item: &Item) -> Option<ConfigValue> {
+    toml_scalar(item.as_value()?)
+}
+
+fn toml_scalar(value: &toml_edit::Value) -> Option<ConfigValue> {
+    match value {
+        toml_edit::Value::Boolean(v) => Some(ConfigValue::Bool(*v.value())),
+        toml_edit::Value::Integer(v) => Some(ConfigValue::Int(*v.value())),
+        toml_edit::Value::String(v) => Some(ConfigValue::String(v.value().clone())),
+        toml_edit::Value::Array(a) => Some(ConfigValue::List(
+            a.iter().filter_map(|v| v.as_str().map(str::to_string)).collect(),
+        )),
+        _ => None,
+    }
+}
+
+fn get_str<'t>(table: &'t Table, key: &str) -> Option<&'t str> {
+    table.get(key).and_then(Item::as_str)
+}
+
+/// Builds a spanned report for `item` in `path`, falling back to an unspanned
+/// one when toml_edit has no span (e.g. for synthesized items).
+fn spanned(path: &Path, _content: &str, item: &Item, message: impl Into<String>) -> Report {
+    match item.span() {
+        Some(span) => Report::from_spanned(path, span, message),
+        None => Report::error(format!("{}: {}", path.display(), message.into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one_option(content: &str) -> Result<ConfigTree, Vec<Report>> {
+        let mut tree = ConfigTree::default();
+        parse_content(Path::new("test/options.toml"), content, &mut tree)?;
+        Ok(tree)
+    }
+
+    #[test]
+    fn rebuild_attribute_is_parsed() {
+        let tree = parse_one_option(
+            r#"
+            [options.heap_size]
+            type = { type = "Integer", min = 0, max = 65536 }
+            default = 4096
+            rebuild = "full"
+            "#,
+        )
+        .unwrap();
+        let option = tree.nodes[0].as_option().unwrap();
+        assert_eq!(option.rebuild, RebuildKind::Full);
+    }
+
+    #[test]
+    fn experimental_attribute_is_parsed() {
+        let tree = parse_one_option(
+            r#"
+            [options.new_scheduler]
+            type = "Bool"
+            attributes = ["Experimental", "Hidden"]
+            "#,
+        )
+        .unwrap();
+        let option = tree.nodes[0].as_option().unwrap();
+        assert_eq!(
+            option.attributes,
+            [Attribute::Experimental, Attribute::Hidden]
+        );
+    }
+
+    #[test]
+    fn rebuild_defaults_to_incremental() {
+        let tree = parse_one_option(
+            r#"
+            [options.verbose]
+            type = "Bool"
+            "#,
+        )
+        .unwrap();
+        let option = tree.nodes[0].as_option().unwrap();
+        assert_eq!(option.rebuild, RebuildKind::Incremental);
+    }
+
+    #[test]
+    fn display_attribute_is_parsed_and_defaults_to_decimal() {
+        let tree = parse_one_option(
+            r#"
+            [options.irq_mask]
+            type = { type = "Integer", min = 0, max = 255 }
+            default = 31
+            display = "hex"
+            "#,
+        )
+        .unwrap();
+        let option = tree.nodes[0].as_option().unwrap();
+        assert_eq!(option.display, DisplayBase::Hex);
+
+        let tree = parse_one_option(
+            r#"
+            [options.heap_size]
+            type = { type = "Integer", min = 0 }
+            "#,
+        )
+        .unwrap();
+        let option = tree.nodes[0].as_option().unwrap();
+        assert_eq!(option.display, DisplayBase::Dec);
+    }
+
+    #[test]
+    fn unknown_display_value_errors() {
+        let result = parse_one_option(
+            r#"
+            [options.irq_mask]
+            type = { type = "Integer", min = 0 }
+            display = "octal"
+            "#,
+        );
+        let reports = result.unwrap_err();
+        assert!(reports[0]
+            .message
+            .contains("display must be \"hex\", \"bin\" or \"dec\""));
+    }
+
+    #[test]
+    fn flags_type_is_parsed_with_values() {
+        let tree = parse_one_option(
+            r#"
+            [options.uart_features]
+            type = { type = "Flags", values = ["parity", "flow_control", "dma"] }
+            default = ["parity", "dma"]
+            "#,
+        )
+        .unwrap();
+        let option = tree.nodes[0].as_option().unwrap();
+        let ConfigType::Flags { values } = &option.ty else {
+            panic!("expected Flags, got {:?}", option.ty);
+        };
+        assert_eq!(values, &["parity", "flow_control", "dma"]);
+        assert_eq!(
+            option.default,
+            ConfigValue::List(vec!["parity".to_string(), "dma".to_string()])
+        );
+    }
+
+    #[test]
+    fn flags_default_outside_values_errors() {
+        let result = parse_one_option(
+            r#"
+            [options.uart_features]
+            type = { type = "Flags", values = ["parity"] }
+            default = ["crc"]
+            "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn byte_unit_suffixes_are_parsed_in_defaults() {
+        for (raw, expected) in [
+            ("\"64KiB\"", 64 * 1024),
+            ("\"2MiB\"", 2 * 1024 * 1024),
+            ("\"1GiB\"", 1024 * 1024 * 1024),
+            ("4096", 4096),
+        ] {
+            let tree = parse_one_option(&format!(
+                r#"
+                [options.heap_size]
+                type = {{ type = "Integer", min = 0, unit = "bytes" }}
+                default = {raw}
+                "#,
+            ))
+            .unwrap();
+            let option = tree.nodes[0].as_option().unwrap();
+            assert_eq!(option.default, ConfigValue::Int(expected), "for {raw}");
+        }
+    }
+
+    #[test]
+    fn grouped_depends_on_assigns_any_and_all() {
+        let tree = parse_one_option(
+            r#"
+            [options.console]
+            type = "Bool"
+            depends_on = { any = [{ driver_a = true }, { driver_b = true }], all = [{ board = true }] }
+            "#,
+        )
+        .unwrap();
+        let option = tree.nodes[0].as_option().unwrap();
+        let groups: Vec<(&str, DependencyGroup)> = option
+            .depends_on
+            .iter()
+            .map(|dep| (dep.raw_key.as_str(), dep.group))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("driver_a", DependencyGroup::Any),
+                ("driver_b", DependencyGroup::Any),
+                ("board", DependencyGroup::All),
+            ]
+        );
+    }
+
+    #[test]
+    fn bare_depends_on_table_stays_all_of() {
+        let tree = parse_one_option(
+            r#"
+            [options.console]
+            type = "Bool"
+            depends_on = { driver_a = true, driver_b = true }
+            "#,
+        )
+        .unwrap();
+        let option = tree.nodes[0].as_option().unwrap();
+        assert_eq!(option.depends_on.len(), 2);
+        assert!(option
+            .depends_on
+            .iter()
+            .all(|dep| dep.group == DependencyGroup::All));
+    }
+
+    #[test]
+    fn per_target_defaults_are_parsed() {
+        let tree = parse_one_option(
+            r#"
+            [options.stack_size]
+            type = { type = "Integer", min = 0 }
+            default = { "thumbv7em-none-eabihf" = 1024, "*" = 256 }
+            "#,
+        )
+        .unwrap();
+        let option = tree.nodes[0].as_option().unwrap();
+        assert_eq!(option.default, ConfigValue::Int(256));
+        assert_eq!(
+            option.target_defaults,
+            [("thumbv7em-none-eabihf".to_string(), ConfigValue::Int(1024))]
+        );
+    }
+
+    #[test]
+    fn per_target_default_outside_range_errors() {
+        let result = parse_one_option(
+            r#"
+            [options.stack_size]
+            type = { type = "Integer", min = 0, max = 512 }
+            default = { "thumbv7em-none-eabihf" = 1024, "*" = 256 }
+            "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn unknown_byte_suffix_errors() {
+        // Decimal "KB" is ambiguous and deliberately rejected.
+        let reports = parse_one_option(
+            r#"
+            [options.heap_size]
+            type = { type = "Integer", unit = "bytes" }
+            default = "64KB"
+            "#,
+        )
+        .unwrap_err();
+        assert!(reports[0].message.contains("unknown unit suffix 'KB'"));
+    }
+
+    #[test]
+    fn independent_errors_are_all_reported() {
+        let reports = parse_one_option(
+            r#"
+            [options.first]
+            type = "Quaternion"
+
+            [options.second]
+            default = true
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(reports.len(), 2);
+        assert!(reports[0].message.contains("unknown option type"));
+        assert!(reports[1].message.contains("missing a type"));
+    }
+
+    #[test]
+    fn nodes_record_the_file_they_were_parsed_from() {
+        let mut tree = ConfigTree::default();
+        let kernel = parse_content(
+            Path::new("kernel/options.toml"),
+            r#"
+            [options.heap_size]
+            type = "Integer"
+            "#,
+            &mut tree,
+        )
+        .unwrap();
+        let uart = parse_content(
+            Path::new("drivers/uart/options.toml"),
+            r#"
+            [options.baud]
+            type = "Integer"
+            "#,
+            &mut tree,
+        )
+        .unwrap();
+
+        assert_eq!(tree.source(kernel.nodes[0]), Path::new("kernel/options.toml"));
+        assert_eq!(tree.source(uart.nodes[0]), Path::new("drivers/uart/options.toml"));
+    }
+
+    #[test]
+    fn unknown_rebuild_value_errors() {
+        let result = parse_one_option(
+            r#"
+            [options.verbose]
+            type = "Bool"
+            rebuild = "everything"
+            "#,
+        );
+        assert!(result.is_err());
+    }
+}
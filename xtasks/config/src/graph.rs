@@ -0,0 +1,69 @@
+//! Graphviz DOT export of the resolved dependency graph.
+
+use std::fmt::Write;
+
+use crate::node::{ConfigNode, ConfigTree};
+
+/// Renders the tree as a DOT digraph: every category and option becomes a
+/// node (visually distinguished), every resolved `depends_on` an edge from
+/// the dependent option to its dependency.
+pub fn to_dot(tree: &ConfigTree) -> String {
+    let mut out = String::from("digraph config {\n");
+    out.push_str("    rankdir=LR;\n");
+
+    for key in tree.keys() {
+        let node = tree.node(key);
+        let full = tree.full_key(key);
+        match node {
+            ConfigNode::Category(_) => {
+                let _ = writeln!(
+                    out,
+                    "    n{} [label=\"{}\", shape=box, style=bold];",
+                    key.0, full
+                );
+            }
+            ConfigNode::Option(_) => {
+                let _ = writeln!(out, "    n{} [label=\"{}\", shape=ellipse];", key.0, full);
+            }
+        }
+    }
+
+    for key in tree.keys() {
+        let Some(option) = tree.node(key).as_option() else {
+            continue;
+        };
+        for dep in &option.depends_on {
+            if let Some(target) = dep.resolved {
+                let _ = writeln!(
+                    out,
+                    "    n{} -> n{} [label=\"= {}\"];",
+                    key.0, target.0, dep.value
+                );
+            }
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{bool_option, tree_of};
+
+    #[test]
+    fn dot_contains_nodes_and_dependency_edges() {
+        let tree = tree_of(vec![
+            bool_option("driver", true, &[]),
+            bool_option("feature", false, &[("driver", true)]),
+        ]);
+        let driver = crate::resolve::lookup(&tree, "driver").unwrap();
+        let feature = crate::resolve::lookup(&tree, "feature").unwrap();
+
+        let dot = to_dot(&tree);
+        assert!(dot.contains(&format!("n{} [label=\"driver\"", driver.0)));
+        assert!(dot.contains(&format!("n{} [label=\"feature\"", feature.0)));
+        assert!(dot.contains(&format!("n{} -> n{}", feature.0, driver.0)));
+    }
+}
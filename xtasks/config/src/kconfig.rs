@@ -0,0 +1,168 @@
+//! Import of Kconfig-style `.config` files.
+//!
+//! Projects migrating from a Kconfig build carry a `.config` of
+//! `CONFIG_FOO=y` lines. The importer translates each symbol to the option
+//! whose env name is `OSIRIS_FOO` (or to an explicitly mapped path), converts
+//! `y`/`n` to booleans and applies whatever validates — bad entries are
+//! reported, not fatal, so one stale symbol doesn't block the rest.
+
+use crate::report::Report;
+use crate::state::ConfigState;
+
+/// One assignment parsed from a `.config` file. The value is already in the
+/// tool's stored representation: `y`/`n` become `true`/`false` and string
+/// quotes are stripped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KconfigEntry {
+    /// The full symbol, including the `CONFIG_` prefix.
+    pub name: String,
+    pub value: String,
+    /// 1-based source line, for diagnostics.
+    pub line: usize,
+}
+
+/// Parses the assignments out of `.config` content. Comments and blank lines
+/// are skipped, except for the conventional `# CONFIG_FOO is not set` form,
+/// which Kconfig uses to record a disabled boolean and which imports as
+/// `false`. Lines that are neither are ignored, matching Kconfig itself.
+pub fn parse_kconfig(content: &str) -> Vec<KconfigEntry> {
+    let mut entries = Vec::new();
+    for (index, raw_line) in content.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw_line.trim();
+        if let Some(rest) = trimmed.strip_prefix("# ") {
+            if let Some(name) = rest.strip_suffix(" is not set") {
+                if name.starts_with("CONFIG_") {
+                    entries.push(KconfigEntry {
+                        name: name.to_string(),
+                        value: "false".to_string(),
+                        line,
+                    });
+                }
+            }
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((name, value)) = trimmed.split_once('=') else {
+            continue;
+        };
+        if !name.starts_with("CONFIG_") {
+            continue;
+        }
+        entries.push(KconfigEntry {
+            name: name.to_string(),
+            value: translate_value(value),
+            line,
+        });
+    }
+    entries
+}
+
+/// Converts one Kconfig value to the stored string representation.
+fn translate_value(raw: &str) -> String {
+    match raw {
+        "y" => "true".to_string(),
+        "n" => "false".to_string(),
+        quoted
+            if quoted.len() >= 2 && quoted.starts_with('"') && quoted.ends_with('"') =>
+        {
+            quoted[1..quoted.len() - 1].to_string()
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Applies the assignments in `content` to `state`. `mapping` pairs symbols
+/// with full dotted option paths, overriding the derived `OSIRIS_` name for
+/// symbols whose Kconfig spelling doesn't match ours. Returns the number of
+/// values applied and one report per entry that wasn't: a warning for a
+/// symbol no option matches, an error for a value the option rejects.
+pub fn apply_kconfig(
+    state: &mut ConfigState,
+    content: &str,
+    mapping: &[(String, String)],
+) -> (usize, Vec<Report>) {
+    let mut applied = 0;
+    let mut reports = Vec::new();
+    for entry in parse_kconfig(content) {
+        let path = match mapping.iter().find(|(name, _)| *name == entry.name) {
+            Some((_, path)) => path.clone(),
+            None => {
+                let env_key = entry.name.replacen("CONFIG_", "OSIRIS_", 1);
+                match state.option_by_env_key(&env_key) {
+                    Some(key) => state.tree.full_key(key).to_string(),
+                    None => {
+                        reports.push(Report::warning(format!(
+                            "line {}: no option matches '{}' (looked for '{env_key}')",
+                            entry.line, entry.name,
+                        )));
+                        continue;
+                    }
+                }
+            }
+        };
+        match state.set_by_path(&path, &entry.value) {
+            Ok(()) => applied += 1,
+            Err(report) => reports.push(Report {
+                message: format!("line {}: {}", entry.line, report.message),
+                ..report
+            }),
+        }
+    }
+    (applied, reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::ConfigValue;
+    use crate::report::Severity;
+    use crate::state::MacroEngine;
+    use crate::testutil::{bool_option, int_option, tree_of};
+
+    fn state() -> ConfigState {
+        let tree = tree_of(vec![
+            bool_option("verbose", false, &[]),
+            int_option("heap_size", 4096, 1024, 65536),
+        ]);
+        ConfigState::new(tree, MacroEngine::new())
+    }
+
+    #[test]
+    fn booleans_map_and_unmapped_symbols_warn() {
+        let mut state = state();
+        let content = "\
+# a comment\n\
+CONFIG_VERBOSE=y\n\
+CONFIG_NO_SUCH_THING=y\n\
+# CONFIG_HEAP_SIZE is not set\n";
+        let (applied, reports) = apply_kconfig(&mut state, content, &[]);
+
+        // `y` landed as a bool; the unknown symbol only warned.
+        assert_eq!(applied, 1);
+        let verbose = crate::resolve::lookup(&state.tree, "verbose").unwrap();
+        assert_eq!(state.values.get(&verbose), Some(&ConfigValue::Bool(true)));
+
+        // One warning for the unknown symbol, one error for setting the
+        // integer option to `false` via the "is not set" line.
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].severity, Severity::Warning);
+        assert!(reports[0].message.contains("CONFIG_NO_SUCH_THING"));
+        assert_eq!(reports[1].severity, Severity::Error);
+        assert!(reports[1].message.contains("line 4"));
+    }
+
+    #[test]
+    fn explicit_mapping_overrides_the_derived_name() {
+        let mut state = state();
+        let mapping = vec![("CONFIG_SYS_HEAP".to_string(), "heap_size".to_string())];
+        let (applied, reports) =
+            apply_kconfig(&mut state, "CONFIG_SYS_HEAP=8192\n", &mapping);
+        assert_eq!(applied, 1);
+        assert!(reports.is_empty());
+        let heap = crate::resolve::lookup(&state.tree, "heap_size").unwrap();
+        assert_eq!(state.values.get(&heap), Some(&ConfigValue::Int(8192)));
+    }
+}
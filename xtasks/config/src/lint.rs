@@ -0,0 +1,79 @@
+//! Documentation lints over the parsed tree.
+//!
+//! The configuration is user-facing, so undocumented entries are a smell:
+//! an option without a `description` gives the TUI nothing to show, and a
+//! category whose name is just its key reads like an internal identifier.
+//! Pure static analysis — no values or cargo config involved.
+
+use crate::node::{ConfigKey, ConfigNode, ConfigTree};
+
+/// Finds nodes with documentation problems, together with a human-readable
+/// reason. Results are sorted by full key for stable output.
+pub fn undocumented_nodes(tree: &ConfigTree) -> Vec<(ConfigKey, String)> {
+    let mut findings: Vec<(ConfigKey, String)> = tree
+        .keys()
+        .filter_map(|key| {
+            let reason = match tree.node(key) {
+                ConfigNode::Option(option) if option.description.is_empty() => {
+                    "option has no description".to_string()
+                }
+                ConfigNode::Category(category) if category.name == category.key => {
+                    format!(
+                        "category name equals its key '{}'; give it a human-readable name",
+                        category.key
+                    )
+                }
+                _ => return None,
+            };
+            Some((key, reason))
+        })
+        .collect();
+    findings.sort_by_key(|&(key, _)| tree.full_key(key));
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{ConfigCategory, ConfigNode};
+    use crate::testutil::{bool_option, tree_of};
+
+    #[test]
+    fn undescribed_option_is_reported_described_is_not() {
+        // The testutil builder writes a description; blank one of them out.
+        let mut bare = bool_option("verbose", false, &[]);
+        if let ConfigNode::Option(o) = &mut bare {
+            o.description.clear();
+        }
+        let tree = tree_of(vec![bool_option("driver", false, &[]), bare]);
+
+        let findings = undocumented_nodes(&tree);
+        assert_eq!(findings.len(), 1);
+        let (key, reason) = &findings[0];
+        assert_eq!(tree.full_key(*key), "verbose");
+        assert!(reason.contains("no description"), "got: {reason}");
+    }
+
+    #[test]
+    fn category_named_after_its_key_is_reported() {
+        let category = |key: &str, name: &str| {
+            ConfigNode::Category(ConfigCategory {
+                key: key.to_string(),
+                name: name.to_string(),
+                description: format!("test category {key}"),
+                attributes: Vec::new(),
+                parent: None,
+                children: Vec::new(),
+            })
+        };
+        let tree = tree_of(vec![
+            category("kernel", "kernel"),
+            category("mem", "Memory management"),
+        ]);
+
+        let findings = undocumented_nodes(&tree);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(tree.full_key(findings[0].0), "kernel");
+        assert!(findings[0].1.contains("human-readable name"));
+    }
+}
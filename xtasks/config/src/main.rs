@@ -0,0 +1,512 @@
+//! The Osiris configuration tool.
+//!
+//! Discovers `options.toml` files across the workspace, maintains the user's
+//! configuration in `.cargo/config.toml`'s `[env]` table and offers an
+//! interactive TUI for editing it.
+
+mod audit;
+mod file;
+mod graph;
+mod kconfig;
+mod lint;
+mod node;
+mod preset;
+mod report;
+mod resolve;
+mod schema;
+mod state;
+mod testutil;
+mod ui;
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use crossterm::event::{self, Event};
+use toml_edit::DocumentMut;
+
+use crate::state::{ConfigState, MacroEngine};
+use crate::ui::base::Action;
+use crate::ui::BaseUI;
+
+#[derive(Parser)]
+#[command(name = "config", about = "Configure the Osiris build")]
+struct Cli {
+    /// Workspace root to scan for options.toml files.
+    #[arg(long, default_value = ".")]
+    root: PathBuf,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Remove all Osiris configuration from .cargo/config.toml.
+    Clean {
+        /// Only list the keys that would be removed; don't touch the file.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Apply a named preset's env table to .cargo/config.toml.
+    Preset {
+        /// Name of the preset (file stem under the presets directory).
+        name: String,
+    },
+    /// Report options that no value assignment can ever enable.
+    Audit,
+    /// Report undocumented options and categories without a readable name.
+    Lint,
+    /// Check the stored configuration and print notices (experimental
+    /// options enabled, warnings from loading).
+    Validate,
+    /// Print the option tree's schema (types, constraints, dependencies) as
+    /// JSON — what can be configured, not the current values.
+    Schema,
+    /// Write the resolved dependency graph in Graphviz DOT format.
+    Graph {
+        /// Output path of the DOT file.
+        #[arg(long, default_value = "config.dot")]
+        out: PathBuf,
+    },
+    /// Write the current values as a flat JSON object of full dotted keys,
+    /// for tooling that generates configs programmatically.
+    ExportJson {
+        /// Output path of the JSON file.
+        #[arg(long, default_value = "config.json")]
+        out: PathBuf,
+    },
+    /// Import values from a JSON object in the `export-json` format,
+    /// validating each against its option's type.
+    ImportJson {
+        /// Path of the JSON file to import.
+        file: PathBuf,
+    },
+    /// Write the enabled options as a plain `.env` file of
+    /// `OSIRIS_KEY=value` lines.
+    ExportEnv {
+        /// Output path of the .env file.
+        #[arg(long, default_value = ".env")]
+        out: PathBuf,
+    },
+    /// Import values from a Kconfig-style `.config` file. `CONFIG_FOO=y`
+    /// maps to the option with env key `OSIRIS_FOO`; entries that don't
+    /// map or don't validate are reported but skipped.
+    ImportKconfig {
+        /// Path of the `.config` file to import.
+        file: PathBuf,
+        /// Extra symbol mapping, e.g. `--map CONFIG_SYS_HEAP=kernel.heap_size`.
+        /// Repeatable; overrides the derived env-key lookup.
+        #[arg(long = "map", value_name = "CONFIG_SYM=PATH")]
+        map: Vec<String>,
+    },
+    /// Set one option non-interactively, e.g. `set kernel.heap_size 8192`.
+    Set {
+        /// Full dotted path of the option.
+        path: String,
+        /// New value, in the same format as the stored env value.
+        value: String,
+    },
+    /// Print one option's current value.
+    Get {
+        /// Full dotted path of the option.
+        path: String,
+    },
+}
+
+fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    match cli.command {
+        None => run_tui(&cli.root),
+        Some(Command::Clean { dry_run }) => run_clean(&cli.root, dry_run),
+        Some(Command::Preset { name }) => run_load_preset(&cli.root, &name),
+        Some(Command::Audit) => run_audit(&cli.root),
+        Some(Command::Lint) => run_lint(&cli.root),
+        Some(Command::Validate) => run_validate(&cli.root),
+        Some(Command::Schema) => run_schema(&cli.root),
+        Some(Command::Graph { out }) => run_graph(&cli.root, &out),
+        Some(Command::ExportJson { out }) => run_export_json(&cli.root, &out),
+        Some(Command::ImportJson { file }) => run_import_json(&cli.root, &file),
+        Some(Command::ExportEnv { out }) => run_export_env(&cli.root, &out),
+        Some(Command::ImportKconfig { file, map }) => run_import_kconfig(&cli.root, &file, &map),
+        Some(Command::Set { path, value }) => run_set(&cli.root, &path, &value),
+        Some(Command::Get { path }) => run_get(&cli.root, &path),
+    }
+}
+
+/// Imports a Kconfig-style `.config`, applying what validates and reporting
+/// the rest, then writes the config back.
+fn run_import_kconfig(root: &Path, file: &Path, map: &[String]) -> io::Result<()> {
+    let mut mapping = Vec::new();
+    for spec in map {
+        let Some((name, path)) = spec.split_once('=') else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("--map '{spec}' must look like CONFIG_SYM=PATH"),
+            ));
+        };
+        mapping.push((name.to_string(), path.to_string()));
+    }
+
+    let mut state = load_state(root)?;
+    let content = std::fs::read_to_string(file)?;
+    let (applied, reports) = kconfig::apply_kconfig(&mut state, &content, &mapping);
+    for report in &reports {
+        eprintln!("{}", report.render(None));
+    }
+    println!("applied {applied} value(s), skipped {}", reports.len());
+    save_state(root, &state)
+}
+
+/// Applies one scripted option edit and writes the config back.
+fn run_set(root: &Path, option_path: &str, value: &str) -> io::Result<()> {
+    let mut state = load_state(root)?;
+    state
+        .set_by_path(option_path, value)
+        .map_err(|report| render_reports(root, vec![report]))?;
+    save_state(root, &state)
+}
+
+/// Prints one option's current value.
+fn run_get(root: &Path, option_path: &str) -> io::Result<()> {
+    let state = load_state(root)?;
+    let value = state
+        .get_by_path(option_path)
+        .map_err(|report| render_reports(root, vec![report]))?;
+    println!("{value}");
+    Ok(())
+}
+
+/// Reports options that can never be enabled; fails when any are found so
+/// the audit can gate CI.
+fn run_audit(root: &Path) -> io::Result<()> {
+    let state = load_state(root)?;
+    let findings = audit::unreachable_options(&state.tree);
+    if findings.is_empty() {
+        println!("no unreachable options");
+        return Ok(());
+    }
+    for (key, reason) in &findings {
+        println!(
+            "unreachable: {}: {reason}",
+            state.tree.full_key(*key)
+        );
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} unreachable option(s)", findings.len()),
+    ))
+}
+
+/// Reports documentation smells in the option tree; fails when any are found
+/// so the lint can gate CI.
+fn run_lint(root: &Path) -> io::Result<()> {
+    let state = load_state(root)?;
+    let findings = lint::undocumented_nodes(&state.tree);
+    if findings.is_empty() {
+        println!("no documentation lints");
+        return Ok(());
+    }
+    for (key, reason) in &findings {
+        println!("lint: {}: {reason}", state.tree.full_key(*key));
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} documentation lint(s)", findings.len()),
+    ))
+}
+
+/// Checks the stored configuration against the option tree. Loading already
+/// surfaces warnings; on top, every *enabled* option that is experimental
+/// (itself or via an ancestor category) is listed in a notice.
+fn run_validate(root: &Path) -> io::Result<()> {
+    let state = load_state(root)?;
+    let experimental: Vec<String> = state
+        .tree
+        .keys()
+        .filter(|&key| {
+            state.tree.node(key).as_option().is_some()
+                && state.is_enabled(key)
+                && state
+                    .tree
+                    .inherits_attribute(key, node::Attribute::Experimental)
+        })
+        .map(|key| state.tree.full_key(key).to_string())
+        .collect();
+    if experimental.is_empty() {
+        println!("configuration valid");
+        return Ok(());
+    }
+    println!(
+        "notice: {} experimental option(s) enabled:",
+        experimental.len()
+    );
+    for key in &experimental {
+        println!("  {key}");
+    }
+    Ok(())
+}
+
+/// Prints the parsed and resolved option tree as a JSON schema.
+fn run_schema(root: &Path) -> io::Result<()> {
+    let state = load_state(root)?;
+    let json = serde_json::to_string_pretty(&schema::to_json(&state.tree))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{err}")))?;
+    println!("{json}");
+    Ok(())
+}
+
+/// Writes the resolved dependency graph as DOT.
+fn run_graph(root: &Path, out: &Path) -> io::Result<()> {
+    let state = load_state(root)?;
+    std::fs::write(out, graph::to_dot(&state.tree))?;
+    println!("wrote {}", out.display());
+    Ok(())
+}
+
+/// Writes the current values as a flat JSON object.
+fn run_export_json(root: &Path, out: &Path) -> io::Result<()> {
+    let state = load_state(root)?;
+    let json = serde_json::to_string_pretty(&state.to_json())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{err}")))?;
+    std::fs::write(out, json)?;
+    println!("wrote {}", out.display());
+    Ok(())
+}
+
+/// Imports values from an `export-json` document and writes the config back.
+fn run_import_json(root: &Path, file: &Path) -> io::Result<()> {
+    let mut state = load_state(root)?;
+    let content = std::fs::read_to_string(file)?;
+    state
+        .deserialize_from_json(file, &content)
+        .map_err(|reports| render_reports(root, reports))?;
+    save_state(root, &state)
+}
+
+/// Writes the enabled options in plain `.env` format.
+fn run_export_env(root: &Path, out: &Path) -> io::Result<()> {
+    let state = load_state(root)?;
+    std::fs::write(out, state.to_env_file())?;
+    println!("wrote {}", out.display());
+    Ok(())
+}
+
+/// Resolves a preset (including its `extends` chain) and writes its env table
+/// into the cargo config.
+fn run_load_preset(root: &Path, name: &str) -> io::Result<()> {
+    let presets = preset::load_presets(&root.join("presets"))
+        .map_err(|report| render_reports(root, vec![report]))?;
+    let env = preset::resolve_preset_env(&presets, name)
+        .map_err(|report| render_reports(root, vec![report]))?;
+
+    // Catch stale presets: keys that no longer map to any option would be
+    // injected as dead config otherwise. Warn but still apply.
+    let state = load_state(root)?;
+    for warning in preset::unknown_key_warnings(&state, &env) {
+        eprintln!("{}", warning.render(None));
+    }
+
+    let path = config_path(root);
+    let mut doc: DocumentMut = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| content.parse().ok())
+        .unwrap_or_default();
+    preset::apply_preset(&mut doc, &env);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, doc.to_string())
+}
+
+/// Path of the cargo config the tool reads and writes.
+fn config_path(root: &Path) -> PathBuf {
+    root.join(".cargo/config.toml")
+}
+
+/// Loads the option tree and current values into a [`ConfigState`].
+fn load_state(root: &Path) -> io::Result<ConfigState> {
+    let (mut tree, files) = match file::load_config(root) {
+        Ok(parts) => parts,
+        Err(reports) => return Err(render_reports(root, reports)),
+    };
+    if let Err(reports) = resolve::link_nodes(&mut tree, &files) {
+        return Err(render_reports(root, reports));
+    }
+    if let Err(reports) = resolve::resolve_paths(&mut tree) {
+        return Err(render_reports(root, reports));
+    }
+
+    let macros = read_target_triple(root)?
+        .map(|triple| MacroEngine::new().with_target_triple(triple))
+        .unwrap_or_default();
+    let mut state = ConfigState::new(tree, macros);
+    state
+        .warnings
+        .extend(resolve::duplicate_category_warnings(&state.tree));
+    for warning in &state.warnings {
+        eprintln!("{}", warning.render(None));
+    }
+
+    let path = config_path(root);
+    if let Ok(content) = std::fs::read_to_string(&path) {
+        if let Err(reports) = state.deserialize_from(&path, &content) {
+            return Err(render_reports(root, reports));
+        }
+    }
+    for warning in state.apply_env_overrides(std::env::vars()) {
+        eprintln!("{}", warning.render(None));
+    }
+    Ok(state)
+}
+
+/// Reads `build.target` from the cargo config, if set, rejecting triples no
+/// machine crate supports with an error pointing at the offending value.
+fn read_target_triple(root: &Path) -> io::Result<Option<String>> {
+    let path = config_path(root);
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let Ok(doc) = toml_edit::ImDocument::parse(content.as_str()) else {
+        return Ok(None);
+    };
+    let Some(item) = doc.get("build").and_then(|b| b.get("target")) else {
+        return Ok(None);
+    };
+    let Some(triple) = item.as_str() else {
+        return Ok(None);
+    };
+
+    if let Err(report) = state::validate_target_triple(triple) {
+        let report = match item.span() {
+            Some(span) => report::Report::from_spanned(&path, span, report.message),
+            None => report,
+        };
+        return Err(render_reports(root, vec![report]));
+    }
+    Ok(Some(triple.to_string()))
+}
+
+/// Writes the state back into the cargo config, preserving unrelated tables.
+fn save_state(root: &Path, state: &ConfigState) -> io::Result<()> {
+    let path = config_path(root);
+    let mut doc: DocumentMut = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| content.parse().ok())
+        .unwrap_or_default();
+    state.serialize_into(&mut doc);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, doc.to_string())
+}
+
+fn run_tui(root: &Path) -> io::Result<()> {
+    let state = load_state(root)?;
+    let mut ui = BaseUI::new(state);
+
+    let mut terminal = ratatui::init();
+    let result = loop {
+        if let Err(err) = terminal.draw(|frame| ui.draw(frame)) {
+            break Err(err);
+        }
+        match event::read() {
+            Ok(Event::Key(key)) => {
+                if ui.handle_key_event(key) == Action::Quit {
+                    break Ok(());
+                }
+            }
+            Ok(_) => {}
+            Err(err) => break Err(err),
+        }
+    };
+    ratatui::restore();
+    result?;
+
+    if ui.state.rebuild_requirement() == node::RebuildKind::Full {
+        println!("note: a changed option invalidates the whole build; run a full rebuild");
+    }
+    save_state(root, &ui.state)
+}
+
+/// Removes every Osiris-owned key from the cargo config after confirmation.
+/// With `dry_run`, only lists what would go and leaves the file alone.
+fn run_clean(root: &Path, dry_run: bool) -> io::Result<()> {
+    let path = config_path(root);
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        println!("nothing to clean: {} does not exist", path.display());
+        return Ok(());
+    };
+    let mut doc: DocumentMut = content
+        .parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{err}")))?;
+
+    if dry_run {
+        for key in osiris_keys(&doc) {
+            println!("would remove [{key}]");
+        }
+        return Ok(());
+    }
+
+    println!("This removes all Osiris configuration from {}. Continue? [y/N]", path.display());
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        println!("aborted");
+        return Ok(());
+    }
+
+    for key in osiris_keys(&doc) {
+        doc.remove(&key);
+    }
+    std::fs::write(&path, doc.to_string())
+}
+
+/// The top-level keys `clean` owns: everything except `alias`, which cargo
+/// users keep for themselves.
+fn osiris_keys(doc: &DocumentMut) -> Vec<String> {
+    doc.iter()
+        .map(|(k, _)| k.to_string())
+        .filter(|k| k != "alias")
+        .collect()
+}
+
+/// Renders reports against their source files and folds them into an error.
+fn render_reports(root: &Path, reports: Vec<report::Report>) -> io::Error {
+    let mut rendered = String::new();
+    for report in &reports {
+        let source = report
+            .file
+            .as_ref()
+            .and_then(|f| std::fs::read_to_string(root.join(f)).ok());
+        rendered.push_str(&report.render(source.as_deref()));
+        rendered.push('\n');
+    }
+    io::Error::new(io::ErrorKind::InvalidData, rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_dry_run_lists_the_right_keys_and_keeps_the_file() {
+        let root = std::env::temp_dir().join(format!("osiris-clean-dry-{}", std::process::id()));
+        std::fs::create_dir_all(root.join(".cargo")).unwrap();
+        let content = "[env]\nOSIRIS_FEATURE = \"true\"\n\n\
+                       [build]\ntarget = \"thumbv7em-none-eabihf\"\n\n\
+                       [alias]\nxtask = \"run -p xtask --\"\n";
+        let path = config_path(&root);
+        std::fs::write(&path, content).unwrap();
+
+        // Everything but `alias` is up for removal...
+        let doc: DocumentMut = content.parse().unwrap();
+        assert_eq!(osiris_keys(&doc), vec!["env", "build"]);
+
+        // ...but a dry run only reports; the file stays byte-identical.
+        run_clean(&root, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+//! The configuration tree: categories and options parsed from `options.toml`
+//! files, linked into a single tree by `resolve`.
+
+use std::path::{Path, PathBuf};
+
+/// Index of a node in the [`ConfigTree`] arena. Stable for the lifetime of the
+/// tree; used everywhere a node needs to be referenced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConfigKey(pub usize);
+
+/// A value an option can hold.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+    List(Vec<String>),
+}
+
+impl std::fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigValue::Bool(v) => write!(f, "{v}"),
+            ConfigValue::Int(v) => write!(f, "{v}"),
+            ConfigValue::String(v) => write!(f, "{v}"),
+            ConfigValue::List(v) => write!(f, "{}", v.join(",")),
+        }
+    }
+}
+
+/// The type (and constraints) of an option.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigType {
+    Bool,
+    /// An integer, optionally range-constrained. `unit = "bytes"` options
+    /// additionally accept `KiB`/`MiB`/`GiB` suffixed input (parsed by
+    /// [`parse_byte_value`]); the stored value is always the raw integer.
+    Integer {
+        min: i64,
+        max: i64,
+        unit: Option<String>,
+    },
+    String { allowed_values: Option<Vec<String>> },
+    List,
+    /// Multi-select over a fixed set of flags. The value is the list of
+    /// selected flag names (a subset of `values`); it serializes as a comma
+    /// list rather than the OR'd mask so the stored config stays readable and
+    /// survives reordering of the declared flags. [`flags_mask`] derives the
+    /// bitmask when one is needed.
+    Flags { values: Vec<String> },
+}
+
+impl ConfigType {
+    /// Checks that `value` is well-typed and within this type's constraints.
+    pub fn validate(&self, value: &ConfigValue) -> Result<(), String> {
+        match (self, value) {
+            (ConfigType::Bool, ConfigValue::Bool(_)) => Ok(()),
+            (ConfigType::Integer { min, max, .. }, ConfigValue::Int(v)) => {
+                if v < min || v > max {
+                    Err(format!("value {v} out of range [{min}, {max}]"))
+                } else {
+                    Ok(())
+                }
+            }
+            (ConfigType::String { allowed_values }, ConfigValue::String(v)) => {
+                match allowed_values {
+                    Some(allowed) if !allowed.contains(v) => Err(format!(
+                        "value '{v}' not one of the allowed values: {}",
+                        allowed.join(", ")
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            (ConfigType::List, ConfigValue::List(_)) => Ok(()),
+            (ConfigType::Flags { values }, ConfigValue::List(selected)) => {
+                match selected.iter().find(|s| !values.contains(s)) {
+                    Some(unknown) => Err(format!(
+                        "'{unknown}' is not one of the flags: {}",
+                        values.join(", ")
+                    )),
+                    None => Ok(()),
+                }
+            }
+            (ty, value) => Err(format!("value '{value}' does not match type {ty:?}")),
+        }
+    }
+}
+
+/// The bitmask of `selected` over the declared flag order: bit `i` is set
+/// when `values[i]` is selected.
+pub fn flags_mask(values: &[String], selected: &[String]) -> u64 {
+    values
+        .iter()
+        .enumerate()
+        .filter(|(_, flag)| selected.contains(flag))
+        .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+/// Parses an integer that may carry a binary unit suffix (`KiB`, `MiB`,
+/// `GiB`), as accepted by `unit = "bytes"` integer options. A bare integer
+/// stays unscaled; anything else — including the ambiguous decimal `KB`
+/// family — is rejected.
+pub fn parse_byte_value(raw: &str) -> Result<i64, String> {
+    let raw = raw.trim();
+    let digits_end = raw
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || (i == 0 && c == '-')))
+        .map(|(i, _)| i)
+        .unwrap_or(raw.len());
+    let (digits, suffix) = raw.split_at(digits_end);
+    let value: i64 = digits
+        .parse()
+        .map_err(|_| format!("'{raw}' is not an integer"))?;
+    let scale: i64 = match suffix.trim_start() {
+        "" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        other => {
+            return Err(format!(
+                "unknown unit suffix '{other}' (expected KiB, MiB or GiB)"
+            ))
+        }
+    };
+    value
+        .checked_mul(scale)
+        .ok_or_else(|| format!("'{raw}' does not fit into 64 bits"))
+}
+
+/// How an integer option's value is shown (and typed) in the UI. Purely a
+/// presentation hint: the serialized env value stays decimal regardless, so
+/// downstream consumers never need base-aware parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayBase {
+    #[default]
+    Dec,
+    Hex,
+    Bin,
+}
+
+impl DisplayBase {
+    /// Formats `value` in this base, `0x`/`0b` prefixed for hex/binary.
+    pub fn format(self, value: i64) -> String {
+        match self {
+            DisplayBase::Dec => format!("{value}"),
+            DisplayBase::Hex => format!("{value:#x}"),
+            DisplayBase::Bin => format!("{value:#b}"),
+        }
+    }
+}
+
+/// Parses an integer accepting `0x`/`0b` prefixed input — as typed in the
+/// editor for hex/binary-display options — alongside plain decimal.
+pub fn parse_int_value(raw: &str) -> Result<i64, String> {
+    let raw = raw.trim();
+    let result = if let Some(hex) = raw.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(bin) = raw.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2)
+    } else {
+        raw.parse()
+    };
+    result.map_err(|_| format!("'{raw}' is not an integer"))
+}
+
+/// Behaviour-modifying attributes on a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    /// Not shown in the UI.
+    Hidden,
+    /// Never written to the serialized env table.
+    NoStore,
+    /// Ignored entirely by the state.
+    Skip,
+    /// Hidden children are not summarized in previews.
+    NoHiddenPreview,
+    /// Unstable: the UI shows a warning badge and `validate` reports every
+    /// enabled option carrying (or inheriting) it.
+    Experimental,
+}
+
+/// How much of the build changing an option invalidates. Ordered so the
+/// strongest requirement across several changes can be taken with `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum RebuildKind {
+    #[default]
+    Incremental,
+    Full,
+}
+
+/// A dependency of an option on another option holding a specific value.
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    /// The key as written in `depends_on` (relative or fully qualified).
+    pub raw_key: String,
+    /// Filled in by `resolve::resolve_paths`.
+    pub resolved: Option<ConfigKey>,
+    /// The value the dependency must hold for this option to be enabled.
+    pub value: ConfigValue,
+    /// How this dependency combines with its siblings.
+    pub group: DependencyGroup,
+}
+
+/// How dependencies combine: every `All` entry must hold, and — when any
+/// `Any` entries exist — at least one of them must hold too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DependencyGroup {
+    #[default]
+    All,
+    Any,
+}
+
+/// A category groups options and other categories.
+#[derive(Debug, Clone)]
+pub struct ConfigCategory {
+    /// The key segment of this category (one path component).
+    pub key: String,
+    /// Human-readable name.
+    pub name: String,
+    pub description: String,
+    pub attributes: Vec<Attribute>,
+    pub parent: Option<ConfigKey>,
+    pub children: Vec<ConfigKey>,
+}
+
+/// A single configurable option.
+#[derive(Debug, Clone)]
+pub struct ConfigOption {
+    /// The key segment of this option (one path component).
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub ty: ConfigType,
+    pub default: ConfigValue,
+    /// Per-target default overrides, keyed by target triple. A `*` entry in
+    /// the source table folds into [`Self::default`], which is also what a
+    /// build without a known target falls back to.
+    pub target_defaults: Vec<(String, ConfigValue)>,
+    pub depends_on: Vec<Dependency>,
+    pub attributes: Vec<Attribute>,
+    /// How much of the build a change to this option invalidates.
+    pub rebuild: RebuildKind,
+    /// The base the UI renders (and accepts) integer values in.
+    pub display: DisplayBase,
+    pub parent: Option<ConfigKey>,
+}
+
+impl ConfigOption {
+    /// Renders a value of this option for the UI, honouring
+    /// [`Self::display`] for integers.
+    pub fn display_value(&self, value: &ConfigValue) -> String {
+        match value {
+            ConfigValue::Int(v) => self.display.format(*v),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// A node of the configuration tree.
+#[derive(Debug, Clone)]
+pub enum ConfigNode {
+    Category(ConfigCategory),
+    Option(ConfigOption),
+}
+
+/// Shared accessors over categories and options.
+pub trait ConfigNodelike {
+    fn key(&self) -> &str;
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+    fn attributes(&self) -> &[Attribute];
+    fn parent(&self) -> Option<ConfigKey>;
+}
+
+impl ConfigNodelike for ConfigNode {
+    fn key(&self) -> &str {
+        match self {
+            ConfigNode::Category(c) => &c.key,
+            ConfigNode::Option(o) => &o.key,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            ConfigNode::Category(c) => &c.name,
+            ConfigNode::Option(o) => &o.name,
+        }
+    }
+
+    fn description(&self) -> &str {
+        match self {
+            ConfigNode::Category(c) => &c.description,
+            ConfigNode::Option(o) => &o.description,
+        }
+    }
+
+    fn attributes(&self) -> &[Attribute] {
+        match self {
+            ConfigNode::Category(c) => &c.attributes,
+            ConfigNode::Option(o) => &o.attributes,
+        }
+    }
+
+    fn parent(&self) -> Option<ConfigKey> {
+        match self {
+            ConfigNode::Category(c) => c.parent,
+            ConfigNode::Option(o) => o.parent,
+        }
+    }
+}
+
+impl ConfigNode {
+    pub fn has_attribute(&self, attr: Attribute) -> bool {
+        self.attributes().contains(&attr)
+    }
+
+    pub fn as_option(&self) -> Option<&ConfigOption> {
+        match self {
+            ConfigNode::Option(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    pub fn as_category(&self) -> Option<&ConfigCategory> {
+        match self {
+            ConfigNode::Category(c) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+/// The arena holding every parsed node. Children reference their parents and
+/// vice versa through [`ConfigKey`] indices.
+#[derive(Debug, Default)]
+pub struct ConfigTree {
+    pub nodes: Vec<ConfigNode>,
+    /// Nodes directly under the (implicit) root.
+    pub root: Vec<ConfigKey>,
+    /// Per-file metadata `parent` declarations, keyed like `nodes`.
+    pub sources: Vec<PathBuf>,
+    /// Each node's full dotted key, keyed like `nodes`. Filled by
+    /// [`Self::cache_full_keys`] once linking is done; empty before that.
+    pub full_keys: Vec<String>,
+}
+
+impl ConfigTree {
+    pub fn node(&self, key: ConfigKey) -> &ConfigNode {
+        &self.nodes[key.0]
+    }
+
+    pub fn node_mut(&mut self, key: ConfigKey) -> &mut ConfigNode {
+        &mut self.nodes[key.0]
+    }
+
+    pub fn push(&mut self, node: ConfigNode, source: PathBuf) -> ConfigKey {
+        let key = ConfigKey(self.nodes.len());
+        self.nodes.push(node);
+        self.sources.push(source);
+        key
+    }
+
+    /// The `options.toml` a node was parsed from.
+    pub fn source(&self, key: ConfigKey) -> &Path {
+        &self.sources[key.0]
+    }
+
+    /// Iterates all node keys in arena order.
+    pub fn keys(&self) -> impl Iterator<Item = ConfigKey> {
+        (0..self.nodes.len()).map(ConfigKey)
+    }
+
+    /// Children of a node (empty for options).
+    pub fn children(&self, key: ConfigKey) -> &[ConfigKey] {
+        match self.node(key) {
+            ConfigNode::Category(c) => &c.children,
+            ConfigNode::Option(_) => &[],
+        }
+    }
+
+    /// Whether a node carries `attr` itself or inherits it from an ancestor
+    /// category, for attributes (like `Experimental`) that apply to whole
+    /// subtrees.
+    pub fn inherits_attribute(&self, key: ConfigKey, attr: Attribute) -> bool {
+        let mut cur = Some(key);
+        while let Some(node) = cur {
+            if self.node(node).has_attribute(attr) {
+                return true;
+            }
+            cur = self.node(node).parent();
+        }
+        false
+    }
+
+    /// Stores every node's full dotted key, turning [`Self::full_key`] into
+    /// an O(1) read. `resolve::resolve_paths` runs this once the tree is
+    /// fully linked; it must be re-run after any later reparenting.
+    pub fn cache_full_keys(&mut self) {
+        self.full_keys = (0..self.nodes.len())
+            .map(|idx| self.build_full_key(ConfigKey(idx)))
+            .collect();
+    }
+
+    /// A node's cached full dotted key. Only valid once
+    /// [`Self::cache_full_keys`] has run; trees still being linked must use
+    /// [`Self::build_full_key`].
+    pub fn full_key(&self, key: ConfigKey) -> &str {
+        &self.full_keys[key.0]
+    }
+
+    /// Reconstructs a node's full dotted key by walking its parents.
+    /// O(depth) per call; linked trees should read [`Self::full_key`]
+    /// instead.
+    pub fn build_full_key(&self, key: ConfigKey) -> String {
+        let mut segments = vec![self.node(key).key().to_string()];
+        let mut cur = self.node(key).parent();
+        while let Some(parent) = cur {
+            segments.push(self.node(parent).key().to_string());
+            cur = self.node(parent).parent();
+        }
+        segments.reverse();
+        segments.join(".")
+    }
+}
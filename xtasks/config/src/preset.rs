@@ -0,0 +1,222 @@
+//! Presets: named `[env]` snapshots that can be applied to the cargo config.
+//!
+//! A preset may declare `extends = "<other>"` to inherit another preset's env
+//! table, overriding individual keys. Chains are resolved base-first.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+
+use toml_edit::{DocumentMut, Item};
+
+use crate::report::Report;
+use crate::state::ConfigState;
+
+/// A parsed preset file.
+#[derive(Debug, Clone)]
+pub struct Preset {
+    pub name: String,
+    /// Name of the preset this one inherits from, if any.
+    pub extends: Option<String>,
+    /// The env keys this preset sets itself (not including inherited ones).
+    pub env: BTreeMap<String, String>,
+}
+
+/// Parses one preset file.
+pub fn parse_preset(name: &str, content: &str) -> Result<Preset, Report> {
+    let doc: DocumentMut = content
+        .parse()
+        .map_err(|err: toml_edit::TomlError| Report::error(format!("preset '{name}': {err}")))?;
+
+    let extends = doc
+        .get("extends")
+        .and_then(Item::as_str)
+        .map(str::to_string);
+
+    let mut env = BTreeMap::new();
+    if let Some(table) = doc.get("env").and_then(Item::as_table) {
+        for (key, item) in table.iter() {
+            let Some(value) = item.as_str() else {
+                return Err(Report::error(format!(
+                    "preset '{name}': env key '{key}' must be a string"
+                )));
+            };
+            env.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    Ok(Preset {
+        name: name.to_string(),
+        extends,
+        env,
+    })
+}
+
+/// Loads every `*.toml` preset in `dir`, keyed by file stem.
+pub fn load_presets(dir: &Path) -> Result<HashMap<String, Preset>, Report> {
+    let mut presets = HashMap::new();
+    let entries = std::fs::read_dir(dir)
+        .map_err(|err| Report::error(format!("{}: {err}", dir.display())))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|err| Report::error(err.to_string()))?
+            .path();
+        if path.extension().is_some_and(|e| e == "toml") {
+            let name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+            let content = std::fs::read_to_string(&path)
+                .map_err(|err| Report::error(format!("{}: {err}", path.display())))?;
+            presets.insert(name.clone(), parse_preset(&name, &content)?);
+        }
+    }
+    Ok(presets)
+}
+
+/// Resolves a preset's full env table by applying its `extends` chain
+/// base-first, so a child's keys override its base's.
+///
+/// Inheritance cycles are detected and reported as errors.
+pub fn resolve_preset_env(
+    presets: &HashMap<String, Preset>,
+    name: &str,
+) -> Result<BTreeMap<String, String>, Report> {
+    // Collect the chain child-first, guarding against cycles.
+    let mut chain = Vec::new();
+    let mut cur = Some(name.to_string());
+    while let Some(preset_name) = cur {
+        if chain.contains(&preset_name) {
+            chain.push(preset_name);
+            return Err(Report::error(format!(
+                "preset inheritance cycle: {}",
+                chain.join(" -> ")
+            )));
+        }
+        let preset = presets.get(&preset_name).ok_or_else(|| {
+            let mut known: Vec<&str> = presets.values().map(|p| p.name.as_str()).collect();
+            known.sort_unstable();
+            Report::error(format!(
+                "unknown preset '{preset_name}' (available: {})",
+                known.join(", ")
+            ))
+        })?;
+        cur = preset.extends.clone();
+        chain.push(preset_name);
+    }
+
+    // Apply base-first so later (more derived) presets win.
+    let mut env = BTreeMap::new();
+    for preset_name in chain.iter().rev() {
+        for (key, value) in &presets[preset_name].env {
+            env.insert(key.clone(), value.clone());
+        }
+    }
+    Ok(env)
+}
+
+/// Warns about preset env keys that map to no option in any `options.toml` —
+/// typically a preset gone stale after an option was renamed or removed.
+/// [`apply_preset`] copies the env wholesale, so without this check a dead
+/// key is silently injected into the stored config. The preset still applies;
+/// the warnings just surface what is stale.
+pub fn unknown_key_warnings(state: &ConfigState, env: &BTreeMap<String, String>) -> Vec<Report> {
+    env.keys()
+        .filter(|key| state.option_by_env_key(key).is_none())
+        .map(|key| {
+            Report::warning(format!(
+                "preset key '{key}' matches no option in any options.toml"
+            ))
+        })
+        .collect()
+}
+
+/// Replaces the `[env]` table of `doc` with the resolved preset env.
+pub fn apply_preset(doc: &mut DocumentMut, env: &BTreeMap<String, String>) {
+    let table = doc["env"].or_insert(Item::Table(toml_edit::Table::new()));
+    if let Some(table) = table.as_table_mut() {
+        table.clear();
+        for (key, value) in env {
+            table.insert(key, toml_edit::value(value.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(name: &str, extends: Option<&str>, env: &[(&str, &str)]) -> Preset {
+        Preset {
+            name: name.to_string(),
+            extends: extends.map(str::to_string),
+            env: env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    fn presets_of(list: Vec<Preset>) -> HashMap<String, Preset> {
+        list.into_iter().map(|p| (p.name.clone(), p)).collect()
+    }
+
+    #[test]
+    fn single_level_extend_inherits_base_keys() {
+        let presets = presets_of(vec![
+            preset("base", None, &[("OSIRIS_A", "1"), ("OSIRIS_B", "2")]),
+            preset("child", Some("base"), &[("OSIRIS_C", "3")]),
+        ]);
+        let env = resolve_preset_env(&presets, "child").unwrap();
+        assert_eq!(env["OSIRIS_A"], "1");
+        assert_eq!(env["OSIRIS_B"], "2");
+        assert_eq!(env["OSIRIS_C"], "3");
+    }
+
+    #[test]
+    fn child_keys_override_base_keys() {
+        let presets = presets_of(vec![
+            preset("base", None, &[("OSIRIS_A", "1")]),
+            preset("child", Some("base"), &[("OSIRIS_A", "override")]),
+        ]);
+        let env = resolve_preset_env(&presets, "child").unwrap();
+        assert_eq!(env["OSIRIS_A"], "override");
+    }
+
+    #[test]
+    fn unknown_preset_keys_warn_but_still_apply() {
+        use crate::state::MacroEngine;
+        use crate::testutil::{bool_option, tree_of};
+
+        let tree = tree_of(vec![bool_option("driver", true, &[])]);
+        let state = ConfigState::new(tree, MacroEngine::new());
+        let presets = presets_of(vec![preset(
+            "stale",
+            None,
+            &[("OSIRIS_DRIVER", "true"), ("OSIRIS_GONE", "1")],
+        )]);
+        let env = resolve_preset_env(&presets, "stale").unwrap();
+
+        let warnings = unknown_key_warnings(&state, &env);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("'OSIRIS_GONE'"));
+
+        // The preset applies wholesale regardless; only the warning flags it.
+        let mut doc = DocumentMut::default();
+        apply_preset(&mut doc, &env);
+        let out = doc.to_string();
+        assert!(out.contains("OSIRIS_DRIVER = \"true\""));
+        assert!(out.contains("OSIRIS_GONE = \"1\""));
+    }
+
+    #[test]
+    fn inheritance_cycle_is_an_error() {
+        let presets = presets_of(vec![
+            preset("a", Some("b"), &[]),
+            preset("b", Some("a"), &[]),
+        ]);
+        let err = resolve_preset_env(&presets, "a").unwrap_err();
+        assert!(err.message.contains("cycle"));
+    }
+}
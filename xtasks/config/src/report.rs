@@ -0,0 +1,78 @@
+//! Error reporting with source spans, rendered via annotate-snippets.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use annotate_snippets::{Level, Renderer, Snippet};
+
+/// A diagnostic tied (optionally) to a span in a source file.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub message: String,
+    pub file: Option<PathBuf>,
+    pub span: Option<Range<usize>>,
+    pub severity: Severity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Report {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            file: None,
+            span: None,
+            severity: Severity::Error,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            file: None,
+            span: None,
+            severity: Severity::Warning,
+        }
+    }
+
+    /// Builds a report pointing at `span` inside `file`.
+    pub fn from_spanned(
+        file: impl AsRef<Path>,
+        span: Range<usize>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            file: Some(file.as_ref().to_path_buf()),
+            span: Some(span),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Renders the report, underlining the span when the source is available.
+    pub fn render(&self, source: Option<&str>) -> String {
+        match (&self.file, &self.span, source) {
+            (Some(file), Some(span), Some(source)) => {
+                let level = match self.severity {
+                    Severity::Error => Level::Error,
+                    Severity::Warning => Level::Warning,
+                };
+                let message = level.title(&self.message).snippet(
+                    Snippet::source(source)
+                        .origin(file.to_str().unwrap_or("<options.toml>"))
+                        .fold(true)
+                        .annotation(level.span(span.clone())),
+                );
+                Renderer::styled().render(message).to_string()
+            }
+            _ => match self.severity {
+                Severity::Error => format!("error: {}", self.message),
+                Severity::Warning => format!("warning: {}", self.message),
+            },
+        }
+    }
+}
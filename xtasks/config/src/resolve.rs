@@ -0,0 +1,591 @@
+//! Linking of parsed files into one tree and resolution of dependency keys
+//! to [`ConfigKey`] ids.
+
+use crate::file::ParsedFile;
+use crate::node::{ConfigKey, ConfigNode, ConfigNodelike, ConfigTree};
+use crate::report::Report;
+use std::collections::HashMap;
+
+/// Attaches every file's top-level nodes under the category named by its
+/// `metadata.parent`, or under the root when no parent is declared.
+///
+/// Files are linked in passes so a parent category declared by a
+/// later-parsed file is still found.
+pub fn link_nodes(tree: &mut ConfigTree, files: &[ParsedFile]) -> Result<(), Vec<Report>> {
+    let mut pending: Vec<&ParsedFile> = Vec::new();
+    for file in files {
+        match &file.parent {
+            None => {
+                for &node in &file.nodes {
+                    tree.root.push(node);
+                }
+            }
+            Some(_) => pending.push(file),
+        }
+    }
+
+    // Fixpoint: attach files whose parent category is reachable, repeating
+    // until no progress is made.
+    loop {
+        let mut progressed = false;
+        let mut still_pending = Vec::new();
+        for file in pending {
+            let parent = file.parent.as_deref().unwrap_or_default();
+            match lookup(tree, parent) {
+                Some(parent_key) if tree.node(parent_key).as_category().is_some() => {
+                    for &node in &file.nodes {
+                        if let ConfigNode::Category(c) = tree.node_mut(parent_key) {
+                            c.children.push(node);
+                        }
+                        set_parent(tree, node, parent_key);
+                    }
+                    progressed = true;
+                }
+                _ => still_pending.push(file),
+            }
+        }
+        pending = still_pending;
+        if pending.is_empty() || !progressed {
+            break;
+        }
+    }
+
+    // Whatever is still pending names a parent category no file declares:
+    // its nodes would silently never appear anywhere in the tree.
+    if pending.is_empty() {
+        Ok(())
+    } else {
+        Err(pending
+            .iter()
+            .map(|file| {
+                Report::error(format!(
+                    "{} is orphaned: its metadata.parent '{}' matches no category",
+                    file.path.display(),
+                    file.parent.as_deref().unwrap_or_default(),
+                ))
+            })
+            .collect())
+    }
+}
+
+/// Warns about categories declared more than once (across files) with
+/// conflicting `name`/`description`: linking keeps the first-seen metadata,
+/// so the other declarations are silently ignored without this check.
+pub fn duplicate_category_warnings(tree: &ConfigTree) -> Vec<Report> {
+    let mut warnings = Vec::new();
+    let categories: Vec<ConfigKey> = tree
+        .keys()
+        .filter(|&k| tree.node(k).as_category().is_some())
+        .collect();
+
+    for (i, &first) in categories.iter().enumerate() {
+        let path = tree.build_full_key(first);
+        for &other in &categories[i + 1..] {
+            if tree.build_full_key(other) != path {
+                continue;
+            }
+            let a = tree.node(first);
+            let b = tree.node(other);
+            if a.name() != b.name() || a.description() != b.description() {
+                warnings.push(Report::warning(format!(
+                    "category '{path}' is declared with conflicting metadata in {} and {}; \
+                     the declaration in {} wins",
+                    tree.source(first).display(),
+                    tree.source(other).display(),
+                    tree.source(first).display(),
+                )));
+            }
+        }
+    }
+    warnings
+}
+
+/// Merges options declared at the same full path in several files.
+///
+/// Two feature modules may intentionally declare the same shared option;
+/// without this pass each declaration would become a distinct node with a
+/// distinct id. Declarations whose type, constraints, defaults and
+/// attributes are identical collapse into the first-seen node, unioning
+/// their dependencies; anything else is a genuine conflict and errors.
+fn merge_duplicate_options(tree: &mut ConfigTree) -> Result<(), Vec<Report>> {
+    let mut reports = Vec::new();
+    let mut removed = vec![false; tree.nodes.len()];
+    let mut seen: HashMap<String, ConfigKey> = HashMap::new();
+
+    for key in tree.keys() {
+        if tree.node(key).as_option().is_none() {
+            continue;
+        }
+        let full = tree.build_full_key(key);
+        let Some(&primary) = seen.get(&full) else {
+            seen.insert(full, key);
+            continue;
+        };
+        let a = tree.node(primary).as_option().unwrap();
+        let b = tree.node(key).as_option().unwrap();
+        let identical = a.ty == b.ty
+            && a.default == b.default
+            && a.target_defaults == b.target_defaults
+            && a.attributes == b.attributes
+            && a.rebuild == b.rebuild
+            && a.display == b.display;
+        if !identical {
+            reports.push(Report::error(format!(
+                "option '{full}' is declared in both {} and {} with conflicting definitions",
+                tree.source(primary).display(),
+                tree.source(key).display(),
+            )));
+            continue;
+        }
+        // Union the duplicate's dependencies into the surviving node.
+        let extra = b.depends_on.clone();
+        if let ConfigNode::Option(first) = tree.node_mut(primary) {
+            for dep in extra {
+                let present = first.depends_on.iter().any(|d| {
+                    d.raw_key == dep.raw_key && d.value == dep.value && d.group == dep.group
+                });
+                if !present {
+                    first.depends_on.push(dep);
+                }
+            }
+        }
+        removed[key.0] = true;
+    }
+
+    if !reports.is_empty() {
+        return Err(reports);
+    }
+    if removed.contains(&true) {
+        remove_nodes(tree, &removed);
+    }
+    Ok(())
+}
+
+/// Drops the marked nodes from the arena, compacting it and rewriting every
+/// stored [`ConfigKey`]. Only valid before dependencies are resolved.
+fn remove_nodes(tree: &mut ConfigTree, removed: &[bool]) {
+    let mut map: Vec<Option<ConfigKey>> = vec![None; tree.nodes.len()];
+    let mut next = 0;
+    for (idx, &gone) in removed.iter().enumerate() {
+        if !gone {
+            map[idx] = Some(ConfigKey(next));
+            next += 1;
+        }
+    }
+
+    let nodes = std::mem::take(&mut tree.nodes);
+    let sources = std::mem::take(&mut tree.sources);
+    for (idx, (node, source)) in nodes.into_iter().zip(sources).enumerate() {
+        if map[idx].is_some() {
+            tree.nodes.push(node);
+            tree.sources.push(source);
+        }
+    }
+
+    tree.root = tree.root.iter().filter_map(|k| map[k.0]).collect();
+    for node in &mut tree.nodes {
+        match node {
+            ConfigNode::Category(c) => {
+                c.parent = c.parent.and_then(|k| map[k.0]);
+                c.children = c.children.iter().filter_map(|k| map[k.0]).collect();
+            }
+            ConfigNode::Option(o) => o.parent = o.parent.and_then(|k| map[k.0]),
+        }
+    }
+}
+
+/// Resolves every dependency's raw key to a [`ConfigKey`], after collapsing
+/// duplicate option declarations via [`merge_duplicate_options`].
+///
+/// Resolution order:
+/// 1. A dotted key is a full path from the root, nothing else.
+/// 2. A bare key is first looked up in the dependent option's own scope
+///    (i.e. as a sibling).
+/// 3. When that fails, the whole tree is searched for options with that key
+///    segment — this covers dependencies on options in other categories and
+///    files, regardless of parse order. Exactly one match resolves; several
+///    matches are reported as ambiguous (qualify the key to pick one), none
+///    as not found.
+pub fn resolve_paths(tree: &mut ConfigTree) -> Result<(), Vec<Report>> {
+    merge_duplicate_options(tree)?;
+
+    let mut reports = Vec::new();
+    let mut resolutions: Vec<(ConfigKey, usize, ConfigKey)> = Vec::new();
+
+    for key in tree.keys() {
+        let ConfigNode::Option(option) = tree.node(key) else {
+            continue;
+        };
+        let scope = match option.parent {
+            Some(parent) => tree.build_full_key(parent),
+            None => String::new(),
+        };
+        for (idx, dep) in option.depends_on.iter().enumerate() {
+            let full = if dep.raw_key.contains('.') || scope.is_empty() {
+                dep.raw_key.clone()
+            } else {
+                format!("{}.{}", scope, dep.raw_key)
+            };
+            match lookup(tree, &full) {
+                Some(target) => resolutions.push((key, idx, target)),
+                None if !dep.raw_key.contains('.') => {
+                    // Qualified fallback: search the whole tree for options
+                    // with this key segment.
+                    let matches: Vec<ConfigKey> = tree
+                        .keys()
+                        .filter(|&k| {
+                            tree.node(k).as_option().is_some()
+                                && tree.node(k).key() == dep.raw_key
+                        })
+                        .collect();
+                    match matches.as_slice() {
+                        [target] => resolutions.push((key, idx, *target)),
+                        [] => reports.push(Report::error(format!(
+                            "option '{}' depends on unknown option '{}'",
+                            tree.build_full_key(key),
+                            dep.raw_key
+                        ))),
+                        candidates => reports.push(Report::error(format!(
+                            "option '{}' has ambiguous dependency '{}'; qualify it as one of: {}",
+                            tree.build_full_key(key),
+                            dep.raw_key,
+                            candidates
+                                .iter()
+                                .map(|&c| tree.build_full_key(c))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ))),
+                    }
+                }
+                None => reports.push(Report::error(format!(
+                    "option '{}' depends on unknown option '{}'",
+                    tree.build_full_key(key),
+                    dep.raw_key
+                ))),
+            }
+        }
+    }
+
+    if !reports.is_empty() {
+        return Err(reports);
+    }
+
+    for (key, idx, target) in resolutions {
+        if let ConfigNode::Option(option) = tree.node_mut(key) {
+            option.depends_on[idx].resolved = Some(target);
+        }
+    }
+    // The tree's structure is final now: cache the full keys so the hot
+    // serialize/deserialize and UI paths read them instead of re-walking
+    // parents.
+    tree.cache_full_keys();
+    Ok(())
+}
+
+/// Finds the node at a full dotted path, walking down from the root.
+pub fn lookup(tree: &ConfigTree, full_key: &str) -> Option<ConfigKey> {
+    let mut candidates: &[ConfigKey] = &tree.root;
+    let mut found = None;
+    for segment in full_key.split('.') {
+        found = candidates
+            .iter()
+            .copied()
+            .find(|&k| tree.node(k).key() == segment);
+        match found {
+            Some(key) => candidates = tree.children(key),
+            None => return None,
+        }
+    }
+    found
+}
+
+fn set_parent(tree: &mut ConfigTree, node: ConfigKey, parent: ConfigKey) {
+    match tree.node_mut(node) {
+        ConfigNode::Category(c) => c.parent = Some(parent),
+        ConfigNode::Option(o) => o.parent = Some(parent),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{ConfigCategory, Dependency, DependencyGroup};
+    use crate::testutil::{bool_option, int_option};
+    use std::path::PathBuf;
+
+    fn category(key: &str) -> ConfigNode {
+        ConfigNode::Category(ConfigCategory {
+            key: key.to_string(),
+            name: key.to_string(),
+            description: String::new(),
+            attributes: Vec::new(),
+            parent: None,
+            children: Vec::new(),
+        })
+    }
+
+    /// Builds categories at the root, each holding the given options.
+    fn tree_with_categories(cats: Vec<(&str, Vec<ConfigNode>)>) -> ConfigTree {
+        let mut tree = ConfigTree::default();
+        for (cat, options) in cats {
+            let cat_key = tree.push(category(cat), PathBuf::from("test/options.toml"));
+            tree.root.push(cat_key);
+            for option in options {
+                let key = tree.push(option, PathBuf::from("test/options.toml"));
+                if let ConfigNode::Category(c) = tree.node_mut(cat_key) {
+                    c.children.push(key);
+                }
+                set_parent(&mut tree, key, cat_key);
+            }
+        }
+        tree
+    }
+
+    #[test]
+    fn bare_key_falls_back_to_a_tree_wide_search() {
+        // "x" in category a depends on "y", which only exists in category b —
+        // the sibling lookup fails, the fallback search must find it.
+        let mut tree = tree_with_categories(vec![
+            ("a", vec![bool_option("x", true, &[("y", true)])]),
+            ("b", vec![bool_option("y", true, &[])]),
+        ]);
+        resolve_paths(&mut tree).unwrap();
+
+        let x = lookup(&tree, "a.x").unwrap();
+        let y = lookup(&tree, "b.y").unwrap();
+        let option = tree.node(x).as_option().unwrap();
+        assert_eq!(option.depends_on[0].resolved, Some(y));
+    }
+
+    #[test]
+    fn siblings_shadow_the_fallback_search() {
+        let mut tree = tree_with_categories(vec![
+            (
+                "a",
+                vec![
+                    bool_option("x", true, &[("y", true)]),
+                    bool_option("y", true, &[]),
+                ],
+            ),
+            ("b", vec![bool_option("y", true, &[])]),
+        ]);
+        resolve_paths(&mut tree).unwrap();
+
+        let x = lookup(&tree, "a.x").unwrap();
+        let sibling = lookup(&tree, "a.y").unwrap();
+        let option = tree.node(x).as_option().unwrap();
+        assert_eq!(option.depends_on[0].resolved, Some(sibling));
+    }
+
+    #[test]
+    fn ambiguous_bare_key_is_distinguished_from_not_found() {
+        let mut tree = tree_with_categories(vec![
+            ("a", vec![bool_option("x", true, &[("y", true)])]),
+            ("b", vec![bool_option("y", true, &[])]),
+            ("c", vec![bool_option("y", true, &[])]),
+        ]);
+        let reports = resolve_paths(&mut tree).unwrap_err();
+        assert!(reports[0].message.contains("ambiguous"));
+        assert!(reports[0].message.contains("b.y"));
+        assert!(reports[0].message.contains("c.y"));
+
+        let mut tree =
+            tree_with_categories(vec![("a", vec![bool_option("x", true, &[("z", true)])])]);
+        let reports = resolve_paths(&mut tree).unwrap_err();
+        assert!(reports[0].message.contains("unknown option 'z'"));
+    }
+
+    #[test]
+    fn orphaned_file_with_unknown_parent_errors() {
+        let mut tree = ConfigTree::default();
+        let rooted = tree.push(
+            bool_option("driver", true, &[]),
+            PathBuf::from("a/options.toml"),
+        );
+        let lost = tree.push(
+            bool_option("tuner", true, &[]),
+            PathBuf::from("b/options.toml"),
+        );
+        let files = vec![
+            ParsedFile {
+                path: PathBuf::from("a/options.toml"),
+                parent: None,
+                nodes: vec![rooted],
+            },
+            ParsedFile {
+                path: PathBuf::from("b/options.toml"),
+                parent: Some("drivers.audio".to_string()),
+                nodes: vec![lost],
+            },
+        ];
+
+        let reports = link_nodes(&mut tree, &files).unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("b/options.toml"));
+        assert!(reports[0].message.contains("orphaned"));
+        assert!(reports[0].message.contains("'drivers.audio'"));
+        // The well-formed file still linked under the root.
+        assert_eq!(tree.root.as_slice(), [rooted]);
+    }
+
+    #[test]
+    fn conflicting_duplicate_category_metadata_warns() {
+        let mut tree = ConfigTree::default();
+        let mut first = category("drivers");
+        if let ConfigNode::Category(c) = &mut first {
+            c.description = "Device drivers".to_string();
+        }
+        let mut second = category("drivers");
+        if let ConfigNode::Category(c) = &mut second {
+            c.description = "Board support".to_string();
+        }
+        let first = tree.push(first, PathBuf::from("a/options.toml"));
+        let second = tree.push(second, PathBuf::from("b/options.toml"));
+        tree.root.push(first);
+        tree.root.push(second);
+
+        let warnings = duplicate_category_warnings(&tree);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("'drivers'"));
+        assert!(warnings[0].message.contains("a/options.toml"));
+        assert!(warnings[0].message.contains("b/options.toml"));
+    }
+
+    #[test]
+    fn identical_duplicate_category_metadata_does_not_warn() {
+        let mut tree = ConfigTree::default();
+        let first = tree.push(category("drivers"), PathBuf::from("a/options.toml"));
+        let second = tree.push(category("drivers"), PathBuf::from("b/options.toml"));
+        tree.root.push(first);
+        tree.root.push(second);
+
+        assert!(duplicate_category_warnings(&tree).is_empty());
+    }
+
+    #[test]
+    fn cached_full_keys_match_the_walked_values() {
+        let mut tree = tree_with_categories(vec![
+            ("kernel", vec![bool_option("verbose", true, &[])]),
+            (
+                "drivers",
+                vec![
+                    bool_option("uart", true, &[]),
+                    bool_option("spi", false, &[]),
+                ],
+            ),
+        ]);
+        // Deepen the tree: move "drivers" under "kernel".
+        let kernel = lookup(&tree, "kernel").unwrap();
+        let drivers = lookup(&tree, "drivers").unwrap();
+        set_parent(&mut tree, drivers, kernel);
+        if let ConfigNode::Category(c) = tree.node_mut(kernel) {
+            c.children.push(drivers);
+        }
+        tree.root.retain(|&k| k != drivers);
+
+        let walked: Vec<String> = (0..tree.nodes.len())
+            .map(|idx| tree.build_full_key(ConfigKey(idx)))
+            .collect();
+        resolve_paths(&mut tree).unwrap();
+        for key in tree.keys() {
+            assert_eq!(tree.full_key(key), walked[key.0]);
+        }
+        assert_eq!(
+            tree.full_key(lookup(&tree, "kernel.drivers.spi").unwrap()),
+            "kernel.drivers.spi"
+        );
+    }
+
+    #[test]
+    fn identical_duplicate_options_merge_and_union_dependencies() {
+        let mut tree = tree_with_categories(vec![(
+            "kernel",
+            vec![
+                bool_option("uart", true, &[]),
+                bool_option("trace", true, &[("uart", true)]),
+            ],
+        )]);
+        // A second file intentionally re-declares kernel.trace with the same
+        // definition but its own dependencies.
+        let kernel = lookup(&tree, "kernel").unwrap();
+        let dup = tree.push(
+            bool_option("trace", true, &[("uart", true), ("dhcp", true)]),
+            PathBuf::from("other/options.toml"),
+        );
+        if let ConfigNode::Category(c) = tree.node_mut(kernel) {
+            c.children.push(dup);
+        }
+        set_parent(&mut tree, dup, kernel);
+        // net.dhcp sits after the duplicate in the arena, so dropping the
+        // duplicate must remap its key.
+        let net = tree.push(category("net"), PathBuf::from("net/options.toml"));
+        tree.root.push(net);
+        let dhcp = tree.push(
+            bool_option("dhcp", true, &[]),
+            PathBuf::from("net/options.toml"),
+        );
+        if let ConfigNode::Category(c) = tree.node_mut(net) {
+            c.children.push(dhcp);
+        }
+        set_parent(&mut tree, dhcp, net);
+
+        let before = tree.nodes.len();
+        resolve_paths(&mut tree).unwrap();
+        assert_eq!(tree.nodes.len(), before - 1);
+
+        let trace = lookup(&tree, "kernel.trace").unwrap();
+        let uart = lookup(&tree, "kernel.uart").unwrap();
+        let dhcp = lookup(&tree, "net.dhcp").unwrap();
+        let option = tree.node(trace).as_option().unwrap();
+        // The shared "uart" dependency deduplicated; "dhcp" was unioned in.
+        assert_eq!(option.depends_on.len(), 2);
+        assert_eq!(option.depends_on[0].resolved, Some(uart));
+        assert_eq!(option.depends_on[1].resolved, Some(dhcp));
+        assert_eq!(tree.full_key(dhcp), "net.dhcp");
+    }
+
+    #[test]
+    fn conflicting_duplicate_option_definitions_error() {
+        let mut tree =
+            tree_with_categories(vec![("kernel", vec![bool_option("trace", true, &[])])]);
+        let kernel = lookup(&tree, "kernel").unwrap();
+        let dup = tree.push(
+            int_option("trace", 4, 0, 8),
+            PathBuf::from("other/options.toml"),
+        );
+        if let ConfigNode::Category(c) = tree.node_mut(kernel) {
+            c.children.push(dup);
+        }
+        set_parent(&mut tree, dup, kernel);
+
+        let reports = resolve_paths(&mut tree).unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("'kernel.trace'"));
+        assert!(reports[0].message.contains("conflicting definitions"));
+        assert!(reports[0].message.contains("test/options.toml"));
+        assert!(reports[0].message.contains("other/options.toml"));
+    }
+
+    #[test]
+    fn dotted_keys_stay_absolute() {
+        let mut tree = tree_with_categories(vec![
+            ("a", vec![bool_option("x", true, &[])]),
+            ("b", vec![bool_option("y", true, &[])]),
+        ]);
+        // Patch in a dotted dependency on b.y.
+        let x = lookup(&tree, "a.x").unwrap();
+        if let ConfigNode::Option(o) = tree.node_mut(x) {
+            o.depends_on.push(Dependency {
+                raw_key: "b.y".to_string(),
+                resolved: None,
+                value: crate::node::ConfigValue::Bool(true),
+                group: DependencyGroup::default(),
+            });
+        }
+        resolve_paths(&mut tree).unwrap();
+
+        let y = lookup(&tree, "b.y").unwrap();
+        let option = tree.node(x).as_option().unwrap();
+        assert_eq!(option.depends_on[0].resolved, Some(y));
+    }
+}
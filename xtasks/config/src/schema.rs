@@ -0,0 +1,161 @@
+//! JSON export of the option tree's *schema* — what can be configured, not
+//! what currently is. The counterpart of `ConfigState::to_json` (which dumps
+//! values) for tooling that wants to generate or validate configs offline.
+
+use crate::node::{Attribute, ConfigNode, ConfigNodelike, ConfigTree, ConfigType};
+use crate::state::config_value_to_json;
+
+/// Renders the parsed and resolved tree as a JSON object mapping each node's
+/// full dotted key to its schema entry: kind, name, description, attributes,
+/// and — for options — the type with all of its constraints, the default and
+/// the resolved dependencies.
+pub fn to_json(tree: &ConfigTree) -> serde_json::Value {
+    let mut map = serde_json::Map::new();
+    let mut entries: Vec<(String, serde_json::Value)> = tree
+        .keys()
+        .map(|key| (tree.full_key(key).to_string(), node_json(tree, key)))
+        .collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (full_key, entry) in entries {
+        map.insert(full_key, entry);
+    }
+    serde_json::Value::Object(map)
+}
+
+fn node_json(tree: &ConfigTree, key: crate::node::ConfigKey) -> serde_json::Value {
+    let node = tree.node(key);
+    let mut entry = serde_json::Map::new();
+    entry.insert("name".into(), node.name().into());
+    entry.insert("description".into(), node.description().into());
+    entry.insert(
+        "attributes".into(),
+        node.attributes()
+            .iter()
+            .map(attribute_name)
+            .collect::<Vec<_>>()
+            .into(),
+    );
+
+    match node {
+        ConfigNode::Category(_) => {
+            entry.insert("kind".into(), "category".into());
+        }
+        ConfigNode::Option(option) => {
+            entry.insert("kind".into(), "option".into());
+            entry.insert("type".into(), type_json(&option.ty));
+            entry.insert("default".into(), config_value_to_json(&option.default));
+            entry.insert(
+                "rebuild".into(),
+                format!("{:?}", option.rebuild).to_lowercase().into(),
+            );
+            entry.insert(
+                "depends_on".into(),
+                option
+                    .depends_on
+                    .iter()
+                    .map(|dep| {
+                        let mut dep_entry = serde_json::Map::new();
+                        // Resolution already ran, so a missing target would
+                        // have failed the load; the raw key is kept for
+                        // traceability all the same.
+                        let key = match dep.resolved {
+                            Some(target) => tree.full_key(target).to_string(),
+                            None => dep.raw_key.clone(),
+                        };
+                        dep_entry.insert("key".into(), key.into());
+                        dep_entry.insert("value".into(), config_value_to_json(&dep.value));
+                        serde_json::Value::Object(dep_entry)
+                    })
+                    .collect::<Vec<_>>()
+                    .into(),
+            );
+        }
+    }
+    serde_json::Value::Object(entry)
+}
+
+/// The type with every constraint the parser knows about, so a consumer can
+/// validate values without re-reading the options.toml files.
+fn type_json(ty: &ConfigType) -> serde_json::Value {
+    let mut entry = serde_json::Map::new();
+    match ty {
+        ConfigType::Bool => {
+            entry.insert("type".into(), "bool".into());
+        }
+        ConfigType::Integer { min, max, unit } => {
+            entry.insert("type".into(), "integer".into());
+            entry.insert("min".into(), (*min).into());
+            entry.insert("max".into(), (*max).into());
+            if let Some(unit) = unit {
+                entry.insert("unit".into(), unit.as_str().into());
+            }
+        }
+        ConfigType::String { allowed_values } => {
+            entry.insert("type".into(), "string".into());
+            if let Some(allowed) = allowed_values {
+                entry.insert(
+                    "allowed_values".into(),
+                    allowed
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .into(),
+                );
+            }
+        }
+        ConfigType::List => {
+            entry.insert("type".into(), "list".into());
+        }
+        ConfigType::Flags { values } => {
+            entry.insert("type".into(), "flags".into());
+            entry.insert(
+                "values".into(),
+                values.iter().map(String::as_str).collect::<Vec<_>>().into(),
+            );
+        }
+    }
+    serde_json::Value::Object(entry)
+}
+
+fn attribute_name(attr: &Attribute) -> &'static str {
+    match attr {
+        Attribute::Hidden => "Hidden",
+        Attribute::NoStore => "NoStore",
+        Attribute::Skip => "Skip",
+        Attribute::NoHiddenPreview => "NoHiddenPreview",
+        Attribute::Experimental => "Experimental",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{bool_option, int_option, tree_of};
+
+    #[test]
+    fn schema_carries_constraints_and_dependencies() {
+        let tree = tree_of(vec![
+            bool_option("driver", true, &[]),
+            bool_option("feature", false, &[("driver", true)]),
+            int_option("depth", 8, 1, 64),
+        ]);
+
+        let schema = to_json(&tree);
+
+        // The integer option's range survives in full.
+        let depth = &schema["depth"];
+        assert_eq!(depth["kind"], "option");
+        assert_eq!(depth["type"]["type"], "integer");
+        assert_eq!(depth["type"]["min"], 1);
+        assert_eq!(depth["type"]["max"], 64);
+
+        // Dependencies come out resolved to full keys, with their required
+        // value.
+        let deps = depth["depends_on"].as_array().unwrap();
+        assert!(deps.is_empty());
+        let feature_deps = schema["feature"]["depends_on"].as_array().unwrap();
+        assert_eq!(feature_deps.len(), 1);
+        assert_eq!(feature_deps[0]["key"], "driver");
+        assert_eq!(feature_deps[0]["value"], true);
+    }
+}
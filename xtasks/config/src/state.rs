@@ -0,0 +1,1133 @@
+//! The live configuration state: current values, dependency evaluation and
+//! (de)serialization to the `[env]` table of `.cargo/config.toml`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use toml_edit::{DocumentMut, Item};
+
+use crate::node::{
+    Attribute, ConfigKey, ConfigNode, ConfigTree, ConfigType, ConfigValue, DependencyGroup,
+    RebuildKind,
+};
+use crate::report::Report;
+
+/// Target triples the workspace's machine crates support. Keep in sync with
+/// the `machine/` selection logic.
+pub const SUPPORTED_TARGETS: &[&str] = &[
+    "thumbv7em-none-eabihf",
+    "x86_64-unknown-linux-gnu",
+    "aarch64-unknown-linux-gnu",
+];
+
+/// Checks `build.target` against the supported list, returning an error that
+/// names the valid options so a typo'd triple fails up front instead of as a
+/// confusing downstream build error.
+pub fn validate_target_triple(triple: &str) -> Result<(), Report> {
+    if SUPPORTED_TARGETS.contains(&triple) {
+        Ok(())
+    } else {
+        Err(Report::error(format!(
+            "unsupported target triple '{triple}'; supported targets are: {}",
+            SUPPORTED_TARGETS.join(", ")
+        )))
+    }
+}
+
+/// Expands `$(...)` macros in string defaults, e.g. `$(target)`.
+#[derive(Debug, Default, Clone)]
+pub struct MacroEngine {
+    target_triple: Option<String>,
+}
+
+impl MacroEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_target_triple(mut self, triple: impl Into<String>) -> Self {
+        self.target_triple = Some(triple.into());
+        self
+    }
+
+    pub fn target_triple(&self) -> Option<&str> {
+        self.target_triple.as_deref()
+    }
+
+    /// Expands known macros in `input`; unknown macros are left verbatim.
+    pub fn expand(&self, input: &str) -> String {
+        let mut out = input.to_string();
+        if let Some(triple) = &self.target_triple {
+            out = out.replace("$(target)", triple);
+        }
+        out
+    }
+}
+
+/// The in-memory configuration: the resolved tree plus a value per option.
+pub struct ConfigState {
+    pub tree: ConfigTree,
+    /// Current value per option node.
+    pub values: HashMap<ConfigKey, ConfigValue>,
+    /// Options whose dependencies are currently all satisfied.
+    enabled: HashSet<ConfigKey>,
+    /// Options changed since the state was loaded (or last marked clean).
+    changed: HashSet<ConfigKey>,
+    /// Values shadowed by a process-environment override: key → the
+    /// persisted value. The override itself lives in `values` but is never
+    /// written back to disk.
+    env_overrides: HashMap<ConfigKey, ConfigValue>,
+    /// Non-fatal authoring problems found while building the state.
+    pub warnings: Vec<Report>,
+    macros: MacroEngine,
+}
+
+impl ConfigState {
+    /// Builds the state from a resolved tree, computing initial values from
+    /// the options' defaults.
+    pub fn new(tree: ConfigTree, macros: MacroEngine) -> Self {
+        let mut state = Self {
+            tree,
+            values: HashMap::new(),
+            enabled: HashSet::new(),
+            changed: HashSet::new(),
+            env_overrides: HashMap::new(),
+            warnings: Vec::new(),
+            macros,
+        };
+        state.compute_initial_values();
+        state.update_dependency_states();
+        state.warnings = state.default_violation_warnings();
+        let type_errors = state.default_type_errors();
+        state.warnings.extend(type_errors);
+        state
+    }
+
+    /// Type-checks every option's (macro-expanded) initial value against its
+    /// declared constraints. This matters most for `NoStore` options: they
+    /// are never serialized, so an out-of-range default would otherwise skip
+    /// the set/deserialize validation and surface only at runtime.
+    fn default_type_errors(&self) -> Vec<Report> {
+        let mut errors = Vec::new();
+        for key in self.tree.keys() {
+            let Some(option) = self.tree.node(key).as_option() else {
+                continue;
+            };
+            if option.attributes.contains(&Attribute::Skip) {
+                continue;
+            }
+            let Some(value) = self.values.get(&key) else {
+                continue;
+            };
+            if let Err(msg) = option.ty.validate(value) {
+                errors.push(Report::error(format!(
+                    "'{}' has an invalid default: {msg}",
+                    self.tree.full_key(key)
+                )));
+            }
+        }
+        errors
+    }
+
+    /// Flags options that are active by default yet disabled by their own
+    /// dependencies' defaults — such an option can never take effect in a
+    /// fresh config, which is almost always an authoring mistake.
+    fn default_violation_warnings(&self) -> Vec<Report> {
+        let mut warnings = Vec::new();
+        for key in self.tree.keys() {
+            let Some(option) = self.tree.node(key).as_option() else {
+                continue;
+            };
+            if option.attributes.contains(&Attribute::Skip) {
+                continue;
+            }
+            if option.default == ConfigValue::Bool(true) && !self.enabled.contains(&key) {
+                warnings.push(Report::warning(format!(
+                    "'{}' defaults to true but its dependencies are unsatisfied \
+                     by default, so a fresh config can never enable it",
+                    self.tree.full_key(key)
+                )));
+            }
+        }
+        warnings
+    }
+
+    /// Seeds every option's value from its (macro-expanded) default. An
+    /// exact match in the option's per-target defaults wins over the base
+    /// (`*`) default when the build target is known.
+    fn compute_initial_values(&mut self) {
+        for key in self.tree.keys() {
+            let ConfigNode::Option(option) = self.tree.node(key) else {
+                continue;
+            };
+            if option.attributes.contains(&Attribute::Skip) {
+                continue;
+            }
+            let default = self
+                .macros
+                .target_triple()
+                .and_then(|triple| {
+                    option
+                        .target_defaults
+                        .iter()
+                        .find(|(target, _)| target == triple)
+                })
+                .map_or(&option.default, |(_, value)| value);
+            let value = match default {
+                ConfigValue::String(s) => ConfigValue::String(self.macros.expand(s)),
+                other => other.clone(),
+            };
+            self.values.insert(key, value);
+        }
+    }
+
+    /// Re-evaluates which options have their dependencies satisfied: every
+    /// `all` dependency must hold, and — when the option has `any`
+    /// dependencies — at least one of those as well.
+    ///
+    /// A dependency is satisfied when the target option currently holds the
+    /// required value and is itself enabled.
+    pub fn update_dependency_states(&mut self) {
+        // Iterate to a fixpoint: disabling one option may disable dependents.
+        let mut enabled: HashSet<ConfigKey> = self
+            .tree
+            .keys()
+            .filter(|&k| self.tree.node(k).as_option().is_some())
+            .collect();
+        loop {
+            let next: HashSet<ConfigKey> = enabled
+                .iter()
+                .copied()
+                .filter(|&key| {
+                    let Some(option) = self.tree.node(key).as_option() else {
+                        return false;
+                    };
+                    let satisfied = |dep: &crate::node::Dependency| match dep.resolved {
+                        Some(target) => {
+                            enabled.contains(&target)
+                                && self.values.get(&target) == Some(&dep.value)
+                        }
+                        None => false,
+                    };
+                    let in_group = |group: DependencyGroup| {
+                        option.depends_on.iter().filter(move |dep| dep.group == group)
+                    };
+                    in_group(DependencyGroup::All).all(satisfied)
+                        && (in_group(DependencyGroup::Any).next().is_none()
+                            || in_group(DependencyGroup::Any).any(satisfied))
+                })
+                .collect();
+            if next == enabled {
+                break;
+            }
+            enabled = next;
+        }
+        self.enabled = enabled;
+    }
+
+    /// Whether `key`'s dependencies are all satisfied.
+    pub fn is_enabled(&self, key: ConfigKey) -> bool {
+        self.enabled.contains(&key)
+    }
+
+    /// Whether `dep` of option `key` is currently satisfied.
+    pub fn dependency_satisfied(&self, dep: &crate::node::Dependency) -> bool {
+        match dep.resolved {
+            Some(target) => {
+                self.enabled.contains(&target) && self.values.get(&target) == Some(&dep.value)
+            }
+            None => false,
+        }
+    }
+
+    /// Sets an option's value after validating it against the option's type.
+    pub fn set_value(&mut self, key: ConfigKey, value: ConfigValue) -> Result<(), Report> {
+        let Some(option) = self.tree.node(key).as_option() else {
+            return Err(Report::error("not an option"));
+        };
+        option
+            .ty
+            .validate(&value)
+            .map_err(|msg| {
+                Report::error(format!(
+                    "{} (defined in {}): {msg}",
+                    self.tree.full_key(key),
+                    self.tree.source(key).display(),
+                ))
+            })?;
+        if self.values.get(&key) != Some(&value) {
+            self.changed.insert(key);
+        }
+        self.values.insert(key, value);
+        self.update_dependency_states();
+        Ok(())
+    }
+
+    /// Forgets change tracking, treating the current values as the baseline.
+    /// Called after loading from disk and after a successful save.
+    pub fn mark_clean(&mut self) {
+        self.changed.clear();
+    }
+
+    /// Whether any option differs from the baseline loaded from disk. The
+    /// quit path uses this to decide whether a confirmation is warranted.
+    pub fn is_dirty(&self) -> bool {
+        !self.changed.is_empty()
+    }
+
+    /// Options changed since the last [`Self::mark_clean`].
+    pub fn changed_keys(&self) -> impl Iterator<Item = ConfigKey> + '_ {
+        self.changed.iter().copied()
+    }
+
+    /// The strongest rebuild requirement across all changed options, so the
+    /// save path can tell the user whether a full rebuild is needed.
+    pub fn rebuild_requirement(&self) -> RebuildKind {
+        self.changed
+            .iter()
+            .filter_map(|key| self.tree.node(*key).as_option())
+            .map(|option| option.rebuild)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The environment variable name an option serializes to.
+    pub fn env_key(&self, key: ConfigKey) -> String {
+        format!(
+            "OSIRIS_{}",
+            self.tree
+                .full_key(key)
+                .to_uppercase()
+                .replace('.', "_")
+        )
+    }
+
+    /// Finds the option whose env name is `env_key`.
+    pub fn option_by_env_key(&self, env_key: &str) -> Option<ConfigKey> {
+        self.tree
+            .keys()
+            .filter(|&k| self.tree.node(k).as_option().is_some())
+            .find(|&k| self.env_key(k) == env_key)
+    }
+
+    /// The enabled, storable options as `(env key, value)` pairs — what both
+    /// [`Self::serialize_into`] and [`Self::to_env_file`] write out.
+    fn storable_entries(&self) -> Vec<(String, &ConfigValue)> {
+        self.values
+            .iter()
+            .filter(|(&key, _)| {
+                !self.tree.node(key).has_attribute(Attribute::NoStore) && self.is_enabled(key)
+            })
+            .map(|(&key, value)| {
+                // A process-environment override is for this run only: the
+                // shadowed value is what belongs on disk.
+                (self.env_key(key), self.env_overrides.get(&key).unwrap_or(value))
+            })
+            .collect()
+    }
+
+    /// Writes the enabled, storable options into the `[env]` table of `doc`.
+    pub fn serialize_into(&self, doc: &mut DocumentMut) {
+        let table = doc["env"].or_insert(Item::Table(toml_edit::Table::new()));
+        if let Some(table) = table.as_table_mut() {
+            let entries = self.storable_entries();
+
+            // Drop only the keys that actually go away: clearing the table
+            // outright would throw away the comments on everything else too.
+            table.retain(|key, _| entries.iter().any(|(k, _)| k == key));
+            for (env_key, value) in &entries {
+                let rendered = value.to_string();
+                match table.get_mut(env_key) {
+                    // Rewrite a surviving key only when its value changed, so
+                    // the decor (comments) around an untouched entry stays
+                    // byte-for-byte intact.
+                    Some(existing) => {
+                        if existing.as_str() != Some(rendered.as_str()) {
+                            *existing = toml_edit::value(rendered);
+                        }
+                    }
+                    None => {
+                        table.insert(env_key, toml_edit::value(rendered));
+                    }
+                }
+            }
+            // Sorted by env key so the file is stable across saves: `values`
+            // is a HashMap, and iteration-order output churns every diff.
+            // Sorting moves each key together with its decor.
+            table.sort_values();
+        }
+    }
+
+    /// Renders the `[env]` table the current state would write, into a
+    /// scratch document. Backs the UI's save preview, so it reflects the
+    /// in-memory state — disabled and `NoStore` exclusions included —
+    /// without touching any file.
+    pub fn preview_env(&self) -> String {
+        let mut doc = DocumentMut::new();
+        self.serialize_into(&mut doc);
+        doc.to_string()
+    }
+
+    /// Renders the same entries [`Self::serialize_into`] writes as a plain
+    /// `.env` file: one shell-escaped `OSIRIS_KEY=value` line per enabled,
+    /// storable option, sorted by key. For build steps that source an env
+    /// file instead of reading the cargo config.
+    pub fn to_env_file(&self) -> String {
+        let mut entries = self.storable_entries();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut out = String::new();
+        for (env_key, value) in entries {
+            out.push_str(&env_key);
+            out.push('=');
+            out.push_str(&shell_escape(&value.to_string()));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Applies per-option overrides from the *process* environment: a set
+    /// `OSIRIS_<KEY>` variable replaces the loaded value for this run only,
+    /// without dirtying the state — [`Self::serialize_into`] keeps writing
+    /// the shadowed value. Returns warnings for overrides failing validation,
+    /// which are ignored.
+    pub fn apply_env_overrides(
+        &mut self,
+        vars: impl Iterator<Item = (String, String)>,
+    ) -> Vec<Report> {
+        let by_env_key: HashMap<String, ConfigKey> = self
+            .tree
+            .keys()
+            .filter(|&k| self.tree.node(k).as_option().is_some())
+            .map(|k| (self.env_key(k), k))
+            .collect();
+
+        let mut reports = Vec::new();
+        for (name, raw) in vars {
+            let Some(&key) = by_env_key.get(&name) else {
+                continue;
+            };
+            let option = self.tree.node(key).as_option().expect("options only");
+            let value = parse_env_value(&raw, &option.ty)
+                .and_then(|value| option.ty.validate(&value).map(|()| value));
+            match value {
+                Ok(value) => {
+                    let shadowed = self
+                        .values
+                        .insert(key, value)
+                        .unwrap_or_else(|| option.default.clone());
+                    self.env_overrides.entry(key).or_insert(shadowed);
+                }
+                Err(msg) => reports.push(Report::warning(format!(
+                    "ignoring environment override {name}: {msg}"
+                ))),
+            }
+        }
+        self.update_dependency_states();
+        reports
+    }
+
+    /// Sets the option at a full dotted `path` from its string representation,
+    /// for non-interactive edits. Rejects unknown paths, non-options,
+    /// currently disabled options and (via [`Self::set_value`]) values
+    /// violating the option's type or range.
+    pub fn set_by_path(&mut self, path: &str, raw: &str) -> Result<(), Report> {
+        let key = self.key_by_path(path)?;
+        if !self.is_enabled(key) {
+            return Err(Report::error(format!(
+                "option '{path}' is disabled by unmet dependencies"
+            )));
+        }
+        let option = self.tree.node(key).as_option().expect("checked above");
+        let value = parse_env_value(raw, &option.ty).map_err(Report::error)?;
+        self.set_value(key, value)
+    }
+
+    /// Reads the option at a full dotted `path`, for non-interactive queries.
+    pub fn get_by_path(&self, path: &str) -> Result<&ConfigValue, Report> {
+        let key = self.key_by_path(path)?;
+        self.values
+            .get(&key)
+            .ok_or_else(|| Report::error(format!("option '{path}' holds no value")))
+    }
+
+    fn key_by_path(&self, path: &str) -> Result<ConfigKey, Report> {
+        let Some(key) = crate::resolve::lookup(&self.tree, path) else {
+            return Err(Report::error(format!("no option at path '{path}'")));
+        };
+        if self.tree.node(key).as_option().is_none() {
+            return Err(Report::error(format!("'{path}' is a category, not an option")));
+        }
+        Ok(key)
+    }
+
+    /// Exports the current values as a flat JSON object mapping each option's
+    /// full dotted key to its value. The structured counterpart of the `[env]`
+    /// serialization, for tooling that generates configs programmatically.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut map = serde_json::Map::new();
+        let mut entries: Vec<(String, &ConfigValue)> = self
+            .values
+            .iter()
+            .map(|(&key, value)| (self.tree.full_key(key).to_string(), value))
+            .collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (full_key, value) in entries {
+            map.insert(full_key, config_value_to_json(value));
+        }
+        serde_json::Value::Object(map)
+    }
+
+    /// Applies values from a JSON document in the [`Self::to_json`] format,
+    /// with the same validation and dependency recomputation as the TOML
+    /// path: unknown keys and type mismatches are collected as errors.
+    pub fn deserialize_from_json(&mut self, path: &Path, json: &str) -> Result<(), Vec<Report>> {
+        let parsed: serde_json::Value = serde_json::from_str(json)
+            .map_err(|err| vec![Report::error(format!("{}: {err}", path.display()))])?;
+        let Some(object) = parsed.as_object() else {
+            return Err(vec![Report::error(format!(
+                "{}: expected a JSON object of full-key/value pairs",
+                path.display()
+            ))]);
+        };
+
+        let mut reports = Vec::new();
+        for (full_key, value) in object {
+            let Some(key) = crate::resolve::lookup(&self.tree, full_key) else {
+                reports.push(Report::error(format!(
+                    "couldn't find option for '{full_key}'"
+                )));
+                continue;
+            };
+            let Some(value) = json_to_config_value(value) else {
+                reports.push(Report::error(format!(
+                    "'{full_key}': unsupported JSON value {value}"
+                )));
+                continue;
+            };
+            if let Err(report) = self.set_value(key, value) {
+                reports.push(report);
+            }
+        }
+
+        self.update_dependency_states();
+        if reports.is_empty() {
+            self.mark_clean();
+            Ok(())
+        } else {
+            Err(reports)
+        }
+    }
+
+    /// Applies values from the `[env]` table of an existing config document,
+    /// validating each against its option's type.
+    pub fn deserialize_from(&mut self, path: &Path, content: &str) -> Result<(), Vec<Report>> {
+        let doc: toml_edit::ImDocument<&str> = toml_edit::ImDocument::parse(content)
+            .map_err(|err: toml_edit::TomlError| vec![Report::error(format!("{err}"))])?;
+        let Some(table) = doc.get("env").and_then(Item::as_table) else {
+            return Ok(());
+        };
+
+        let mut reports = Vec::new();
+        for (env_key, item) in table.iter() {
+            if !env_key.starts_with("OSIRIS_") {
+                continue;
+            }
+            let key_span = table.key(env_key).and_then(|k| k.span());
+            let Some(key) = self.option_by_env_key(env_key) else {
+                // Point at the key itself, not its value: that's the part
+                // that's wrong.
+                reports.push(spanned_report(
+                    path,
+                    key_span.or_else(|| item.span()),
+                    format!("couldn't find option for '{env_key}'"),
+                ));
+                continue;
+            };
+            let raw = item.as_str().unwrap_or_default();
+            let ty = self
+                .tree
+                .node(key)
+                .as_option()
+                .expect("checked above")
+                .ty
+                .clone();
+            match parse_env_value(raw, &ty) {
+                Ok(value) => {
+                    if let Err(report) = self.set_value(key, value) {
+                        // A value left behind by a type change points at the
+                        // key — the value was right for the option as it used
+                        // to be declared. Other validation failures underline
+                        // the value itself.
+                        reports.push(match stale_type_message(env_key, raw, &ty) {
+                            Some(msg) => {
+                                spanned_report(path, key_span.or_else(|| item.span()), msg)
+                            }
+                            None => match item.span() {
+                                Some(span) => Report::from_spanned(path, span, report.message),
+                                None => report,
+                            },
+                        });
+                    }
+                }
+                Err(msg) => {
+                    reports.push(match stale_type_message(env_key, raw, &ty) {
+                        Some(msg) => spanned_report(path, key_span.or_else(|| item.span()), msg),
+                        None => spanned_report(path, item.span(), msg),
+                    });
+                }
+            }
+        }
+
+        self.update_dependency_states();
+        if reports.is_empty() {
+            // Values read from disk are the baseline, not user edits.
+            self.mark_clean();
+            Ok(())
+        } else {
+            Err(reports)
+        }
+    }
+}
+
+/// Maps a [`ConfigValue`] to its JSON representation.
+pub fn config_value_to_json(value: &ConfigValue) -> serde_json::Value {
+    match value {
+        ConfigValue::Bool(v) => serde_json::Value::Bool(*v),
+        ConfigValue::Int(v) => serde_json::Value::Number((*v).into()),
+        ConfigValue::String(v) => serde_json::Value::String(v.clone()),
+        ConfigValue::List(v) => serde_json::Value::Array(
+            v.iter()
+                .map(|s| serde_json::Value::String(s.clone()))
+                .collect(),
+        ),
+    }
+}
+
+/// Maps a JSON value back to a [`ConfigValue`]; `None` for shapes no option
+/// type can hold (floats, nested objects, mixed arrays).
+fn json_to_config_value(value: &serde_json::Value) -> Option<ConfigValue> {
+    match value {
+        serde_json::Value::Bool(v) => Some(ConfigValue::Bool(*v)),
+        serde_json::Value::Number(v) => v.as_i64().map(ConfigValue::Int),
+        serde_json::Value::String(v) => Some(ConfigValue::String(v.clone())),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect::<Option<Vec<String>>>()
+            .map(ConfigValue::List),
+        _ => None,
+    }
+}
+
+/// Builds a report pointing at `span` when one is known, plain otherwise.
+fn spanned_report(path: &Path, span: Option<std::ops::Range<usize>>, message: String) -> Report {
+    match span {
+        Some(span) => Report::from_spanned(path, span, message),
+        None => Report::error(message),
+    }
+}
+
+/// Detects a stored value left behind by a type change of its option: the
+/// string is a well-formed value of some *other* kind while failing the
+/// declared type. Returns the specific diagnostic so the user sees "type
+/// changed" instead of a generic parse or constraint error.
+fn stale_type_message(env_key: &str, raw: &str, ty: &ConfigType) -> Option<String> {
+    let stored_kind = if raw.parse::<bool>().is_ok() {
+        "bool"
+    } else if raw.parse::<i64>().is_ok() {
+        "integer"
+    } else {
+        // Free-form strings are no evidence of an old type: a typo'd enum
+        // value looks the same.
+        return None;
+    };
+    if stored_kind == type_name(ty) {
+        // Same kind, so this is a plain range/constraint violation.
+        return None;
+    }
+    Some(format!(
+        "{env_key}: option type changed: stored value '{raw}' ({stored_kind}) \
+         is no longer valid for type {}",
+        type_name(ty)
+    ))
+}
+
+/// The short name of an option type, for diagnostics.
+fn type_name(ty: &ConfigType) -> &'static str {
+    match ty {
+        ConfigType::Bool => "bool",
+        ConfigType::Integer { .. } => "integer",
+        ConfigType::String { .. } => "string",
+        ConfigType::List => "list",
+        ConfigType::Flags { .. } => "flags",
+    }
+}
+
+/// Quotes a value for a `.env` line. Values made only of characters no shell
+/// interprets stay bare; anything else is single-quoted, with embedded quotes
+/// escaped the POSIX way (`'` becomes `'\''`).
+fn shell_escape(value: &str) -> String {
+    let bare = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ',' | ':'));
+    if bare {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// Parses an env-table string back into a typed value.
+pub fn parse_env_value(raw: &str, ty: &ConfigType) -> Result<ConfigValue, String> {
+    match ty {
+        ConfigType::Bool => raw
+            .parse::<bool>()
+            .map(ConfigValue::Bool)
+            .map_err(|_| format!("'{raw}' is not a boolean")),
+        // Byte-sized integers additionally accept `KiB`/`MiB`/`GiB` input;
+        // the stored value is always the raw integer.
+        ConfigType::Integer { unit, .. } if unit.as_deref() == Some("bytes") => {
+            crate::node::parse_byte_value(raw).map(ConfigValue::Int)
+        }
+        // `0x`/`0b` prefixed input is accepted (hex/binary-display options
+        // are edited in their base); the stored value is the raw integer.
+        ConfigType::Integer { .. } => crate::node::parse_int_value(raw).map(ConfigValue::Int),
+        ConfigType::String { .. } => Ok(ConfigValue::String(raw.to_string())),
+        // Flags serialize exactly like a list: the selected names, joined by
+        // commas. Membership is checked by `validate` on assignment.
+        ConfigType::List | ConfigType::Flags { .. } => Ok(ConfigValue::List(
+            raw.split(',')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::ConfigNode;
+    use crate::testutil::{bool_option, int_option, tree_of};
+
+    #[test]
+    fn env_file_mirrors_the_env_table_with_shell_escaping() {
+        let mut cmdline = bool_option("cmdline", false, &[]);
+        if let ConfigNode::Option(o) = &mut cmdline {
+            o.ty = ConfigType::String {
+                allowed_values: None,
+            };
+            o.default = ConfigValue::String("it's -- on".to_string());
+        }
+        let tree = tree_of(vec![
+            bool_option("driver", true, &[]),
+            // Disabled by its dependency: must not appear in either output.
+            bool_option("feature", true, &[("driver", false)]),
+            cmdline,
+        ]);
+        let state = ConfigState::new(tree, MacroEngine::new());
+
+        // Bare values stay bare; the quoted one uses POSIX `'\''` escaping.
+        assert_eq!(
+            state.to_env_file(),
+            "OSIRIS_CMDLINE='it'\\''s -- on'\nOSIRIS_DRIVER=true\n"
+        );
+
+        // The file carries exactly the entries of the serialized env table.
+        let mut doc = DocumentMut::new();
+        state.serialize_into(&mut doc);
+        let table = doc["env"].as_table().unwrap();
+        assert_eq!(table.len(), 2);
+        assert!(table.get("OSIRIS_FEATURE").is_none());
+        assert_eq!(table.get("OSIRIS_CMDLINE").unwrap().as_str(), Some("it's -- on"));
+        assert_eq!(table.get("OSIRIS_DRIVER").unwrap().as_str(), Some("true"));
+    }
+
+    #[test]
+    fn bogus_target_triple_is_rejected_with_valid_options() {
+        assert!(validate_target_triple("thumbv7em-none-eabihf").is_ok());
+
+        let err = validate_target_triple("thumbv7em-none-eabih").unwrap_err();
+        assert!(err.message.contains("thumbv7em-none-eabih"));
+        // The error must list the supported triples so the fix is obvious.
+        assert!(err.message.contains("thumbv7em-none-eabihf"));
+        assert!(err.message.contains("x86_64-unknown-linux-gnu"));
+    }
+
+    #[test]
+    fn any_group_enables_on_either_dependency() {
+        let mut console = bool_option("console", false, &[]);
+        if let ConfigNode::Option(o) = &mut console {
+            for driver in ["driver_a", "driver_b"] {
+                o.depends_on.push(crate::node::Dependency {
+                    raw_key: driver.to_string(),
+                    resolved: None,
+                    value: ConfigValue::Bool(true),
+                    group: DependencyGroup::Any,
+                });
+            }
+        }
+        let tree = tree_of(vec![
+            bool_option("driver_a", false, &[]),
+            bool_option("driver_b", false, &[]),
+            console,
+        ]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+        let a = crate::resolve::lookup(&state.tree, "driver_a").unwrap();
+        let b = crate::resolve::lookup(&state.tree, "driver_b").unwrap();
+        let console = crate::resolve::lookup(&state.tree, "console").unwrap();
+
+        // Neither driver on: disabled.
+        assert!(!state.is_enabled(console));
+
+        // Either one alone satisfies the any-group.
+        state.set_value(a, ConfigValue::Bool(true)).unwrap();
+        assert!(state.is_enabled(console));
+
+        state.set_value(a, ConfigValue::Bool(false)).unwrap();
+        state.set_value(b, ConfigValue::Bool(true)).unwrap();
+        assert!(state.is_enabled(console));
+    }
+
+    #[test]
+    fn per_target_default_resolves_against_the_build_target() {
+        let nodes = || {
+            let mut option = int_option("stack_size", 256, 0, 65536);
+            if let ConfigNode::Option(o) = &mut option {
+                o.target_defaults = vec![(
+                    "thumbv7em-none-eabihf".to_string(),
+                    ConfigValue::Int(1024),
+                )];
+            }
+            vec![option]
+        };
+
+        // An exact target match wins over the `*` base default...
+        let state = ConfigState::new(
+            tree_of(nodes()),
+            MacroEngine::new().with_target_triple("thumbv7em-none-eabihf"),
+        );
+        let key = crate::resolve::lookup(&state.tree, "stack_size").unwrap();
+        assert_eq!(state.values[&key], ConfigValue::Int(1024));
+
+        // ...any other target (and no target at all) falls back to it.
+        let state = ConfigState::new(
+            tree_of(nodes()),
+            MacroEngine::new().with_target_triple("x86_64-unknown-linux-gnu"),
+        );
+        let key = crate::resolve::lookup(&state.tree, "stack_size").unwrap();
+        assert_eq!(state.values[&key], ConfigValue::Int(256));
+
+        let state = ConfigState::new(tree_of(nodes()), MacroEngine::new());
+        let key = crate::resolve::lookup(&state.tree, "stack_size").unwrap();
+        assert_eq!(state.values[&key], ConfigValue::Int(256));
+    }
+
+    #[test]
+    fn rebuild_requirement_aggregates_strongest() {
+        let mut full = int_option("heap_size", 4096, 0, 65536);
+        if let ConfigNode::Option(o) = &mut full {
+            o.rebuild = RebuildKind::Full;
+        }
+        let tree = tree_of(vec![bool_option("verbose", false, &[]), full]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+        let verbose = crate::resolve::lookup(&state.tree, "verbose").unwrap();
+        let heap = crate::resolve::lookup(&state.tree, "heap_size").unwrap();
+
+        assert_eq!(state.rebuild_requirement(), RebuildKind::Incremental);
+
+        state.set_value(verbose, ConfigValue::Bool(true)).unwrap();
+        assert_eq!(state.rebuild_requirement(), RebuildKind::Incremental);
+
+        state.set_value(heap, ConfigValue::Int(8192)).unwrap();
+        assert_eq!(state.rebuild_requirement(), RebuildKind::Full);
+
+        state.mark_clean();
+        assert_eq!(state.rebuild_requirement(), RebuildKind::Incremental);
+    }
+
+    #[test]
+    fn default_violating_dependencies_warns() {
+        let tree = tree_of(vec![
+            bool_option("driver", false, &[]),
+            bool_option("feature", true, &[("driver", true)]),
+        ]);
+        let state = ConfigState::new(tree, MacroEngine::new());
+
+        assert_eq!(state.warnings.len(), 1);
+        assert!(state.warnings[0].message.contains("feature"));
+
+        // With the dependency satisfied by default there is nothing to warn
+        // about.
+        let tree = tree_of(vec![
+            bool_option("driver", true, &[]),
+            bool_option("feature", true, &[("driver", true)]),
+        ]);
+        let state = ConfigState::new(tree, MacroEngine::new());
+        assert!(state.warnings.is_empty());
+    }
+
+    #[test]
+    fn process_env_override_applies_without_persisting() {
+        let tree = tree_of(vec![bool_option("driver", true, &[])]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+        let driver = crate::resolve::lookup(&state.tree, "driver").unwrap();
+
+        let reports = state
+            .apply_env_overrides([("OSIRIS_DRIVER".to_string(), "false".to_string())].into_iter());
+        assert!(reports.is_empty());
+        assert_eq!(state.values[&driver], ConfigValue::Bool(false));
+        // An override is not an edit: the state stays clean.
+        assert_eq!(state.changed_keys().count(), 0);
+
+        // Serialization keeps the persisted value, not the override.
+        let mut doc = DocumentMut::new();
+        state.serialize_into(&mut doc);
+        assert!(doc.to_string().contains("OSIRIS_DRIVER = \"true\""));
+
+        // An invalid override is ignored with a warning.
+        let reports = state
+            .apply_env_overrides([("OSIRIS_DRIVER".to_string(), "lots".to_string())].into_iter());
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("ignoring environment override"));
+        assert_eq!(state.values[&driver], ConfigValue::Bool(false));
+    }
+
+    #[test]
+    fn serialized_env_keys_come_out_sorted() {
+        let tree = tree_of(vec![
+            bool_option("zeta", true, &[]),
+            int_option("middle", 4, 1, 8),
+            bool_option("alpha", false, &[]),
+        ]);
+        let state = ConfigState::new(tree, MacroEngine::new());
+
+        let mut doc = DocumentMut::new();
+        state.serialize_into(&mut doc);
+        let keys: Vec<&str> = doc["env"]
+            .as_table()
+            .unwrap()
+            .iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, ["OSIRIS_ALPHA", "OSIRIS_MIDDLE", "OSIRIS_ZETA"]);
+    }
+
+    #[test]
+    fn comments_on_retained_keys_survive_a_save() {
+        let tree = tree_of(vec![
+            bool_option("driver", true, &[]),
+            bool_option("feature", true, &[("driver", true)]),
+        ]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+        let content = "[env]\n\
+                       # keep the driver on\n\
+                       OSIRIS_DRIVER = \"true\"\n\
+                       # gated on the driver\n\
+                       OSIRIS_FEATURE = \"true\"\n";
+        state
+            .deserialize_from(Path::new("config.toml"), content)
+            .unwrap();
+
+        // Disabling the driver drops the dependent key from the env table.
+        let driver = crate::resolve::lookup(&state.tree, "driver").unwrap();
+        state.set_value(driver, ConfigValue::Bool(false)).unwrap();
+
+        let mut doc: DocumentMut = content.parse().unwrap();
+        state.serialize_into(&mut doc);
+        let out = doc.to_string();
+        // The surviving key keeps its comment; the removed key takes its
+        // comment with it.
+        assert!(out.contains("# keep the driver on"));
+        assert!(out.contains("OSIRIS_DRIVER = \"false\""));
+        assert!(!out.contains("OSIRIS_FEATURE"));
+        assert!(!out.contains("# gated on the driver"));
+    }
+
+    #[test]
+    fn set_by_path_updates_and_rejects() {
+        let tree = tree_of(vec![
+            int_option("slots", 4, 1, 8),
+            bool_option("driver", false, &[]),
+            bool_option("feature", false, &[("driver", true)]),
+        ]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+
+        // Successful set.
+        state.set_by_path("slots", "6").unwrap();
+        assert_eq!(state.get_by_path("slots").unwrap(), &ConfigValue::Int(6));
+
+        // Out of range.
+        let err = state.set_by_path("slots", "9").unwrap_err();
+        assert!(err.message.contains("out of range"));
+
+        // Unknown path.
+        let err = state.set_by_path("kernel.typo", "1").unwrap_err();
+        assert!(err.message.contains("no option at path"));
+
+        // Disabled by dependencies.
+        let err = state.set_by_path("feature", "true").unwrap_err();
+        assert!(err.message.contains("disabled"));
+    }
+
+    #[test]
+    fn prefixed_integer_input_is_accepted() {
+        let ty = ConfigType::Integer {
+            min: 0,
+            max: 255,
+            unit: None,
+        };
+        assert_eq!(parse_env_value("0x1f", &ty), Ok(ConfigValue::Int(31)));
+        assert_eq!(parse_env_value("0b101", &ty), Ok(ConfigValue::Int(5)));
+        assert_eq!(parse_env_value("31", &ty), Ok(ConfigValue::Int(31)));
+        assert!(parse_env_value("0xzz", &ty).is_err());
+    }
+
+    #[test]
+    fn json_roundtrip_yields_an_equivalent_state() {
+        let nodes = || {
+            vec![
+                bool_option("driver", false, &[]),
+                int_option("slots", 4, 1, 8),
+            ]
+        };
+        let mut state = ConfigState::new(tree_of(nodes()), MacroEngine::new());
+        let driver = crate::resolve::lookup(&state.tree, "driver").unwrap();
+        let slots = crate::resolve::lookup(&state.tree, "slots").unwrap();
+        state.set_value(driver, ConfigValue::Bool(true)).unwrap();
+        state.set_value(slots, ConfigValue::Int(7)).unwrap();
+
+        let json = state.to_json().to_string();
+        let mut restored = ConfigState::new(tree_of(nodes()), MacroEngine::new());
+        restored
+            .deserialize_from_json(Path::new("config.json"), &json)
+            .unwrap();
+
+        assert_eq!(restored.values, state.values);
+        // Imported values are the baseline, not pending edits.
+        assert_eq!(restored.changed_keys().count(), 0);
+    }
+
+    #[test]
+    fn json_unknown_key_and_type_mismatch_error() {
+        let tree = tree_of(vec![int_option("slots", 4, 1, 8)]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+
+        let reports = state
+            .deserialize_from_json(Path::new("config.json"), r#"{"typo": 1, "slots": true}"#)
+            .unwrap_err();
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().any(|r| r.message.contains("typo")));
+        assert!(reports.iter().any(|r| r.message.contains("does not match")));
+    }
+
+    #[test]
+    fn unknown_env_key_error_spans_the_key() {
+        let tree = tree_of(vec![bool_option("driver", true, &[])]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+
+        let content = "[env]\nOSIRIS_DRIVER = \"true\"\nOSIRIS_TYPO = \"true\"\n";
+        let reports = state
+            .deserialize_from(Path::new("config.toml"), content)
+            .unwrap_err();
+
+        assert_eq!(reports.len(), 1);
+        let span = reports[0].span.clone().expect("span");
+        assert_eq!(&content[span], "OSIRIS_TYPO");
+    }
+
+    #[test]
+    fn nostore_default_outside_allowed_values_errors_at_load() {
+        // A NoStore option never reaches the serialize/deserialize
+        // validation, so its bad default must be caught when the state is
+        // built.
+        let tree = tree_of(vec![ConfigNode::Option(crate::node::ConfigOption {
+            key: "console".to_string(),
+            name: "console".to_string(),
+            description: "test option console".to_string(),
+            ty: ConfigType::String {
+                allowed_values: Some(vec!["lpuart1".to_string(), "usb".to_string()]),
+            },
+            default: ConfigValue::String("swd".to_string()),
+            target_defaults: Vec::new(),
+            depends_on: Vec::new(),
+            attributes: vec![Attribute::NoStore],
+            rebuild: RebuildKind::default(),
+            display: crate::node::DisplayBase::default(),
+            parent: None,
+        })]);
+        let state = ConfigState::new(tree, MacroEngine::new());
+
+        let error = state
+            .warnings
+            .iter()
+            .find(|r| r.severity == crate::report::Severity::Error)
+            .expect("the invalid default must be reported");
+        assert!(error.message.contains("'console'"));
+        assert!(error.message.contains("invalid default"));
+        assert!(error.message.contains("swd"));
+    }
+
+    #[test]
+    fn stale_typed_value_reports_a_type_change() {
+        // `console` used to be an integer; an old config still stores its
+        // numeric value while the declaration is now an enumerated string.
+        let tree = tree_of(vec![ConfigNode::Option(crate::node::ConfigOption {
+            key: "console".to_string(),
+            name: "console".to_string(),
+            description: "test option console".to_string(),
+            ty: ConfigType::String {
+                allowed_values: Some(vec!["lpuart1".to_string(), "usb".to_string()]),
+            },
+            default: ConfigValue::String("lpuart1".to_string()),
+            target_defaults: Vec::new(),
+            depends_on: Vec::new(),
+            attributes: Vec::new(),
+            rebuild: RebuildKind::default(),
+            display: crate::node::DisplayBase::default(),
+            parent: None,
+        })]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+
+        let content = "[env]\nOSIRIS_CONSOLE = \"115200\"\n";
+        let reports = state
+            .deserialize_from(Path::new("config.toml"), content)
+            .unwrap_err();
+
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("option type changed"));
+        assert!(reports[0].message.contains("'115200'"));
+        assert!(reports[0].message.contains("no longer valid for type string"));
+        // The diagnostic points at the env key, not the (formerly fine)
+        // value.
+        let span = reports[0].span.clone().expect("span");
+        assert_eq!(&content[span], "OSIRIS_CONSOLE");
+
+        // The parse-failure path gets the same treatment: a boolean left in
+        // an option that is now an integer.
+        let tree = tree_of(vec![int_option("slots", 4, 1, 8)]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+        let reports = state
+            .deserialize_from(Path::new("config.toml"), "[env]\nOSIRIS_SLOTS = \"true\"\n")
+            .unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("option type changed"));
+        assert!(reports[0].message.contains("(bool)"));
+    }
+
+    #[test]
+    fn bad_env_value_error_spans_the_value() {
+        let tree = tree_of(vec![int_option("slots", 4, 1, 8)]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+
+        let content = "[env]\nOSIRIS_SLOTS = \"lots\"\n";
+        let reports = state
+            .deserialize_from(Path::new("config.toml"), content)
+            .unwrap_err();
+
+        assert_eq!(reports.len(), 1);
+        let span = reports[0].span.clone().expect("span");
+        assert_eq!(&content[span], "\"lots\"");
+    }
+}
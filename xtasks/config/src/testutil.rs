@@ -0,0 +1,102 @@
+//! Shared helpers for constructing trees in unit tests.
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use crate::node::{
+    ConfigNode, ConfigOption, ConfigTree, ConfigType, ConfigValue, Dependency, DependencyGroup,
+    DisplayBase, RebuildKind,
+};
+
+/// A boolean option with dependencies on sibling options by key.
+pub fn bool_option(key: &str, default: bool, deps: &[(&str, bool)]) -> ConfigNode {
+    ConfigNode::Option(ConfigOption {
+        key: key.to_string(),
+        name: key.to_string(),
+        description: format!("test option {key}"),
+        ty: ConfigType::Bool,
+        default: ConfigValue::Bool(default),
+        target_defaults: Vec::new(),
+        depends_on: deps
+            .iter()
+            .map(|(dep, value)| Dependency {
+                raw_key: dep.to_string(),
+                resolved: None,
+                value: ConfigValue::Bool(*value),
+                group: DependencyGroup::default(),
+            })
+            .collect(),
+        attributes: Vec::new(),
+        rebuild: RebuildKind::default(),
+        display: DisplayBase::default(),
+        parent: None,
+    })
+}
+
+/// An integer option with a range constraint.
+pub fn int_option(key: &str, default: i64, min: i64, max: i64) -> ConfigNode {
+    ConfigNode::Option(ConfigOption {
+        key: key.to_string(),
+        name: key.to_string(),
+        description: format!("test option {key}"),
+        ty: ConfigType::Integer {
+            min,
+            max,
+            unit: None,
+        },
+        default: ConfigValue::Int(default),
+        target_defaults: Vec::new(),
+        depends_on: Vec::new(),
+        attributes: Vec::new(),
+        rebuild: RebuildKind::default(),
+        display: DisplayBase::default(),
+        parent: None,
+    })
+}
+
+/// A free-form list option.
+pub fn list_option(key: &str, default: &[&str]) -> ConfigNode {
+    ConfigNode::Option(ConfigOption {
+        key: key.to_string(),
+        name: key.to_string(),
+        description: format!("test option {key}"),
+        ty: ConfigType::List,
+        default: ConfigValue::List(default.iter().map(|s| s.to_string()).collect()),
+        target_defaults: Vec::new(),
+        depends_on: Vec::new(),
+        attributes: Vec::new(),
+        rebuild: RebuildKind::default(),
+        display: DisplayBase::default(),
+        parent: None,
+    })
+}
+
+/// A multi-select flags option over a fixed set of flag names.
+pub fn flags_option(key: &str, values: &[&str], default: &[&str]) -> ConfigNode {
+    ConfigNode::Option(ConfigOption {
+        key: key.to_string(),
+        name: key.to_string(),
+        description: format!("test option {key}"),
+        ty: ConfigType::Flags {
+            values: values.iter().map(|s| s.to_string()).collect(),
+        },
+        default: ConfigValue::List(default.iter().map(|s| s.to_string()).collect()),
+        target_defaults: Vec::new(),
+        depends_on: Vec::new(),
+        attributes: Vec::new(),
+        rebuild: RebuildKind::default(),
+        display: DisplayBase::default(),
+        parent: None,
+    })
+}
+
+/// Builds a tree with all `nodes` at the root and dependencies resolved.
+pub fn tree_of(nodes: Vec<ConfigNode>) -> ConfigTree {
+    let mut tree = ConfigTree::default();
+    for node in nodes {
+        let key = tree.push(node, PathBuf::from("test/options.toml"));
+        tree.root.push(key);
+    }
+    crate::resolve::resolve_paths(&mut tree).expect("test tree must resolve");
+    tree
+}
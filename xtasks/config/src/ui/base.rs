@@ -0,0 +1,728 @@
+//! The main tree-browsing UI: a navigable option list plus a details panel.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{
+    Block, Borders, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
+    ScrollbarState, Wrap,
+};
+use ratatui::Frame;
+
+use crate::node::{Attribute, ConfigKey, ConfigNode, ConfigNodelike, ConfigTree};
+use crate::state::ConfigState;
+use crate::ui::editor::EditorModal;
+use crate::ui::modal::{ExitConfirmationModal, GotoModal, GotoResult, ModalResult, PreviewModal};
+
+/// What the event loop should do after a key event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    Quit,
+}
+
+enum Modal {
+    ExitConfirmation(ExitConfirmationModal),
+    Editor(EditorModal),
+    Goto(GotoModal),
+    Preview(PreviewModal),
+}
+
+/// The base UI: a navigation stack of categories, a selectable list of the
+/// current category's children and a details panel for the selection.
+pub struct BaseUI {
+    pub state: ConfigState,
+    /// Category descent stack; empty means the root level.
+    nav: Vec<ConfigKey>,
+    selected: usize,
+    modal: Option<Modal>,
+    /// Whether `Hidden` options are shown (toggled with `a`).
+    reveal_hidden: bool,
+    /// Whether scroll keys go to the details panel (toggled with Tab).
+    details_focused: bool,
+    /// First visible line of the details content.
+    details_scroll: usize,
+    /// Inner height of the details panel at the last draw, for clamping.
+    details_viewport: usize,
+    /// Width of the details panel in percent (adjusted with `<`/`>`).
+    details_width: u16,
+}
+
+/// Default and bounds of the details panel width, in percent.
+const DETAILS_WIDTH_DEFAULT: u16 = 30;
+const DETAILS_WIDTH_RANGE: std::ops::RangeInclusive<u16> = 15..=60;
+const DETAILS_WIDTH_STEP: u16 = 5;
+
+/// Clamps a details scroll offset so the last content line stays reachable
+/// but never scrolls past the end.
+fn clamp_scroll(offset: usize, content_lines: usize, viewport_lines: usize) -> usize {
+    offset.min(content_lines.saturating_sub(viewport_lines))
+}
+
+impl BaseUI {
+    pub fn new(state: ConfigState) -> Self {
+        Self {
+            state,
+            nav: Vec::new(),
+            selected: 0,
+            modal: None,
+            reveal_hidden: false,
+            details_focused: false,
+            details_scroll: 0,
+            details_viewport: 0,
+            details_width: DETAILS_WIDTH_DEFAULT,
+        }
+    }
+
+    /// The visible children of the current navigation level. `Skip` nodes are
+    /// always filtered out; `Hidden` nodes only appear while the advanced
+    /// reveal toggle is active.
+    pub fn children_nodes(&self) -> Vec<ConfigKey> {
+        let children: Vec<ConfigKey> = match self.nav.last() {
+            Some(&cat) => self.state.tree.children(cat).to_vec(),
+            None => self.state.tree.root.clone(),
+        };
+        children
+            .into_iter()
+            .filter(|&k| {
+                let node = self.state.tree.node(k);
+                (self.reveal_hidden || !node.has_attribute(Attribute::Hidden))
+                    && !node.has_attribute(Attribute::Skip)
+            })
+            .collect()
+    }
+
+    /// The currently selected node, if any.
+    pub fn selected_node(&self) -> Option<ConfigKey> {
+        self.children_nodes().get(self.selected).copied()
+    }
+
+    pub fn handle_key_event(&mut self, event: KeyEvent) -> Action {
+        // The goto prompt navigates the base UI itself, so it is taken out of
+        // the modal slot while the key is handled and put back if it stays
+        // open (unresolved path or more typing to come).
+        if matches!(self.modal, Some(Modal::Goto(_))) {
+            let Some(Modal::Goto(mut m)) = self.modal.take() else {
+                unreachable!()
+            };
+            match m.handle_key_event(event) {
+                GotoResult::Open => self.modal = Some(Modal::Goto(m)),
+                GotoResult::Close => {}
+                GotoResult::Go(path) => {
+                    if let Err(msg) = self.navigate_to(&path) {
+                        m.error = Some(msg);
+                        self.modal = Some(Modal::Goto(m));
+                    }
+                }
+            }
+            return Action::Continue;
+        }
+
+        if let Some(modal) = &mut self.modal {
+            let result = match modal {
+                Modal::ExitConfirmation(m) => m.handle_key_event(event),
+                Modal::Editor(m) => m.handle_key_event(&mut self.state, event),
+                Modal::Goto(_) => unreachable!("handled above"),
+                Modal::Preview(m) => m.handle_key_event(event),
+            };
+            match result {
+                ModalResult::Open => {}
+                ModalResult::Close => self.modal = None,
+                ModalResult::Quit => return Action::Quit,
+            }
+            return Action::Continue;
+        }
+
+        // Keys shared by both focus targets.
+        match event.code {
+            KeyCode::Tab => {
+                self.details_focused = !self.details_focused;
+                return Action::Continue;
+            }
+            KeyCode::Char('<') => {
+                self.details_width = self
+                    .details_width
+                    .saturating_sub(DETAILS_WIDTH_STEP)
+                    .max(*DETAILS_WIDTH_RANGE.start());
+                return Action::Continue;
+            }
+            KeyCode::Char('>') => {
+                self.details_width =
+                    (self.details_width + DETAILS_WIDTH_STEP).min(*DETAILS_WIDTH_RANGE.end());
+                return Action::Continue;
+            }
+            _ => {}
+        }
+
+        if self.details_focused {
+            return self.handle_details_key_event(event);
+        }
+
+        let children = self.children_nodes();
+        match event.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                self.details_scroll = 0;
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < children.len() => {
+                self.selected += 1;
+                self.details_scroll = 0;
+            }
+            KeyCode::Enter | KeyCode::Right => {
+                if let Some(key) = self.selected_node() {
+                    match self.state.tree.node(key) {
+                        ConfigNode::Category(_) => {
+                            self.nav.push(key);
+                            self.selected = 0;
+                            self.details_scroll = 0;
+                        }
+                        ConfigNode::Option(_) => {
+                            if self.state.is_enabled(key) {
+                                self.modal =
+                                    Some(Modal::Editor(EditorModal::new(&self.state, key)));
+                            }
+                        }
+                    }
+                }
+            }
+            KeyCode::Esc | KeyCode::Left if self.nav.pop().is_some() => {
+                self.selected = 0;
+                self.details_scroll = 0;
+            }
+            KeyCode::Char('a') => {
+                self.reveal_hidden = !self.reveal_hidden;
+                self.selected = 0;
+                self.details_scroll = 0;
+            }
+            KeyCode::Char('g') => {
+                self.modal = Some(Modal::Goto(GotoModal::default()));
+            }
+            KeyCode::Char('p') => {
+                // Review exactly what a save would write before writing it.
+                self.modal = Some(Modal::Preview(PreviewModal::new(&self.state.preview_env())));
+            }
+            KeyCode::Char('q') => {
+                // Only ask when there is something to lose.
+                if !self.state.is_dirty() {
+                    return Action::Quit;
+                }
+                self.modal = Some(Modal::ExitConfirmation(ExitConfirmationModal {
+                    changed: self.state.changed_keys().count(),
+                }));
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    /// Rebuilds the nav stack to land on the node at `path`, descending its
+    /// category chain and selecting it at the final level. Hidden targets
+    /// turn on the reveal toggle so the jump is actually visible.
+    fn navigate_to(&mut self, path: &str) -> Result<(), String> {
+        let (nav, target) = path_to_nav(&self.state.tree, path)
+            .ok_or_else(|| format!("no option or category at '{path}'"))?;
+        self.nav = nav;
+        if self.state.tree.node(target).has_attribute(Attribute::Hidden) {
+            self.reveal_hidden = true;
+        }
+        self.selected = self
+            .children_nodes()
+            .iter()
+            .position(|&k| k == target)
+            .unwrap_or(0);
+        self.details_scroll = 0;
+        Ok(())
+    }
+
+    /// Scroll keys for the details panel while it holds focus.
+    fn handle_details_key_event(&mut self, event: KeyEvent) -> Action {
+        let content = match self.selected_node() {
+            Some(key) => details_text(&self.state, key).len(),
+            None => 0,
+        };
+        let offset = match event.code {
+            KeyCode::Up | KeyCode::Char('k') => self.details_scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.details_scroll + 1,
+            KeyCode::PageUp => self.details_scroll.saturating_sub(self.details_viewport),
+            KeyCode::PageDown => self.details_scroll + self.details_viewport,
+            KeyCode::Home => 0,
+            KeyCode::End => content,
+            KeyCode::Char('q') | KeyCode::Esc => {
+                // Hand focus back rather than quitting out of a scroll.
+                self.details_focused = false;
+                return Action::Continue;
+            }
+            _ => return Action::Continue,
+        };
+        self.details_scroll = clamp_scroll(offset, content, self.details_viewport);
+        Action::Continue
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(100 - self.details_width),
+                Constraint::Percentage(self.details_width),
+            ])
+            .split(frame.area());
+
+        self.draw_list_in_area(frame, chunks[0]);
+        self.draw_details_panel_in_area(frame, chunks[1]);
+
+        match &self.modal {
+            Some(Modal::ExitConfirmation(m)) => m.draw(frame, frame.area()),
+            Some(Modal::Editor(m)) => m.draw(&self.state, frame, frame.area()),
+            Some(Modal::Goto(m)) => m.draw(frame, frame.area()),
+            Some(Modal::Preview(m)) => m.draw(frame, frame.area()),
+            None => {}
+        }
+    }
+
+    fn draw_list_in_area(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .children_nodes()
+            .iter()
+            .map(|&k| self.to_list_item(k))
+            .collect();
+        let title = match self.nav.last() {
+            Some(&cat) => self.state.tree.full_key(cat).to_string(),
+            None => "osiris config".to_string(),
+        };
+        let list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut list_state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, area, &mut list_state);
+    }
+
+    /// Renders one node as a list row: categories with a trailing `/`, options
+    /// with their current value, disabled options dimmed.
+    pub fn to_list_item(&self, key: ConfigKey) -> ListItem<'static> {
+        let node = self.state.tree.node(key);
+        let badge = experimental_badge(&self.state, key);
+        match node {
+            ConfigNode::Category(c) => ListItem::new(format!("{}/{badge}", c.name)),
+            ConfigNode::Option(o) => {
+                let value = self
+                    .state
+                    .values
+                    .get(&key)
+                    .map(|v| o.display_value(v))
+                    .unwrap_or_default();
+                let item = ListItem::new(format!("{} = {}{badge}", o.name, value));
+                if node.has_attribute(Attribute::Hidden) {
+                    // Only visible via the reveal toggle; mark as advanced.
+                    item.style(
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::ITALIC),
+                    )
+                } else if self.state.is_enabled(key) {
+                    item
+                } else {
+                    item.style(Style::default().fg(Color::DarkGray))
+                }
+            }
+        }
+    }
+
+    /// Renders the details panel for the current selection, scrolled to the
+    /// current offset and with a scrollbar when the content overflows.
+    pub fn draw_details_panel_in_area(&mut self, frame: &mut Frame, area: Rect) {
+        let lines = match self.selected_node() {
+            Some(key) => details_text(&self.state, key),
+            None => Vec::new(),
+        };
+        // Remember the inner height so the key handler clamps correctly, and
+        // re-clamp now in case a resize shrank the panel under the offset.
+        self.details_viewport = area.height.saturating_sub(2) as usize;
+        self.details_scroll = clamp_scroll(self.details_scroll, lines.len(), self.details_viewport);
+
+        let block = Block::default().title("Details").borders(Borders::ALL);
+        let block = if self.details_focused {
+            block.border_style(Style::default().fg(Color::Cyan))
+        } else {
+            block
+        };
+        frame.render_widget(
+            Paragraph::new(lines.join("\n"))
+                .wrap(Wrap { trim: false })
+                .scroll((self.details_scroll as u16, 0))
+                .block(block),
+            area,
+        );
+
+        if lines.len() > self.details_viewport {
+            let mut scrollbar_state = ScrollbarState::new(lines.len() - self.details_viewport)
+                .position(self.details_scroll);
+            frame.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight),
+                area,
+                &mut scrollbar_state,
+            );
+        }
+    }
+}
+
+/// Resolves a full dotted path (with or without a leading `.`) to the nav
+/// stack landing on it: the chain of ancestor categories to descend, plus the
+/// target node to select at the final level.
+pub fn path_to_nav(tree: &ConfigTree, path: &str) -> Option<(Vec<ConfigKey>, ConfigKey)> {
+    let target = crate::resolve::lookup(tree, path.trim_start_matches('.'))?;
+    let mut nav = Vec::new();
+    let mut cursor = tree.node(target).parent();
+    while let Some(key) = cursor {
+        nav.push(key);
+        cursor = tree.node(key).parent();
+    }
+    nav.reverse();
+    Some((nav, target))
+}
+
+/// The warning badge shown next to experimental nodes (inherited from parent
+/// categories), or an empty string.
+pub fn experimental_badge(state: &ConfigState, key: ConfigKey) -> &'static str {
+    if state.tree.inherits_attribute(key, Attribute::Experimental) {
+        " [experimental]"
+    } else {
+        ""
+    }
+}
+
+/// Builds the details panel content for `key`: name, path, description and —
+/// for options — type, current value and the dependency list with a marker
+/// showing whether each dependency is currently satisfied.
+pub fn details_text(state: &ConfigState, key: ConfigKey) -> Vec<String> {
+    let node = state.tree.node(key);
+    let mut lines = vec![
+        format!("{}{}", node.name(), experimental_badge(state, key)),
+        format!("path: .{}", state.tree.full_key(key)),
+        format!("source: {}", state.tree.source(key).display()),
+        String::new(),
+        node.description().to_string(),
+    ];
+
+    if let Some(option) = node.as_option() {
+        lines.push(String::new());
+        lines.push(format!("type: {:?}", option.ty));
+        if let Some(value) = state.values.get(&key) {
+            lines.push(format!("value: {}", option.display_value(value)));
+        }
+
+        if !option.depends_on.is_empty() {
+            lines.push(String::new());
+            lines.push("Depends on:".to_string());
+            for dep in &option.depends_on {
+                let marker = if state.dependency_satisfied(dep) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                lines.push(format!("  {marker} {} = {}", dep.raw_key, dep.value));
+            }
+            if !state.is_enabled(key) {
+                lines.push("(disabled: unmet dependencies)".to_string());
+            }
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{bool_option, tree_of};
+
+    #[test]
+    fn hidden_options_appear_only_when_revealed() {
+        let mut hidden = bool_option("debug_knob", false, &[]);
+        if let ConfigNode::Option(o) = &mut hidden {
+            o.attributes.push(Attribute::Hidden);
+        }
+        let tree = tree_of(vec![bool_option("driver", true, &[]), hidden]);
+        let state = ConfigState::new(tree, crate::state::MacroEngine::new());
+        let hidden_key = crate::resolve::lookup(&state.tree, "debug_knob").unwrap();
+
+        let mut ui = BaseUI::new(state);
+        assert!(!ui.children_nodes().contains(&hidden_key));
+        ui.handle_key_event(KeyEvent::from(KeyCode::Char('a')));
+        assert!(ui.children_nodes().contains(&hidden_key));
+        ui.handle_key_event(KeyEvent::from(KeyCode::Char('a')));
+        assert!(!ui.children_nodes().contains(&hidden_key));
+    }
+
+    #[test]
+    fn quit_asks_for_confirmation_only_when_dirty() {
+        let tree = tree_of(vec![bool_option("driver", true, &[])]);
+        let state = ConfigState::new(tree, crate::state::MacroEngine::new());
+        let driver = crate::resolve::lookup(&state.tree, "driver").unwrap();
+
+        // Clean state: 'q' quits immediately.
+        let mut ui = BaseUI::new(state);
+        assert!(!ui.state.is_dirty());
+        assert_eq!(ui.handle_key_event(KeyEvent::from(KeyCode::Char('q'))), Action::Quit);
+
+        // Dirty state: 'q' opens the confirmation, 'y' quits.
+        ui.state
+            .set_value(driver, crate::node::ConfigValue::Bool(false))
+            .unwrap();
+        assert!(ui.state.is_dirty());
+        assert_eq!(
+            ui.handle_key_event(KeyEvent::from(KeyCode::Char('q'))),
+            Action::Continue
+        );
+        assert_eq!(ui.handle_key_event(KeyEvent::from(KeyCode::Char('y'))), Action::Quit);
+    }
+
+    #[test]
+    fn experimental_badge_shows_and_is_inherited() {
+        use crate::node::{ConfigCategory, ConfigTree};
+        use std::path::PathBuf;
+
+        // An experimental category with a plain option inside.
+        let mut tree = ConfigTree::default();
+        let labs = tree.push(
+            ConfigNode::Category(ConfigCategory {
+                key: "labs".to_string(),
+                name: "labs".to_string(),
+                description: String::new(),
+                attributes: vec![Attribute::Experimental],
+                parent: None,
+                children: Vec::new(),
+            }),
+            PathBuf::from("test/options.toml"),
+        );
+        tree.root.push(labs);
+        let child = tree.push(bool_option("new_scheduler", true, &[]), PathBuf::from("test/options.toml"));
+        if let ConfigNode::Option(o) = tree.node_mut(child) {
+            o.parent = Some(labs);
+        }
+        if let ConfigNode::Category(c) = tree.node_mut(labs) {
+            c.children.push(child);
+        }
+        let stable = tree.push(bool_option("driver", true, &[]), PathBuf::from("test/options.toml"));
+        tree.root.push(stable);
+        tree.cache_full_keys();
+        let state = ConfigState::new(tree, crate::state::MacroEngine::new());
+
+        assert_eq!(experimental_badge(&state, labs), " [experimental]");
+        // The badge is inherited by everything under the category...
+        assert_eq!(experimental_badge(&state, child), " [experimental]");
+        assert!(details_text(&state, child)[0].contains("[experimental]"));
+        // ...but not by unrelated stable nodes.
+        assert_eq!(experimental_badge(&state, stable), "");
+    }
+
+    #[test]
+    fn details_scrolling_is_clamped_to_the_content() {
+        let tree = tree_of(vec![bool_option("driver", true, &[])]);
+        let state = ConfigState::new(tree, crate::state::MacroEngine::new());
+        let mut ui = BaseUI::new(state);
+
+        // A panel shorter than the details content.
+        let content = details_text(&ui.state, ui.selected_node().unwrap()).len();
+        ui.details_viewport = 3;
+        assert!(content > ui.details_viewport);
+        let max = content - ui.details_viewport;
+
+        // Unfocused, Down moves the selection, not the panel.
+        ui.handle_key_event(KeyEvent::from(KeyCode::Down));
+        assert_eq!(ui.details_scroll, 0);
+
+        ui.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        ui.handle_key_event(KeyEvent::from(KeyCode::Down));
+        assert_eq!(ui.details_scroll, 1);
+        ui.handle_key_event(KeyEvent::from(KeyCode::End));
+        assert_eq!(ui.details_scroll, max);
+        ui.handle_key_event(KeyEvent::from(KeyCode::PageDown));
+        assert_eq!(ui.details_scroll, max, "must not scroll past the end");
+        ui.handle_key_event(KeyEvent::from(KeyCode::Home));
+        ui.handle_key_event(KeyEvent::from(KeyCode::Up));
+        assert_eq!(ui.details_scroll, 0, "must not scroll above the start");
+
+        // Esc hands focus back; the list works again.
+        ui.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(!ui.details_focused);
+
+        // A viewport taller than the content never scrolls at all.
+        assert_eq!(clamp_scroll(10, 4, 8), 0);
+    }
+
+    /// Builds `.drivers.uart.baud` plus a root-level option and returns the
+    /// tree with the keys of the two categories and the deep option.
+    fn deep_tree() -> (crate::node::ConfigTree, ConfigKey, ConfigKey, ConfigKey) {
+        use crate::node::{ConfigCategory, ConfigTree};
+        use std::path::PathBuf;
+
+        fn category(key: &str, parent: Option<ConfigKey>) -> ConfigNode {
+            ConfigNode::Category(ConfigCategory {
+                key: key.to_string(),
+                name: key.to_string(),
+                description: String::new(),
+                attributes: Vec::new(),
+                parent,
+                children: Vec::new(),
+            })
+        }
+
+        let mut tree = ConfigTree::default();
+        let drivers = tree.push(category("drivers", None), PathBuf::from("test/options.toml"));
+        tree.root.push(drivers);
+        let uart = tree.push(
+            category("uart", Some(drivers)),
+            PathBuf::from("test/options.toml"),
+        );
+        if let ConfigNode::Category(c) = tree.node_mut(drivers) {
+            c.children.push(uart);
+        }
+        let baud = tree.push(
+            bool_option("baud", true, &[]),
+            PathBuf::from("test/options.toml"),
+        );
+        if let ConfigNode::Option(o) = tree.node_mut(baud) {
+            o.parent = Some(uart);
+        }
+        if let ConfigNode::Category(c) = tree.node_mut(uart) {
+            c.children.push(baud);
+        }
+        let stable = tree.push(
+            bool_option("driver", true, &[]),
+            PathBuf::from("test/options.toml"),
+        );
+        tree.root.push(stable);
+        tree.cache_full_keys();
+        (tree, drivers, uart, baud)
+    }
+
+    #[test]
+    fn path_to_nav_resolves_deep_paths_and_rejects_invalid_ones() {
+        let (tree, drivers, uart, baud) = deep_tree();
+
+        // The leading dot shown in the details panel is accepted but optional.
+        let (nav, target) = path_to_nav(&tree, ".drivers.uart.baud").unwrap();
+        assert_eq!(nav, [drivers, uart]);
+        assert_eq!(target, baud);
+        assert_eq!(
+            path_to_nav(&tree, "drivers.uart.baud"),
+            Some((vec![drivers, uart], baud))
+        );
+
+        // A category resolves too, selected at its parent's level.
+        assert_eq!(path_to_nav(&tree, ".drivers.uart"), Some((vec![drivers], uart)));
+
+        assert_eq!(path_to_nav(&tree, ".drivers.uart.parity"), None);
+        assert_eq!(path_to_nav(&tree, ".bogus"), None);
+    }
+
+    #[test]
+    fn goto_key_jumps_to_a_path_and_keeps_the_prompt_open_on_error() {
+        let (tree, drivers, uart, baud) = deep_tree();
+        let state = ConfigState::new(tree, crate::state::MacroEngine::new());
+        let mut ui = BaseUI::new(state);
+
+        // An invalid path errors and does not navigate.
+        ui.handle_key_event(KeyEvent::from(KeyCode::Char('g')));
+        for c in ".bogus".chars() {
+            ui.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        ui.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert!(ui.nav.is_empty());
+        match &ui.modal {
+            Some(Modal::Goto(m)) => assert!(m.error.as_deref().unwrap().contains(".bogus")),
+            _ => panic!("goto prompt must stay open on an invalid path"),
+        }
+
+        // Retype a valid deep path: the nav stack descends the category chain
+        // and the leaf ends up selected.
+        for _ in 0..".bogus".len() {
+            ui.handle_key_event(KeyEvent::from(KeyCode::Backspace));
+        }
+        for c in ".drivers.uart.baud".chars() {
+            ui.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        ui.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert!(ui.modal.is_none());
+        assert_eq!(ui.nav, [drivers, uart]);
+        assert_eq!(ui.selected_node(), Some(baud));
+    }
+
+    #[test]
+    fn hex_display_option_renders_its_value_prefixed() {
+        use crate::node::DisplayBase;
+        use crate::testutil::int_option;
+
+        let mut option = int_option("irq_mask", 0x1f, 0, 255);
+        if let ConfigNode::Option(o) = &mut option {
+            o.display = DisplayBase::Hex;
+        }
+        let tree = tree_of(vec![option]);
+        let state = ConfigState::new(tree, crate::state::MacroEngine::new());
+        let key = crate::resolve::lookup(&state.tree, "irq_mask").unwrap();
+
+        let text = details_text(&state, key);
+        assert!(text.iter().any(|l| l == "value: 0x1f"));
+    }
+
+    #[test]
+    fn preview_shows_the_serialized_env_table() {
+        let mut secret = bool_option("secret", true, &[]);
+        if let ConfigNode::Option(o) = &mut secret {
+            o.attributes.push(Attribute::NoStore);
+        }
+        let tree = tree_of(vec![
+            bool_option("driver", true, &[]),
+            // Disabled by its dependency on driver being off.
+            bool_option("feature", true, &[("driver", false)]),
+            secret,
+        ]);
+        let state = ConfigState::new(tree, crate::state::MacroEngine::new());
+        let mut ui = BaseUI::new(state);
+
+        ui.handle_key_event(KeyEvent::from(KeyCode::Char('p')));
+        let Some(Modal::Preview(m)) = &ui.modal else {
+            panic!("'p' must open the preview");
+        };
+        let content = m.lines.join("\n");
+        // The preview is exactly the serialized table: the stored option
+        // appears, the disabled and NoStore ones do not.
+        assert_eq!(format!("{content}\n"), ui.state.preview_env());
+        assert!(content.contains("OSIRIS_DRIVER = \"true\""));
+        assert!(!content.contains("OSIRIS_FEATURE"));
+        assert!(!content.contains("OSIRIS_SECRET"));
+
+        // Esc closes it again.
+        ui.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(ui.modal.is_none());
+    }
+
+    #[test]
+    fn details_text_lists_unmet_dependency() {
+        let tree = tree_of(vec![
+            bool_option("driver", false, &[]),
+            bool_option("feature", true, &[("driver", true)]),
+        ]);
+        let state = ConfigState::new(tree, crate::state::MacroEngine::new());
+        let feature = crate::resolve::lookup(&state.tree, "feature").unwrap();
+
+        assert!(!state.is_enabled(feature));
+        let text = details_text(&state, feature);
+        assert!(text.iter().any(|l| l.contains("[ ] driver = true")));
+        assert!(text.iter().any(|l| l.contains("disabled")));
+    }
+
+    #[test]
+    fn details_text_marks_satisfied_dependency() {
+        let tree = tree_of(vec![
+            bool_option("driver", true, &[]),
+            bool_option("feature", true, &[("driver", true)]),
+        ]);
+        let state = ConfigState::new(tree, crate::state::MacroEngine::new());
+        let feature = crate::resolve::lookup(&state.tree, "feature").unwrap();
+
+        assert!(state.is_enabled(feature));
+        let text = details_text(&state, feature);
+        assert!(text.iter().any(|l| l.contains("[x] driver = true")));
+    }
+}
@@ -0,0 +1,487 @@
+//! The value editor modal for a single option.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::node::{flags_mask, ConfigKey, ConfigType, ConfigValue};
+use crate::state::{parse_env_value, ConfigState};
+use crate::ui::modal::{centered, ModalResult};
+
+/// Edits one option's value. Booleans toggle directly; flags are a checkbox
+/// list; lists get add/edit/delete keys over their entries; other types are
+/// edited as text and parsed/validated on confirm.
+#[derive(Debug)]
+pub struct EditorModal {
+    pub key: ConfigKey,
+    input: String,
+    error: Option<String>,
+    /// Cursor into the flag list, for `Flags` options.
+    flag_cursor: usize,
+    /// Working per-flag selection (aligned with the declared values), for
+    /// `Flags` options.
+    flag_selected: Vec<bool>,
+    /// Working copy of the entries, for `List` options.
+    list_entries: Vec<String>,
+    /// Cursor into the entry list, for `List` options.
+    list_cursor: usize,
+    /// An entry being typed for a `List` option: the index being edited
+    /// (`None` appends a new entry) and the text so far. `None` while
+    /// browsing the list.
+    list_input: Option<(Option<usize>, String)>,
+}
+
+impl EditorModal {
+    pub fn new(state: &ConfigState, key: ConfigKey) -> Self {
+        // Prefill in the option's display base so a hex option is edited as
+        // the `0x...` it renders as.
+        let input = match state.tree.node(key).as_option() {
+            Some(option) => state
+                .values
+                .get(&key)
+                .map(|v| option.display_value(v))
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        let flag_selected = match state.tree.node(key).as_option().map(|o| &o.ty) {
+            Some(ConfigType::Flags { values }) => {
+                let current: &[String] = match state.values.get(&key) {
+                    Some(ConfigValue::List(selected)) => selected,
+                    _ => &[],
+                };
+                values.iter().map(|flag| current.contains(flag)).collect()
+            }
+            _ => Vec::new(),
+        };
+        let list_entries = match state.tree.node(key).as_option().map(|o| &o.ty) {
+            Some(ConfigType::List) => match state.values.get(&key) {
+                Some(ConfigValue::List(entries)) => entries.clone(),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        Self {
+            key,
+            input,
+            error: None,
+            flag_cursor: 0,
+            flag_selected,
+            list_entries,
+            list_cursor: 0,
+            list_input: None,
+        }
+    }
+
+    /// Key handling for a `Flags` option: Up/Down move, Space toggles the
+    /// checkbox under the cursor, Enter commits the selection.
+    fn handle_flags_key_event(
+        &mut self,
+        state: &mut ConfigState,
+        values: &[String],
+        code: KeyCode,
+    ) -> ModalResult {
+        match code {
+            KeyCode::Esc => ModalResult::Close,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.flag_cursor = self.flag_cursor.saturating_sub(1);
+                ModalResult::Open
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if self.flag_cursor + 1 < values.len() {
+                    self.flag_cursor += 1;
+                }
+                ModalResult::Open
+            }
+            KeyCode::Char(' ') => {
+                if let Some(selected) = self.flag_selected.get_mut(self.flag_cursor) {
+                    *selected = !*selected;
+                }
+                ModalResult::Open
+            }
+            KeyCode::Enter => {
+                // Commit in declared order so the serialized list is stable.
+                let selection: Vec<String> = values
+                    .iter()
+                    .zip(&self.flag_selected)
+                    .filter(|(_, &on)| on)
+                    .map(|(flag, _)| flag.clone())
+                    .collect();
+                match state.set_value(self.key, ConfigValue::List(selection)) {
+                    Ok(()) => ModalResult::Close,
+                    Err(report) => {
+                        self.error = Some(report.message);
+                        ModalResult::Open
+                    }
+                }
+            }
+            _ => ModalResult::Open,
+        }
+    }
+
+    /// Key handling for a `List` option while browsing: Up/Down move, `a`
+    /// adds a new entry, `e` edits and `d` deletes the selected one, Enter
+    /// commits the working list. While an entry is being typed, keys go into
+    /// the entry text instead and Enter/Esc accept or abort it.
+    fn handle_list_key_event(&mut self, state: &mut ConfigState, code: KeyCode) -> ModalResult {
+        if let Some((target, text)) = &mut self.list_input {
+            match code {
+                KeyCode::Esc => {
+                    self.list_input = None;
+                    self.error = None;
+                }
+                KeyCode::Enter => {
+                    let (target, text) = (*target, text.trim().to_string());
+                    self.accept_list_entry(target, text);
+                }
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            }
+            return ModalResult::Open;
+        }
+
+        match code {
+            KeyCode::Esc => ModalResult::Close,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.list_cursor = self.list_cursor.saturating_sub(1);
+                ModalResult::Open
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if self.list_cursor + 1 < self.list_entries.len() {
+                    self.list_cursor += 1;
+                }
+                ModalResult::Open
+            }
+            KeyCode::Char('a') => {
+                self.list_input = Some((None, String::new()));
+                ModalResult::Open
+            }
+            KeyCode::Char('e') => {
+                if let Some(entry) = self.list_entries.get(self.list_cursor) {
+                    self.list_input = Some((Some(self.list_cursor), entry.clone()));
+                }
+                ModalResult::Open
+            }
+            KeyCode::Char('d') => {
+                if self.list_cursor < self.list_entries.len() {
+                    self.list_entries.remove(self.list_cursor);
+                    self.list_cursor = self
+                        .list_cursor
+                        .min(self.list_entries.len().saturating_sub(1));
+                }
+                ModalResult::Open
+            }
+            KeyCode::Enter => {
+                match state.set_value(self.key, ConfigValue::List(self.list_entries.clone())) {
+                    Ok(()) => ModalResult::Close,
+                    Err(report) => {
+                        self.error = Some(report.message);
+                        ModalResult::Open
+                    }
+                }
+            }
+            _ => ModalResult::Open,
+        }
+    }
+
+    /// Applies a typed entry to the working list, enforcing inline that the
+    /// entry is non-empty and unique. On rejection the input stays open with
+    /// the reason shown, so the text can be fixed instead of retyped.
+    fn accept_list_entry(&mut self, target: Option<usize>, text: String) {
+        if text.is_empty() {
+            self.error = Some("entry must not be empty".to_string());
+            return;
+        }
+        let duplicate = self
+            .list_entries
+            .iter()
+            .enumerate()
+            .any(|(i, entry)| *entry == text && Some(i) != target);
+        if duplicate {
+            self.error = Some(format!("'{text}' is already in the list"));
+            return;
+        }
+        match target {
+            Some(index) => self.list_entries[index] = text,
+            None => {
+                self.list_entries.push(text);
+                self.list_cursor = self.list_entries.len() - 1;
+            }
+        }
+        self.list_input = None;
+        self.error = None;
+    }
+
+    pub fn handle_key_event(&mut self, state: &mut ConfigState, event: KeyEvent) -> ModalResult {
+        let Some(option) = state.tree.node(self.key).as_option() else {
+            return ModalResult::Close;
+        };
+        let ty = option.ty.clone();
+        if let ConfigType::Flags { values } = &ty {
+            return self.handle_flags_key_event(state, values, event.code);
+        }
+        if matches!(ty, ConfigType::List) {
+            return self.handle_list_key_event(state, event.code);
+        }
+
+        match event.code {
+            KeyCode::Esc => ModalResult::Close,
+            KeyCode::Enter => {
+                if matches!(ty, ConfigType::Bool) {
+                    let current = matches!(state.values.get(&self.key), Some(ConfigValue::Bool(true)));
+                    let _ = state.set_value(self.key, ConfigValue::Bool(!current));
+                    return ModalResult::Close;
+                }
+                match parse_env_value(&self.input, &ty) {
+                    Ok(value) => match state.set_value(self.key, value) {
+                        Ok(()) => ModalResult::Close,
+                        Err(report) => {
+                            self.error = Some(report.message);
+                            ModalResult::Open
+                        }
+                    },
+                    Err(msg) => {
+                        self.error = Some(msg);
+                        ModalResult::Open
+                    }
+                }
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                ModalResult::Open
+            }
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                ModalResult::Open
+            }
+            _ => ModalResult::Open,
+        }
+    }
+
+    pub fn draw(&self, state: &ConfigState, frame: &mut Frame, area: Rect) {
+        let popup = centered(area, 50, 6);
+        frame.render_widget(Clear, popup);
+        let mut text = match state.tree.node(self.key).as_option().map(|o| &o.ty) {
+            Some(ConfigType::Flags { values }) => {
+                let mut lines: Vec<String> = values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, flag)| {
+                        let checked = if self.flag_selected.get(i).copied().unwrap_or(false) {
+                            'x'
+                        } else {
+                            ' '
+                        };
+                        let cursor = if i == self.flag_cursor { '>' } else { ' ' };
+                        format!("{cursor}[{checked}] {flag}")
+                    })
+                    .collect();
+                // The mask over the declared flag order, the form the
+                // selection takes in generated headers and registers.
+                let selection: Vec<String> = values
+                    .iter()
+                    .zip(&self.flag_selected)
+                    .filter(|(_, &on)| on)
+                    .map(|(flag, _)| flag.clone())
+                    .collect();
+                lines.push(format!("mask: {:#b}", flags_mask(values, &selection)));
+                lines.join("\n")
+            }
+            Some(ConfigType::List) => {
+                let mut lines: Vec<String> = self
+                    .list_entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        let cursor = if i == self.list_cursor { '>' } else { ' ' };
+                        format!("{cursor} {entry}")
+                    })
+                    .collect();
+                match &self.list_input {
+                    Some((_, text)) => lines.push(format!("> {text}_")),
+                    None => lines.push("a: add  e: edit  d: delete".to_string()),
+                }
+                lines.join("\n")
+            }
+            _ => self.input.clone(),
+        };
+        if let Some(error) = &self.error {
+            text.push_str(&format!("\nerror: {error}"));
+        }
+        frame.render_widget(
+            Paragraph::new(text).block(
+                Block::default()
+                    .title("Edit value (Enter to apply, Esc to cancel)")
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::Cyan)),
+            ),
+            popup,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::MacroEngine;
+    use crate::testutil::{flags_option, list_option, tree_of};
+
+    /// Feeds `keys` into the editor, asserting the modal stays open.
+    fn type_keys(editor: &mut EditorModal, state: &mut ConfigState, keys: &[KeyCode]) {
+        for &code in keys {
+            assert_eq!(
+                editor.handle_key_event(state, KeyEvent::from(code)),
+                ModalResult::Open
+            );
+        }
+    }
+
+    #[test]
+    fn selecting_multiple_flags_builds_the_mask() {
+        let tree = tree_of(vec![flags_option(
+            "uart_features",
+            &["parity", "flow_control", "dma"],
+            &[],
+        )]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+        let key = crate::resolve::lookup(&state.tree, "uart_features").unwrap();
+
+        let mut editor = EditorModal::new(&state, key);
+        // Check "parity", skip "flow_control", check "dma", commit.
+        for code in [
+            KeyCode::Char(' '),
+            KeyCode::Down,
+            KeyCode::Down,
+            KeyCode::Char(' '),
+        ] {
+            assert_eq!(
+                editor.handle_key_event(&mut state, KeyEvent::from(code)),
+                ModalResult::Open
+            );
+        }
+        assert_eq!(
+            editor.handle_key_event(&mut state, KeyEvent::from(KeyCode::Enter)),
+            ModalResult::Close
+        );
+
+        let ConfigValue::List(selected) = state.values.get(&key).unwrap().clone() else {
+            panic!("expected a list value");
+        };
+        assert_eq!(selected, ["parity", "dma"]);
+
+        let values: Vec<String> = ["parity", "flow_control", "dma"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(flags_mask(&values, &selected), 0b101);
+    }
+
+    #[test]
+    fn list_entries_can_be_added_edited_and_deleted() {
+        let tree = tree_of(vec![list_option("modules", &["uart"])]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+        let key = crate::resolve::lookup(&state.tree, "modules").unwrap();
+        let mut editor = EditorModal::new(&state, key);
+
+        // Add "spi": the cursor follows the new entry.
+        type_keys(
+            &mut editor,
+            &mut state,
+            &[
+                KeyCode::Char('a'),
+                KeyCode::Char('s'),
+                KeyCode::Char('p'),
+                KeyCode::Char('i'),
+                KeyCode::Enter,
+            ],
+        );
+        assert_eq!(editor.list_entries, ["uart", "spi"]);
+
+        // Edit the selected entry in place.
+        type_keys(
+            &mut editor,
+            &mut state,
+            &[
+                KeyCode::Char('e'),
+                KeyCode::Backspace,
+                KeyCode::Backspace,
+                KeyCode::Backspace,
+                KeyCode::Char('i'),
+                KeyCode::Char('2'),
+                KeyCode::Char('c'),
+                KeyCode::Enter,
+            ],
+        );
+        assert_eq!(editor.list_entries, ["uart", "i2c"]);
+
+        // Delete it again and commit what is left.
+        type_keys(&mut editor, &mut state, &[KeyCode::Char('d')]);
+        assert_eq!(editor.list_entries, ["uart"]);
+        assert_eq!(
+            editor.handle_key_event(&mut state, KeyEvent::from(KeyCode::Enter)),
+            ModalResult::Close
+        );
+        assert_eq!(
+            state.values.get(&key),
+            Some(&ConfigValue::List(vec!["uart".to_string()]))
+        );
+    }
+
+    #[test]
+    fn duplicate_list_entry_is_rejected_inline() {
+        let tree = tree_of(vec![list_option("modules", &["uart"])]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+        let key = crate::resolve::lookup(&state.tree, "modules").unwrap();
+        let mut editor = EditorModal::new(&state, key);
+
+        type_keys(
+            &mut editor,
+            &mut state,
+            &[
+                KeyCode::Char('a'),
+                KeyCode::Char('u'),
+                KeyCode::Char('a'),
+                KeyCode::Char('r'),
+                KeyCode::Char('t'),
+                KeyCode::Backspace,
+                KeyCode::Backspace,
+                KeyCode::Backspace,
+                KeyCode::Char('a'),
+                KeyCode::Char('r'),
+                KeyCode::Char('t'),
+                KeyCode::Enter,
+            ],
+        );
+        // The entry was refused, the input stays open with the reason.
+        assert_eq!(editor.list_entries, ["uart"]);
+        assert!(editor.list_input.is_some());
+        assert!(editor.error.as_deref().unwrap().contains("already in the list"));
+
+        // Aborting the input leaves the list untouched; editing an entry to
+        // its own text is not a duplicate.
+        type_keys(&mut editor, &mut state, &[KeyCode::Esc]);
+        type_keys(&mut editor, &mut state, &[KeyCode::Char('e'), KeyCode::Enter]);
+        assert_eq!(editor.list_entries, ["uart"]);
+        assert!(editor.list_input.is_none());
+    }
+
+    #[test]
+    fn toggling_a_flag_off_removes_it() {
+        let tree = tree_of(vec![flags_option("features", &["a", "b"], &["a", "b"])]);
+        let mut state = ConfigState::new(tree, MacroEngine::new());
+        let key = crate::resolve::lookup(&state.tree, "features").unwrap();
+
+        let mut editor = EditorModal::new(&state, key);
+        editor.handle_key_event(&mut state, KeyEvent::from(KeyCode::Char(' ')));
+        editor.handle_key_event(&mut state, KeyEvent::from(KeyCode::Enter));
+
+        assert_eq!(
+            state.values.get(&key),
+            Some(&ConfigValue::List(vec!["b".to_string()]))
+        );
+    }
+}
@@ -0,0 +1,7 @@
+//! The interactive terminal UI.
+
+pub mod base;
+pub mod editor;
+pub mod modal;
+
+pub use base::BaseUI;
@@ -0,0 +1,173 @@
+//! Modal dialogs layered over the base UI.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+/// Outcome of feeding a key event to a modal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModalResult {
+    /// The modal consumed the key and stays open.
+    Open,
+    /// The modal is done; close it.
+    Close,
+    /// The modal is done and the application should exit.
+    Quit,
+}
+
+/// Asks the user to confirm quitting. Only shown when there are unsaved
+/// changes; a clean state quits without ceremony.
+#[derive(Debug, Default)]
+pub struct ExitConfirmationModal {
+    /// Number of options changed since the last save.
+    pub changed: usize,
+}
+
+impl ExitConfirmationModal {
+    pub fn handle_key_event(&mut self, event: KeyEvent) -> ModalResult {
+        match event.code {
+            KeyCode::Char('y') | KeyCode::Enter => ModalResult::Quit,
+            KeyCode::Char('n') | KeyCode::Esc => ModalResult::Close,
+            _ => ModalResult::Open,
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect) {
+        let popup = centered(area, 40, 5);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(format!(
+                "Discard {} unsaved change(s) and quit? (y/n)",
+                self.changed
+            ))
+            .block(
+                Block::default()
+                    .title("Confirm exit")
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::Yellow)),
+            ),
+            popup,
+        );
+    }
+}
+
+/// Outcome of feeding a key event to the goto prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GotoResult {
+    /// The prompt consumed the key and stays open.
+    Open,
+    /// The prompt was aborted.
+    Close,
+    /// A path was confirmed; the caller resolves it and navigates.
+    Go(String),
+}
+
+/// Prompts for a full config path (e.g. `.drivers.uart.baud`) to jump to.
+/// Resolution and navigation happen in the caller, which reports a failed
+/// resolution back through [`GotoModal::error`].
+#[derive(Debug, Default)]
+pub struct GotoModal {
+    pub input: String,
+    pub error: Option<String>,
+}
+
+impl GotoModal {
+    pub fn handle_key_event(&mut self, event: KeyEvent) -> GotoResult {
+        match event.code {
+            KeyCode::Esc => GotoResult::Close,
+            KeyCode::Enter => GotoResult::Go(self.input.trim().to_string()),
+            KeyCode::Backspace => {
+                self.input.pop();
+                GotoResult::Open
+            }
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                GotoResult::Open
+            }
+            _ => GotoResult::Open,
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect) {
+        let popup = centered(area, 50, 5);
+        frame.render_widget(Clear, popup);
+        let mut text = format!("{}_", self.input);
+        if let Some(error) = &self.error {
+            text.push_str(&format!("\nerror: {error}"));
+        }
+        frame.render_widget(
+            Paragraph::new(text).block(
+                Block::default()
+                    .title("Go to path (Enter to jump, Esc to cancel)")
+                    .borders(Borders::ALL)
+                    .style(Style::default().fg(Color::Cyan)),
+            ),
+            popup,
+        );
+    }
+}
+
+/// Shows the `[env]` table a save would write — the serialized current
+/// state, with disabled and `NoStore` options already excluded — so the
+/// output can be reviewed before touching the file. Read-only, scrollable.
+#[derive(Debug)]
+pub struct PreviewModal {
+    pub lines: Vec<String>,
+    scroll: usize,
+}
+
+impl PreviewModal {
+    pub fn new(content: &str) -> Self {
+        Self {
+            lines: content.lines().map(str::to_string).collect(),
+            scroll: 0,
+        }
+    }
+
+    pub fn handle_key_event(&mut self, event: KeyEvent) -> ModalResult {
+        match event.code {
+            KeyCode::Esc | KeyCode::Char('q') => ModalResult::Close,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.scroll = self.scroll.saturating_sub(1);
+                ModalResult::Open
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if self.scroll + 1 < self.lines.len() {
+                    self.scroll += 1;
+                }
+                ModalResult::Open
+            }
+            _ => ModalResult::Open,
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect) {
+        let popup = centered(area, 60, 15);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(self.lines.join("\n"))
+                .scroll((self.scroll as u16, 0))
+                .block(
+                    Block::default()
+                        .title("Save preview (Esc to close)")
+                        .borders(Borders::ALL)
+                        .style(Style::default().fg(Color::Cyan)),
+                ),
+            popup,
+        );
+    }
+}
+
+/// Centers a `width`x`height` rect inside `area`.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
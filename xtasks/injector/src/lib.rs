@@ -0,0 +1,254 @@
+//! Injection of symbol data into a reserved ELF section.
+//!
+//! The kernel image keeps an empty `.syms_area` section that the linker
+//! sizes but cannot fill; this tool copies the image's own `.symtab` and
+//! `.strtab` into it after linking, so runtime backtraces can resolve
+//! symbol names. The target section and the sources are overridable for
+//! alternate linker scripts (see the `--section`/`--from-*` flags).
+//!
+//! Only 32-bit little-endian ELF (the Cortex-M output format) is supported.
+
+/// The reserved section filled by default.
+pub const DEFAULT_TARGET_SECTION: &str = ".syms_area";
+
+/// The default payload sources.
+pub const SYMTAB_SECTION: &str = ".symtab";
+pub const STRTAB_SECTION: &str = ".strtab";
+
+/// One section of the image: its name and file-offset extent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Errors from parsing or injecting.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InjectError {
+    /// The input is not a 32-bit little-endian ELF.
+    NotAnElf,
+    /// The named section does not exist in the image.
+    MissingSection(String),
+    /// The payload does not fit the reserved section.
+    PayloadTooLarge { payload: usize, capacity: usize },
+}
+
+impl std::fmt::Display for InjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InjectError::NotAnElf => write!(f, "not a 32-bit little-endian ELF image"),
+            InjectError::MissingSection(name) => write!(f, "no section named '{name}'"),
+            InjectError::PayloadTooLarge { payload, capacity } => write!(
+                f,
+                "payload of {payload} bytes exceeds the reserved {capacity} bytes"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InjectError {}
+
+fn read_u16(elf: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(elf.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(elf: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(elf.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Parses the section headers of a 32-bit little-endian ELF.
+pub fn sections(elf: &[u8]) -> Result<Vec<Section>, InjectError> {
+    // Magic, ELFCLASS32, ELFDATA2LSB.
+    if elf.len() < 0x34 || &elf[..4] != b"\x7fELF" || elf[4] != 1 || elf[5] != 1 {
+        return Err(InjectError::NotAnElf);
+    }
+    let shoff = read_u32(elf, 0x20).ok_or(InjectError::NotAnElf)? as usize;
+    let shentsize = read_u16(elf, 0x2E).ok_or(InjectError::NotAnElf)? as usize;
+    let shnum = read_u16(elf, 0x30).ok_or(InjectError::NotAnElf)? as usize;
+    let shstrndx = read_u16(elf, 0x32).ok_or(InjectError::NotAnElf)? as usize;
+
+    let header = |index: usize| -> Option<(usize, usize, usize)> {
+        let base = shoff + index * shentsize;
+        Some((
+            read_u32(elf, base)? as usize,       // sh_name
+            read_u32(elf, base + 16)? as usize,  // sh_offset
+            read_u32(elf, base + 20)? as usize,  // sh_size
+        ))
+    };
+
+    // The section name string table.
+    let (_, strtab_off, strtab_size) = header(shstrndx).ok_or(InjectError::NotAnElf)?;
+    let names = elf
+        .get(strtab_off..strtab_off + strtab_size)
+        .ok_or(InjectError::NotAnElf)?;
+
+    let mut out = Vec::with_capacity(shnum);
+    for index in 0..shnum {
+        let (name_idx, offset, size) = header(index).ok_or(InjectError::NotAnElf)?;
+        let name = names
+            .get(name_idx..)
+            .and_then(|tail| tail.split(|&b| b == 0).next())
+            .and_then(|raw| std::str::from_utf8(raw).ok())
+            .unwrap_or_default()
+            .to_string();
+        out.push(Section { name, offset, size });
+    }
+    Ok(out)
+}
+
+/// Finds a section by name.
+pub fn find_section(elf: &[u8], name: &str) -> Result<Section, InjectError> {
+    sections(elf)?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| InjectError::MissingSection(name.to_string()))
+}
+
+/// Builds the payload from the selected source sections: per included
+/// source a little-endian length word followed by the raw bytes, in
+/// symtab-then-strtab order.
+pub fn build_payload(
+    elf: &[u8],
+    from_symtab: bool,
+    from_strtab: bool,
+) -> Result<Vec<u8>, InjectError> {
+    let mut payload = Vec::new();
+    for (include, name) in [(from_symtab, SYMTAB_SECTION), (from_strtab, STRTAB_SECTION)] {
+        if !include {
+            continue;
+        }
+        let section = find_section(elf, name)?;
+        let bytes = elf
+            .get(section.offset..section.offset + section.size)
+            .ok_or(InjectError::NotAnElf)?;
+        payload.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        payload.extend_from_slice(bytes);
+    }
+    Ok(payload)
+}
+
+/// Writes `payload` at the start of the named reserved section, in place.
+/// The payload must fit; the rest of the section is left untouched.
+pub fn inject(elf: &mut [u8], target: &str, payload: &[u8]) -> Result<(), InjectError> {
+    let section = find_section(elf, target)?;
+    if payload.len() > section.size {
+        return Err(InjectError::PayloadTooLarge {
+            payload: payload.len(),
+            capacity: section.size,
+        });
+    }
+    elf[section.offset..section.offset + payload.len()].copy_from_slice(payload);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal ELF32 with the given `(name, contents, capacity)`
+    /// sections; `capacity` pads a section beyond its contents (a reserved
+    /// area).
+    fn synthetic_elf(specs: &[(&str, &[u8], usize)]) -> Vec<u8> {
+        // Section name string table: NUL, each name NUL-terminated,
+        // ".shstrtab" last.
+        let mut shstrtab = vec![0u8];
+        let mut name_offsets = Vec::new();
+        for (name, _, _) in specs {
+            name_offsets.push(shstrtab.len());
+            shstrtab.extend_from_slice(name.as_bytes());
+            shstrtab.push(0);
+        }
+        let shstrtab_name = shstrtab.len();
+        shstrtab.extend_from_slice(b".shstrtab\0");
+
+        // Layout: ELF header, section contents, shstrtab, section headers.
+        let mut elf = vec![0u8; 0x34];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4] = 1; // ELFCLASS32
+        elf[5] = 1; // ELFDATA2LSB
+
+        let mut offsets = Vec::new();
+        for (_, contents, capacity) in specs {
+            offsets.push(elf.len());
+            elf.extend_from_slice(contents);
+            elf.resize(elf.len() + capacity.saturating_sub(contents.len()), 0);
+        }
+        let shstrtab_offset = elf.len();
+        elf.extend_from_slice(&shstrtab);
+
+        let shoff = elf.len();
+        let mut headers: Vec<u8> = Vec::new();
+        let mut push_header = |name: usize, offset: usize, size: usize| {
+            let mut header = [0u8; 40];
+            header[..4].copy_from_slice(&(name as u32).to_le_bytes());
+            header[16..20].copy_from_slice(&(offset as u32).to_le_bytes());
+            header[20..24].copy_from_slice(&(size as u32).to_le_bytes());
+            headers.extend_from_slice(&header);
+        };
+        for (i, (_, contents, capacity)) in specs.iter().enumerate() {
+            push_header(name_offsets[i], offsets[i], contents.len().max(*capacity));
+        }
+        push_header(shstrtab_name, shstrtab_offset, shstrtab.len());
+        elf.extend_from_slice(&headers);
+
+        elf[0x20..0x24].copy_from_slice(&(shoff as u32).to_le_bytes());
+        elf[0x2E..0x30].copy_from_slice(&40u16.to_le_bytes());
+        elf[0x30..0x32].copy_from_slice(&((specs.len() + 1) as u16).to_le_bytes());
+        elf[0x32..0x34].copy_from_slice(&(specs.len() as u16).to_le_bytes());
+        elf
+    }
+
+    fn image() -> Vec<u8> {
+        synthetic_elf(&[
+            (".symtab", &[0xAA; 8], 8),
+            (".strtab", b"main\0", 5),
+            (".syms_area", &[], 64),
+            (".alt_area", &[], 32),
+        ])
+    }
+
+    #[test]
+    fn bytes_land_at_the_default_sections_offset() {
+        let mut elf = image();
+        let payload = build_payload(&elf, true, true).unwrap();
+        inject(&mut elf, DEFAULT_TARGET_SECTION, &payload).unwrap();
+
+        let area = find_section(&elf, DEFAULT_TARGET_SECTION).unwrap();
+        let written = &elf[area.offset..area.offset + payload.len()];
+        // Length-prefixed symtab bytes, then length-prefixed strtab bytes.
+        assert_eq!(&written[..4], &8u32.to_le_bytes());
+        assert_eq!(&written[4..12], &[0xAA; 8]);
+        assert_eq!(&written[12..16], &5u32.to_le_bytes());
+        assert_eq!(&written[16..21], b"main\0");
+    }
+
+    #[test]
+    fn a_custom_section_name_is_honoured() {
+        let mut elf = image();
+        let payload = build_payload(&elf, false, true).unwrap();
+        inject(&mut elf, ".alt_area", &payload).unwrap();
+
+        let area = find_section(&elf, ".alt_area").unwrap();
+        assert_eq!(&elf[area.offset..area.offset + 4], &5u32.to_le_bytes());
+        assert_eq!(&elf[area.offset + 4..area.offset + 9], b"main\0");
+        // The default area stays untouched.
+        let default = find_section(&elf, DEFAULT_TARGET_SECTION).unwrap();
+        assert!(elf[default.offset..default.offset + default.size]
+            .iter()
+            .all(|&b| b == 0));
+    }
+
+    #[test]
+    fn an_oversized_payload_is_rejected() {
+        let mut elf = image();
+        let payload = vec![1u8; 128];
+        assert_eq!(
+            inject(&mut elf, DEFAULT_TARGET_SECTION, &payload),
+            Err(InjectError::PayloadTooLarge {
+                payload: 128,
+                capacity: 64,
+            })
+        );
+    }
+}
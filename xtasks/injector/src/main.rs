@@ -0,0 +1,42 @@
+//! CLI front-end for the symbol injector.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(name = "injector", about = "Inject symbol data into a reserved ELF section")]
+struct Cli {
+    /// The linked kernel image to patch in place.
+    image: PathBuf,
+
+    /// Name of the reserved section to fill.
+    #[arg(long, default_value = injector::DEFAULT_TARGET_SECTION)]
+    section: String,
+
+    /// Include only `.symtab` in the payload.
+    #[arg(long)]
+    from_symtab: bool,
+
+    /// Include only `.strtab` in the payload.
+    #[arg(long)]
+    from_strtab: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    // With neither source flag given, inject both (the historical default).
+    let both = !cli.from_symtab && !cli.from_strtab;
+
+    let mut elf = std::fs::read(&cli.image)?;
+    let payload = injector::build_payload(&elf, cli.from_symtab || both, cli.from_strtab || both)?;
+    injector::inject(&mut elf, &cli.section, &payload)?;
+    std::fs::write(&cli.image, &elf)?;
+    println!(
+        "injected {} bytes into {} of {}",
+        payload.len(),
+        cli.section,
+        cli.image.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! Packing of the kernel and applications into `osiris.img`.
+//!
+//! The image must be byte-identical for identical inputs (reproducible
+//! builds): inputs are processed in sorted order, padding is zeroed and no
+//! timestamps or other build-environment data are embedded.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use interface::{Args, ConsoleInfo, InitDescriptor, BOOT_INFO_VERSION};
+
+/// Image magic at offset 0.
+pub const IMAGE_MAGIC: &[u8; 4] = b"OSIR";
+
+/// Image format version.
+pub const IMAGE_VERSION: u32 = 1;
+
+/// Alignment of every embedded binary within the image.
+const BINARY_ALIGN: usize = 8;
+
+/// One input binary: its image-visible name and contents.
+#[derive(Debug, Clone)]
+pub struct Input {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Collects the binaries under `dir` as pack inputs, sorted by path so the
+/// resulting image does not depend on directory iteration order.
+pub fn collect_inputs(dir: &Path) -> io::Result<Vec<Input>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            Ok(Input {
+                name: path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                data: std::fs::read(&path)?,
+            })
+        })
+        .collect()
+}
+
+/// Builds the image from the given inputs. The first input is the init
+/// application referenced by the embedded [`Args`].
+///
+/// Layout: magic, image version, boot ABI version (what the bootloader must
+/// stamp into `BootInfo.version`), `Args`, `ConsoleInfo` (what the bootloader
+/// copies into `BootInfo.console`), entry count, then per input its name
+/// (length-prefixed), data offset and size, followed by the `BINARY_ALIGN`ed
+/// data blobs with zero padding.
+pub fn pack(inputs: &[Input], cmdline: &str, console: &ConsoleInfo) -> Result<Vec<u8>, PackError> {
+    let mut args = Args::empty();
+    args.set_cmdline(cmdline.as_bytes())
+        .map_err(|err| PackError::CmdlineTooLong(err.len))?;
+
+    // Directory size: per input, name length (4) + name + offset (4) + size (4).
+    let header_size =
+        4 + 4 + 4 + core::mem::size_of::<Args>() + core::mem::size_of::<ConsoleInfo>() + 4;
+    let dir_size: usize = inputs
+        .iter()
+        .map(|input| 4 + input.name.len() + 4 + 4)
+        .sum();
+
+    // Compute data offsets first so the directory can reference them.
+    let mut offsets = Vec::with_capacity(inputs.len());
+    let mut cursor = align_up(header_size + dir_size, BINARY_ALIGN);
+    for input in inputs {
+        offsets.push(cursor);
+        cursor = align_up(cursor + input.data.len(), BINARY_ALIGN);
+    }
+
+    if let Some((first, &offset)) = inputs.first().zip(offsets.first()) {
+        args.init = InitDescriptor {
+            offset: offset as u32,
+            size: first.data.len() as u32,
+            entry: 0,
+        };
+    }
+
+    let mut image = Vec::with_capacity(cursor);
+    image.extend_from_slice(IMAGE_MAGIC);
+    image.extend_from_slice(&IMAGE_VERSION.to_le_bytes());
+    image.extend_from_slice(&BOOT_INFO_VERSION.to_le_bytes());
+    image.extend_from_slice(args_bytes(&args));
+    image.extend_from_slice(console_bytes(console));
+    image.extend_from_slice(&(inputs.len() as u32).to_le_bytes());
+    for (input, &offset) in inputs.iter().zip(&offsets) {
+        image.extend_from_slice(&(input.name.len() as u32).to_le_bytes());
+        image.extend_from_slice(input.name.as_bytes());
+        image.extend_from_slice(&(offset as u32).to_le_bytes());
+        image.extend_from_slice(&(input.data.len() as u32).to_le_bytes());
+    }
+    for (input, &offset) in inputs.iter().zip(&offsets) {
+        // Zeroed padding keeps the image independent of allocator contents.
+        image.resize(offset, 0);
+        image.extend_from_slice(&input.data);
+    }
+    image.resize(cursor, 0);
+
+    Ok(image)
+}
+
+/// Errors from packing.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PackError {
+    CmdlineTooLong(usize),
+}
+
+impl std::fmt::Display for PackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackError::CmdlineTooLong(len) => {
+                write!(f, "command line too long: {len} bytes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+/// The raw bytes of `Args` as laid out in the image (`#[repr(C)]`).
+fn args_bytes(args: &Args) -> &[u8] {
+    // SAFETY: Args is repr(C), fully initialized and contains no pointers.
+    unsafe {
+        core::slice::from_raw_parts(
+            (args as *const Args).cast::<u8>(),
+            core::mem::size_of::<Args>(),
+        )
+    }
+}
+
+/// The raw bytes of `ConsoleInfo` as laid out in the image (`#[repr(C)]`).
+fn console_bytes(console: &ConsoleInfo) -> &[u8] {
+    // SAFETY: ConsoleInfo is repr(C), fully initialized and has no pointers.
+    unsafe {
+        core::slice::from_raw_parts(
+            (console as *const ConsoleInfo).cast::<u8>(),
+            core::mem::size_of::<ConsoleInfo>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use interface::ConsoleKind;
+
+    fn inputs() -> Vec<Input> {
+        vec![
+            Input {
+                name: "init".to_string(),
+                data: vec![0xAA; 100],
+            },
+            Input {
+                name: "svc_log".to_string(),
+                data: vec![0xBB; 33],
+            },
+        ]
+    }
+
+    #[test]
+    fn packing_identical_inputs_is_byte_identical() {
+        let a = pack(&inputs(), "console=lpuart1", &ConsoleInfo::empty()).unwrap();
+        let b = pack(&inputs(), "console=lpuart1", &ConsoleInfo::empty()).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn image_starts_with_magic_and_versions() {
+        let image = pack(&inputs(), "", &ConsoleInfo::empty()).unwrap();
+        assert_eq!(&image[..4], IMAGE_MAGIC);
+        assert_eq!(image[4..8], IMAGE_VERSION.to_le_bytes());
+        assert_eq!(image[8..12], BOOT_INFO_VERSION.to_le_bytes());
+    }
+
+    #[test]
+    fn console_info_round_trips_through_the_image() {
+        let console = ConsoleInfo::new(ConsoleKind::Uart, 0x4000_8000, 115_200);
+        let image = pack(&inputs(), "", &console).unwrap();
+
+        // Read the console block back the way a bootloader would: raw
+        // little-endian fields right after `Args`.
+        let offset = 12 + core::mem::size_of::<Args>();
+        let bytes = &image[offset..offset + core::mem::size_of::<ConsoleInfo>()];
+        let read_back = ConsoleInfo {
+            base_addr: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            kind: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            baud: u32::from_le_bytes(bytes[12..16].try_into().unwrap()),
+        };
+
+        // Copied into the boot info, the kernel-side accessor sees it.
+        let mut info = interface::BootInfo::empty();
+        info.console = read_back;
+        assert_eq!(info.console(), Some(console));
+    }
+
+    #[test]
+    fn binaries_are_aligned_with_zero_padding() {
+        let image = pack(&inputs(), "", &ConsoleInfo::empty()).unwrap();
+        // Image length is a multiple of the alignment, and the tail padding
+        // after the last 0xBB byte is zero.
+        assert_eq!(image.len() % 8, 0);
+        let last_data = image.iter().rposition(|&b| b == 0xBB).unwrap();
+        assert!(image[last_data + 1..].iter().all(|&b| b == 0));
+    }
+}
@@ -0,0 +1,53 @@
+//! CLI front-end for the packer.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(name = "pack", about = "Pack kernel and applications into osiris.img")]
+struct Cli {
+    /// Directory containing the binaries to pack (first in sorted order is
+    /// treated as the init application).
+    #[arg(long, default_value = "target/apps")]
+    apps: PathBuf,
+
+    /// Boot command line embedded into the image.
+    #[arg(long, default_value = "")]
+    cmdline: String,
+
+    /// Output image path.
+    #[arg(long, default_value = "osiris.img")]
+    out: PathBuf,
+
+    /// Base address of the UART console to describe in the image (e.g.
+    /// 0x40008000). Omitted means no console info.
+    #[arg(long, value_parser = parse_addr)]
+    console_uart: Option<u64>,
+
+    /// Baud rate for --console-uart.
+    #[arg(long, default_value_t = 115_200)]
+    baud: u32,
+}
+
+/// Parses an address, accepting the usual `0x` prefix.
+fn parse_addr(raw: &str) -> Result<u64, String> {
+    let parsed = match raw.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => raw.parse(),
+    };
+    parsed.map_err(|_| format!("'{raw}' is not a valid address"))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let inputs = pack::collect_inputs(&cli.apps)?;
+    let console = match cli.console_uart {
+        Some(base) => interface::ConsoleInfo::new(interface::ConsoleKind::Uart, base, cli.baud),
+        None => interface::ConsoleInfo::empty(),
+    };
+    let image = pack::pack(&inputs, &cli.cmdline, &console)?;
+    std::fs::write(&cli.out, &image)?;
+    println!("wrote {} ({} bytes)", cli.out.display(), image.len());
+    Ok(())
+}
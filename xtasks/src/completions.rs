@@ -0,0 +1,131 @@
+//! The `completions` subcommand: shell completion for the dynamic command
+//! set.
+//!
+//! Besides the built-in subcommands, `xtask` dispatches to the sibling tool
+//! crates under `xtasks/` (`config`, `injector`, ...), which are discovered
+//! at runtime — so clap's static completion generation can't know them. This
+//! emits a small hand-rolled script over the discovered names instead;
+//! regenerate it whenever a tool crate is added.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Subcommands built into the `xtask` binary itself.
+pub const BUILTIN_SUBCOMMANDS: &[&str] = &["flash", "completions"];
+
+/// The shells a completion script can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Discovers the available subcommand names: the built-ins plus every sibling
+/// tool crate under `xtasks_dir` (a directory with a `Cargo.toml`), sorted
+/// for stable output.
+pub fn find_subcommands(xtasks_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_SUBCOMMANDS
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    if let Ok(entries) = std::fs::read_dir(xtasks_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.join("Cargo.toml").is_file() {
+                continue;
+            }
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Renders the completion script for `shell` over the discovered subcommand
+/// names.
+pub fn generate(shell: Shell, subcommands: &[String]) -> String {
+    let words = subcommands.join(" ");
+    let mut out = String::new();
+    match shell {
+        Shell::Bash => {
+            let _ = writeln!(out, "# bash completion for xtask (generated)");
+            let _ = writeln!(out, "_xtask() {{");
+            let _ = writeln!(out, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+            let _ = writeln!(out, "    if [ \"$COMP_CWORD\" -eq 1 ]; then");
+            let _ = writeln!(
+                out,
+                "        COMPREPLY=( $(compgen -W \"{words}\" -- \"$cur\") )"
+            );
+            let _ = writeln!(out, "    fi");
+            let _ = writeln!(out, "}}");
+            let _ = writeln!(out, "complete -F _xtask xtask");
+        }
+        Shell::Zsh => {
+            let _ = writeln!(out, "#compdef xtask");
+            let _ = writeln!(out, "# zsh completion for xtask (generated)");
+            let _ = writeln!(out, "_xtask() {{");
+            let _ = writeln!(out, "    _arguments '1:command:({words})'");
+            let _ = writeln!(out, "}}");
+            let _ = writeln!(out, "_xtask \"$@\"");
+        }
+        Shell::Fish => {
+            let _ = writeln!(out, "# fish completion for xtask (generated)");
+            for name in subcommands {
+                let _ = writeln!(
+                    out,
+                    "complete -c xtask -n __fish_use_subcommand -a {name}"
+                );
+            }
+        }
+    }
+    out
+}
+
+/// Prints the completion script for `shell` to stdout, discovering the
+/// subcommands relative to the workspace layout.
+pub fn run(shell: Shell) -> Result<(), Box<dyn std::error::Error>> {
+    let subcommands = find_subcommands(Path::new("xtasks"));
+    print!("{}", generate(shell, &subcommands));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake xtasks directory with two tool crates and one stray directory
+    /// that must not become a subcommand.
+    fn fake_xtasks_dir() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("xtask-completions-{}", std::process::id()));
+        for tool in ["config", "injector"] {
+            std::fs::create_dir_all(dir.join(tool)).unwrap();
+            std::fs::write(dir.join(tool).join("Cargo.toml"), "[package]\n").unwrap();
+        }
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn every_discovered_subcommand_appears_in_each_script() {
+        let dir = fake_xtasks_dir();
+        let subcommands = find_subcommands(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Built-ins and tool crates, sorted; `src` has no Cargo.toml.
+        assert_eq!(subcommands, ["completions", "config", "flash", "injector"]);
+
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = generate(shell, &subcommands);
+            for name in &subcommands {
+                assert!(
+                    script.contains(name.as_str()),
+                    "{shell:?} script misses '{name}'"
+                );
+            }
+        }
+    }
+}
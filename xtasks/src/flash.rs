@@ -0,0 +1,157 @@
+//! The `flash` subcommand: writes an image to a board through `st-flash`.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Start of flash on the stm32l4xx family.
+pub const DEFAULT_FLASH_ADDR: u32 = 0x0800_0000;
+
+/// Parses a flash address, accepting the usual `0x` prefix.
+pub fn parse_addr(raw: &str) -> Result<u32, String> {
+    let parsed = match raw.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => raw.parse(),
+    };
+    parsed.map_err(|_| format!("'{raw}' is not a valid flash address"))
+}
+
+/// The triple of the machine running the xtask, from `rustc -vV`.
+pub fn host_triple() -> Option<String> {
+    let output = Command::new("rustc").arg("-vV").output().ok()?;
+    String::from_utf8(output.stdout)
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("host: ").map(str::to_string))
+}
+
+/// Resolves the image to flash: an explicit path wins, otherwise the most
+/// recently modified `osiris.img` under the target directory. The host
+/// triple's artifact directory is skipped — only cross-built images can be
+/// flashed, and a stale host-side build must not shadow them.
+pub fn resolve_image(
+    target_dir: &Path,
+    explicit: Option<PathBuf>,
+    host_triple: Option<&str>,
+) -> Result<PathBuf, String> {
+    if let Some(path) = explicit {
+        return if path.is_file() {
+            Ok(path)
+        } else {
+            Err(format!("image '{}' does not exist", path.display()))
+        };
+    }
+
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    let mut pending = vec![target_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                let is_host_dir = host_triple
+                    .is_some_and(|host| path.file_name().is_some_and(|n| n == host));
+                if !is_host_dir {
+                    pending.push(path);
+                }
+            } else if path.file_name().is_some_and(|n| n == "osiris.img") {
+                let modified = entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+                if newest.as_ref().is_none_or(|(t, _)| modified > *t) {
+                    newest = Some((modified, path));
+                }
+            }
+        }
+    }
+    newest
+        .map(|(_, path)| path)
+        .ok_or_else(|| "no osiris.img found under target/; build and pack first".to_string())
+}
+
+/// Assembles the `st-flash` argument list. Split out so tests can check the
+/// command line without an ST-Link attached.
+pub fn flash_args(image: &Path, serial: Option<&str>, addr: u32) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(serial) = serial {
+        args.push("--serial".to_string());
+        args.push(serial.to_string());
+    }
+    args.push("write".to_string());
+    args.push(image.display().to_string());
+    args.push(format!("{addr:#x}"));
+    args
+}
+
+/// Runs `st-flash` against the resolved image.
+pub fn run(
+    image: Option<PathBuf>,
+    serial: Option<&str>,
+    addr: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let image = resolve_image(Path::new("target"), image, host_triple().as_deref())?;
+    let args = flash_args(&image, serial, addr);
+    println!("st-flash {}", args.join(" "));
+
+    let status = Command::new("st-flash")
+        .args(&args)
+        .status()
+        .map_err(|err| format!("failed to run st-flash (is it installed?): {err}"))?;
+    if !status.success() {
+        return Err(format!("st-flash failed with {status}").into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flash_command_line_with_serial() {
+        let args = flash_args(Path::new("osiris.img"), Some("0667FF49"), DEFAULT_FLASH_ADDR);
+        assert_eq!(
+            args,
+            ["--serial", "0667FF49", "write", "osiris.img", "0x8000000"]
+        );
+    }
+
+    #[test]
+    fn flash_command_line_without_serial() {
+        let args = flash_args(Path::new("target/osiris.img"), None, 0x0800_4000);
+        assert_eq!(args, ["write", "target/osiris.img", "0x8004000"]);
+    }
+
+    #[test]
+    fn resolution_skips_the_host_triples_artifacts() {
+        let root = std::env::temp_dir().join(format!("osiris-flash-{}", std::process::id()));
+        let host = "x86_64-unknown-linux-gnu";
+        let host_dir = root.join(host).join("debug");
+        let cross_dir = root.join("thumbv7em-none-eabihf/release");
+        std::fs::create_dir_all(&host_dir).unwrap();
+        std::fs::create_dir_all(&cross_dir).unwrap();
+
+        // The host-side image is newer, but only the cross-built one counts.
+        std::fs::write(cross_dir.join("osiris.img"), b"cross").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(host_dir.join("osiris.img"), b"host").unwrap();
+
+        let resolved = resolve_image(&root, None, Some(host)).unwrap();
+        assert_eq!(resolved, cross_dir.join("osiris.img"));
+
+        // With nothing but the host build present, resolution fails loudly.
+        std::fs::remove_file(cross_dir.join("osiris.img")).unwrap();
+        assert!(resolve_image(&root, None, Some(host)).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn addr_parses_hex_and_decimal() {
+        assert_eq!(parse_addr("0x8000000"), Ok(0x0800_0000));
+        assert_eq!(parse_addr("1024"), Ok(1024));
+        assert!(parse_addr("flash").is_err());
+    }
+}
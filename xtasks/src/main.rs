@@ -0,0 +1,52 @@
+//! The xtask runner: developer commands around building and deploying Osiris.
+
+mod completions;
+mod flash;
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(name = "xtask", about = "Osiris developer tasks")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Flash an image to an attached board via st-flash.
+    Flash {
+        /// Image to flash; defaults to the most recently built osiris.img.
+        #[arg(long)]
+        image: Option<PathBuf>,
+
+        /// Serial of the ST-Link probe, for multi-board setups.
+        #[arg(long)]
+        serial: Option<String>,
+
+        /// Flash base address.
+        #[arg(long, default_value_t = flash::DEFAULT_FLASH_ADDR, value_parser = flash::parse_addr)]
+        addr: u32,
+    },
+
+    /// Emit a shell completion script over the discovered subcommands.
+    /// Regenerate after adding a tool crate under xtasks/.
+    Completions {
+        /// Shell to generate the script for.
+        shell: completions::Shell,
+    },
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Flash {
+            image,
+            serial,
+            addr,
+        } => flash::run(image, serial.as_deref(), addr),
+        Command::Completions { shell } => completions::run(shell),
+    }
+}